@@ -4,10 +4,34 @@
 use rusqlite::{Connection, OptionalExtension};
 use serde_json::Value;
 use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
 
+use super::status_snapshot::STATUS_SNAPSHOT_KEY;
 use crate::constants::database;
 use crate::path_utils::AppPaths;
 
+/// 备份文件读改写锁
+///
+/// 智能备份、状态快照、备注与切换时间都会读出整个备份文件再写回，
+/// 持有此锁可避免并发写入互相覆盖
+static BACKUP_FILE_LOCK: Mutex<()> = Mutex::new(());
+
+/// 获取备份文件读改写锁
+pub fn lock_backup_files() -> MutexGuard<'static, ()> {
+    BACKUP_FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 原子写入备份文件：先写同目录下的临时文件再重命名，崩溃时不会留下截断的备份
+pub fn write_backup_file(path: &Path, content: &str) -> Result<(), String> {
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, content).map_err(|e| format!("写入临时文件失败: {}", e))?;
+    fs::rename(&tmp, path).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        format!("替换备份文件失败: {}", e)
+    })
+}
+
 /// 智能备份 Antigravity 账户（终极版 - 保存完整 Marker）
 ///
 /// 备份策略：
@@ -26,6 +50,7 @@ pub fn smart_backup_antigravity_account(email: &str) -> Result<(String, bool), S
 
     let config_dir = AppPaths::backup_dir().ok_or("无法获取备份目录")?;
     fs::create_dir_all(&config_dir).map_err(|e| e.to_string())?;
+    let _guard = lock_backup_files();
 
     // 简单的覆盖逻辑：每个邮箱只保留一个备份
     let backup_name = email.to_string();
@@ -128,10 +153,24 @@ pub fn smart_backup_antigravity_account(email: &str) -> Result<(String, bool), S
         Value::String(chrono::Local::now().to_rfc3339()),
     );
 
-    // 4. 写入备份文件
+    // 3.5. 覆盖时保留用户维护的字段（备注、最后切换时间）和最近的状态快照
     let backup_file = config_dir.join(format!("{}.json", backup_name));
+    if is_overwrite {
+        if let Some(existing) = fs::read_to_string(&backup_file)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        {
+            for field in ["remark", "last_switched", STATUS_SNAPSHOT_KEY] {
+                if let Some(v) = existing.get(field) {
+                    data_map.insert(field.to_string(), v.clone());
+                }
+            }
+        }
+    }
+
+    // 4. 写入备份文件
     let file_content = serde_json::to_string_pretty(&data_map).map_err(|e| e.to_string())?;
-    fs::write(&backup_file, file_content).map_err(|e| e.to_string())?;
+    write_backup_file(&backup_file, &file_content)?;
 
     let action = if is_overwrite { "覆盖" } else { "创建" };
     tracing::info!(target: "backup::database", action = %action, file = %backup_file.display(), "备份成功");
@@ -3,3 +3,5 @@ pub mod cleanup;
 pub mod path_config;
 pub mod restore;
 pub mod starter;
+pub mod status_snapshot;
+pub mod value_decoder;
@@ -39,6 +39,7 @@ pub fn save_custom_executable_path(path: String) -> Result<(), String> {
 
     config.custom_executable_path = Some(path);
     write_config(&config_file, &config)?;
+    crate::platform::snapshot::invalidate_installations();
 
   tracing::info!("✅ 已保存自定义 Antigravity 可执行文件路径");
     Ok(())
@@ -82,6 +83,7 @@ pub fn clear_custom_path() -> Result<(), String> {
 
     if config_file.exists() {
         fs::remove_file(&config_file).map_err(|e| format!("删除配置文件失败: {}", e))?;
+        crate::platform::snapshot::invalidate_installations();
       tracing::info!("✅ 已清除自定义 Antigravity 路径");
     }
 
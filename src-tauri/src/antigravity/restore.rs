@@ -281,6 +281,7 @@ pub fn update_backup_last_switched(backup_file_path: &PathBuf) -> Result<(), Str
     if !backup_file_path.exists() {
         return Err(format!("备份文件不存在: {}", backup_file_path.display()));
     }
+    let _guard = super::backup::lock_backup_files();
 
     // 读取备份文件
     let content = fs::read_to_string(backup_file_path)
@@ -297,8 +298,7 @@ pub fn update_backup_last_switched(backup_file_path: &PathBuf) -> Result<(), Str
     let updated_content = serde_json::to_string_pretty(&backup_data)
         .map_err(|e| format!("序列化备份文件失败: {}", e))?;
     
-    fs::write(backup_file_path, updated_content)
-        .map_err(|e| format!("写入备份文件失败: {}", e))?;
+    super::backup::write_backup_file(backup_file_path, &updated_content)?;
     
     tracing::debug!(target: "restore::update_timestamp", last_switched = %now, "已更新 last_switched 时间戳");
     
@@ -231,8 +231,10 @@ fn try_start_from_path(path: &PathBuf) -> Result<String, String> {
             use std::os::windows::process::CommandExt;
             const CREATE_NO_WINDOW: u32 = 0x08000000;
             
-            Command::new(path)
+            // 通过 start 启动，Antigravity 不会成为本程序的子进程
+            Command::new("cmd")
                 .creation_flags(CREATE_NO_WINDOW)  // 关键：防止创建控制台窗口
+                .raw_arg(format!("/C start \"\" \"{}\"", path.display()))
                 .stdout(Stdio::null())
                 .stderr(Stdio::null())
                 .stdin(Stdio::null())  // 也重定向 stdin
@@ -243,18 +245,44 @@ fn try_start_from_path(path: &PathBuf) -> Result<String, String> {
         // Linux：重定向输出到 null 设备
         #[cfg(target_os = "linux")]
         {
-            Command::new(path)
-                .stdout(Stdio::null())
-                .stderr(Stdio::null())
-                .stdin(Stdio::null())
-                .spawn()
-                .map_err(|e| format!("启动失败: {}", e))?;
+            spawn_detached(
+                Command::new(path)
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .stdin(Stdio::null()),
+            )
+            .map_err(|e| format!("启动失败: {}", e))?;
         }
 
         Ok("Antigravity 已启动".to_string())
     }
 }
 
+/// 以脱离本程序的方式启动进程
+///
+/// 子进程再 fork 一次后立即退出，实际执行的进程由 init 接管，
+/// 这样 Antigravity 不会成为本程序的子进程（识别 Antigravity 进程时会排除本程序的子孙进程）
+#[cfg(unix)]
+fn spawn_detached(command: &mut Command) -> std::io::Result<()> {
+    use std::os::unix::process::CommandExt;
+
+    // SAFETY: 闭包只调用 fork、setsid、_exit 这些 async-signal-safe 的函数
+    unsafe {
+        command.pre_exec(|| match libc::fork() {
+            -1 => Err(std::io::Error::last_os_error()),
+            0 => {
+                libc::setsid();
+                Ok(())
+            }
+            _ => libc::_exit(0),
+        });
+    }
+
+    // 回收中间进程；exec 失败时 spawn 会返回错误
+    command.spawn()?.wait()?;
+    Ok(())
+}
+
 /// 尝试从系统命令启动应用程序（静默启动）
 fn try_start_from_commands(commands: Vec<&str>) -> Result<String, String> {
     let mut errors = Vec::new();
@@ -283,12 +311,12 @@ fn try_start_from_commands(commands: Vec<&str>) -> Result<String, String> {
         
         #[cfg(not(target_os = "windows"))]
         {
-            match Command::new(cmd)
-                .stdout(Stdio::null())
-                .stderr(Stdio::null())
-                .stdin(Stdio::null())
-                .spawn()
-            {
+            match spawn_detached(
+                Command::new(cmd)
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .stdin(Stdio::null()),
+            ) {
                 Ok(_) => {
                     return Ok("Antigravity 已启动".to_string());
                 }
@@ -0,0 +1,209 @@
+// 账户用户状态快照
+// 每次获取到 GetUserStatus 时，把套餐、积分和各模型剩余配额的摘要写入对应账户的备份文件，
+// 选择要切换的账户时即可看到它最近一次的状态
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+
+use super::backup::{lock_backup_files, write_backup_file};
+use crate::language_server::types::{GetUserStatusResponse, PlanInfo};
+use crate::path_utils::AppPaths;
+
+/// 备份文件中保存快照的字段名
+pub const STATUS_SNAPSHOT_KEY: &str = "status_snapshot";
+
+/// 单个模型的配额摘要
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelStatusSummary {
+    pub model: String,
+    pub label: String,
+    /// 剩余比例（0.0 ~ 1.0），没有配额信息时为 `None`
+    pub remaining_fraction: Option<f64>,
+    pub reset_time: Option<String>,
+}
+
+/// 用户状态快照（保存在备份文件中）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountStatusSnapshot {
+    /// 采集时间（毫秒时间戳）
+    pub captured_at: i64,
+    pub plan_name: String,
+    pub teams_tier: String,
+    /// 套餐中的布尔开关（如 `browserEnabled`）
+    #[serde(default)]
+    pub plan_flags: BTreeMap<String, bool>,
+    pub available_prompt_credits: f64,
+    pub available_flow_credits: f64,
+    pub monthly_prompt_credits: f64,
+    pub monthly_flow_credits: f64,
+    #[serde(default)]
+    pub models: Vec<ModelStatusSummary>,
+}
+
+/// 返回给前端的快照及其时效
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountStatusSummary {
+    #[serde(flatten)]
+    pub snapshot: AccountStatusSnapshot,
+    /// 距采集时的秒数
+    pub age_secs: i64,
+}
+
+impl AccountStatusSnapshot {
+    /// 从 GetUserStatus 响应生成快照，返回 `(邮箱, 快照)`
+    pub fn from_response(status: &GetUserStatusResponse, captured_at: i64) -> Option<(String, Self)> {
+        let user_status = status.user_status.as_ref()?;
+        if user_status.email.is_empty() {
+            return None;
+        }
+
+        let plan = user_status.plan_status.as_ref();
+        let plan_info = plan.and_then(|p| p.plan_info.as_ref());
+
+        let models = user_status
+            .cascade_model_config_data
+            .iter()
+            .flat_map(|data| data.client_model_configs.iter())
+            .map(|config| ModelStatusSummary {
+                model: config
+                    .model_or_alias
+                    .as_ref()
+                    .map(|m| m.model.clone())
+                    .filter(|m| !m.is_empty())
+                    .unwrap_or_else(|| config.label.clone()),
+                label: config.label.clone(),
+                remaining_fraction: config.quota_info.as_ref().map(|q| q.remaining_fraction),
+                reset_time: config.quota_info.as_ref().and_then(|q| q.reset_time.clone()),
+            })
+            .collect();
+
+        let snapshot = Self {
+            captured_at,
+            plan_name: plan_info.map(|p| p.plan_name.clone()).unwrap_or_default(),
+            teams_tier: plan_info.map(|p| p.teams_tier.clone()).unwrap_or_default(),
+            plan_flags: plan_info.map(plan_flags).unwrap_or_default(),
+            available_prompt_credits: plan.map_or(0.0, |p| p.available_prompt_credits),
+            available_flow_credits: plan.map_or(0.0, |p| p.available_flow_credits),
+            monthly_prompt_credits: plan_info.map_or(0.0, |p| p.monthly_prompt_credits),
+            monthly_flow_credits: plan_info.map_or(0.0, |p| p.monthly_flow_credits),
+            models,
+        };
+
+        Some((user_status.email.clone(), snapshot))
+    }
+
+    /// 除采集时间外内容是否相同
+    pub fn same_status(&self, other: &Self) -> bool {
+        Self { captured_at: other.captured_at, ..self.clone() } == *other
+    }
+
+    /// 读取备份数据中的快照并计算时效
+    pub fn summary_from_backup(backup_data: &Value, now: i64) -> Option<AccountStatusSummary> {
+        let snapshot: Self = serde_json::from_value(backup_data.get(STATUS_SNAPSHOT_KEY)?.clone()).ok()?;
+        let age_secs = ((now - snapshot.captured_at) / 1000).max(0);
+        Some(AccountStatusSummary { snapshot, age_secs })
+    }
+}
+
+/// 套餐信息中的布尔开关（按 JSON 字段名）
+fn plan_flags(plan_info: &PlanInfo) -> BTreeMap<String, bool> {
+    match serde_json::to_value(plan_info) {
+        Ok(Value::Object(map)) => map
+            .into_iter()
+            .filter_map(|(key, value)| value.as_bool().map(|flag| (key, flag)))
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+/// 内容未变时重新写入快照（只为刷新采集时间）的最小间隔
+const UNCHANGED_REFRESH_MS: i64 = 10 * 60 * 1000;
+
+/// 把 GetUserStatus 响应的摘要写入对应账户的备份文件
+///
+/// 只更新已有备份，返回是否写入。配额采样等会频繁调用，摘要未变化时跳过写入，
+/// 只每隔 `UNCHANGED_REFRESH_MS` 刷新一次采集时间。会阻塞（文件读写），异步上下文中请放到
+/// `spawn_blocking` 中执行
+pub fn record_user_status(status: &GetUserStatusResponse) -> Result<bool, String> {
+    let Some((email, snapshot)) =
+        AccountStatusSnapshot::from_response(status, chrono::Local::now().timestamp_millis())
+    else {
+        return Ok(false);
+    };
+
+    let backup_file = AppPaths::backup_dir()
+        .ok_or("无法获取备份目录")?
+        .join(format!("{}.json", email));
+
+    // 与智能备份等读改写操作互斥，避免互相覆盖
+    let _guard = lock_backup_files();
+    if !backup_file.exists() {
+        tracing::debug!(target: "backup::status_snapshot", email = %email, "账户没有备份，跳过状态快照");
+        return Ok(false);
+    }
+
+    let content = fs::read_to_string(&backup_file).map_err(|e| format!("读取备份文件失败: {}", e))?;
+    let mut backup_data: Value =
+        serde_json::from_str(&content).map_err(|e| format!("解析备份文件失败: {}", e))?;
+
+    let previous: Option<AccountStatusSnapshot> = backup_data
+        .get(STATUS_SNAPSHOT_KEY)
+        .and_then(|v| serde_json::from_value(v.clone()).ok());
+    if previous.is_some_and(|p| {
+        snapshot.captured_at - p.captured_at < UNCHANGED_REFRESH_MS && p.same_status(&snapshot)
+    }) {
+        return Ok(false);
+    }
+
+    backup_data[STATUS_SNAPSHOT_KEY] =
+        serde_json::to_value(&snapshot).map_err(|e| format!("序列化状态快照失败: {}", e))?;
+
+    let updated_content = serde_json::to_string_pretty(&backup_data)
+        .map_err(|e| format!("序列化备份文件失败: {}", e))?;
+    write_backup_file(&backup_file, &updated_content)?;
+
+    tracing::debug!(
+        target: "backup::status_snapshot",
+        email = %email,
+        models = snapshot.models.len(),
+        "已更新账户状态快照"
+    );
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language_server::test_support::sample_user_status;
+
+    #[test]
+    fn summarizes_user_status_for_account_backup() {
+        let response: GetUserStatusResponse =
+            serde_json::from_value(sample_user_status("tester@example.com")).unwrap();
+
+        let (email, snapshot) = AccountStatusSnapshot::from_response(&response, 1_000_000).unwrap();
+
+        assert_eq!(email, "tester@example.com");
+        assert_eq!(snapshot.plan_name, "Pro");
+        assert_eq!(snapshot.teams_tier, "TEAMS_TIER_PRO");
+        assert_eq!(snapshot.available_prompt_credits, 480.0);
+        assert_eq!(snapshot.monthly_flow_credits, 1000.0);
+        assert_eq!(snapshot.models.len(), 2);
+        assert_eq!(snapshot.models[0].model, "MODEL_PLACEHOLDER_M7");
+        assert_eq!(snapshot.models[0].remaining_fraction, Some(0.75));
+        // 剩余比例为 0 时服务端省略该字段
+        assert_eq!(snapshot.models[1].remaining_fraction, Some(0.0));
+
+        let backup = serde_json::json!({
+            "account_email": email,
+            "status_snapshot": serde_json::to_value(&snapshot).unwrap(),
+        });
+        let summary = AccountStatusSnapshot::summary_from_backup(&backup, 1_090_000).unwrap();
+        assert_eq!(summary.age_secs, 90);
+        assert_eq!(summary.snapshot.models.len(), 2);
+
+        assert!(AccountStatusSnapshot::summary_from_backup(&serde_json::json!({}), 0).is_none());
+    }
+}
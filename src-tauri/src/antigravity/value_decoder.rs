@@ -0,0 +1,452 @@
+//! 数据库值解码模块
+//!
+//! `state.vscdb` 中的部分字段（如 allUserSettings、agentManagerInitState）是
+//! Base64 包裹的 protobuf 二进制，直接比较字符串无法看出差异。
+//! 这里提供可插拔的解码器，按字段 key 注册，把原始值渲染成 JSON 树，
+//! 并在此基础上计算字段级差异。
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::constants::database;
+
+/// protobuf 嵌套解析的最大深度，防止恶意/异常数据导致深递归
+const MAX_PROTOBUF_DEPTH: usize = 16;
+
+/// 单个字段差异的最大数量，避免超大对象产生海量差异
+const MAX_FIELD_CHANGES: usize = 200;
+
+/// 值解码器
+pub trait ValueDecoder: Send + Sync {
+    /// 解码器名称（用于展示与调试）
+    fn name(&self) -> &'static str;
+
+    /// 将原始字符串解码为 JSON 树
+    fn decode(&self, raw: &str) -> Result<Value, String>;
+}
+
+/// JSON 解码器：值本身是 JSON 字符串
+pub struct JsonDecoder;
+
+impl ValueDecoder for JsonDecoder {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn decode(&self, raw: &str) -> Result<Value, String> {
+        serde_json::from_str(raw).map_err(|e| format!("JSON 解析失败: {}", e))
+    }
+}
+
+/// Base64 解码器：解码后若为 UTF-8 文本则返回文本，否则返回十六进制
+pub struct Base64Decoder;
+
+impl ValueDecoder for Base64Decoder {
+    fn name(&self) -> &'static str {
+        "base64"
+    }
+
+    fn decode(&self, raw: &str) -> Result<Value, String> {
+        let bytes = decode_base64(raw)?;
+        Ok(match String::from_utf8(bytes.clone()) {
+            Ok(text) => json!({ "length": bytes.len(), "text": text }),
+            Err(_) => json!({ "length": bytes.len(), "hex": to_hex(&bytes) }),
+        })
+    }
+}
+
+/// protobuf 线格式解码器（无 schema）
+///
+/// 值先按 Base64 解码，再按 wire format 逐字段解析；
+/// 长度分隔字段会依次尝试嵌套消息、UTF-8 字符串，最后回退为十六进制。
+pub struct ProtobufWireDecoder;
+
+impl ValueDecoder for ProtobufWireDecoder {
+    fn name(&self) -> &'static str {
+        "protobuf-wire"
+    }
+
+    fn decode(&self, raw: &str) -> Result<Value, String> {
+        let bytes = decode_base64(raw)?;
+        parse_protobuf_message(&bytes, 0)
+            .ok_or_else(|| "不是有效的 protobuf 线格式数据".to_string())
+    }
+}
+
+/// 解码结果
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedValue {
+    pub key: String,
+    /// 使用的解码器名称，`None` 表示没有可用解码器，`tree` 为原始字符串
+    pub decoder: Option<String>,
+    pub tree: Value,
+    /// 所有已注册解码器都失败时的错误信息
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+/// 字段级差异
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldChange {
+    /// JSON Pointer 风格的路径，如 `/3/0/value`
+    pub path: String,
+    /// added / removed / changed
+    pub change_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_value: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_value: Option<Value>,
+}
+
+/// 解码器注册表：key -> 按顺序尝试的解码器列表
+pub struct DecoderRegistry {
+    decoders: RwLock<HashMap<String, Vec<Arc<dyn ValueDecoder>>>>,
+}
+
+impl DecoderRegistry {
+    /// 创建空的注册表
+    pub fn new() -> Self {
+        Self {
+            decoders: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 创建带默认注册项的注册表
+    pub fn with_defaults() -> Self {
+        let registry = Self::new();
+        let json: Arc<dyn ValueDecoder> = Arc::new(JsonDecoder);
+        let protobuf: Arc<dyn ValueDecoder> = Arc::new(ProtobufWireDecoder);
+        let base64: Arc<dyn ValueDecoder> = Arc::new(Base64Decoder);
+
+        registry.register(database::AUTH_STATUS, json.clone());
+        registry.register(database::ONBOARDING, json.clone());
+        registry.register(database::COMMAND_CONFIGS, json.clone());
+        registry.register(database::CHAT_SESSION, json.clone());
+        registry.register(database::TARGET_STORAGE_MARKER, json.clone());
+
+        registry.register(database::USER_SETTINGS, protobuf.clone());
+        registry.register(database::USER_SETTINGS, base64.clone());
+        registry.register(database::AGENT_STATE, protobuf.clone());
+        registry.register(database::AGENT_STATE, base64.clone());
+        registry.register(database::GOOGLE_DATA, json);
+        registry.register(database::GOOGLE_DATA, protobuf);
+        registry.register(database::GOOGLE_DATA, base64);
+
+        registry
+    }
+
+    /// 为指定 key 追加解码器（按注册顺序尝试）
+    pub fn register(&self, key: &str, decoder: Arc<dyn ValueDecoder>) {
+        self.decoders
+            .write()
+            .unwrap()
+            .entry(key.to_string())
+            .or_default()
+            .push(decoder);
+    }
+
+    /// 是否为指定 key 注册了解码器
+    pub fn has_decoder(&self, key: &str) -> bool {
+        self.decoders.read().unwrap().contains_key(key)
+    }
+
+    /// 解码指定 key 的值
+    pub fn decode(&self, key: &str, raw: &str) -> DecodedValue {
+        let decoders = self
+            .decoders
+            .read()
+            .unwrap()
+            .get(key)
+            .cloned()
+            .unwrap_or_default();
+
+        let mut errors = Vec::new();
+        for decoder in decoders {
+            match decoder.decode(raw) {
+                Ok(tree) => {
+                    return DecodedValue {
+                        key: key.to_string(),
+                        decoder: Some(decoder.name().to_string()),
+                        tree,
+                        errors,
+                    };
+                }
+                Err(e) => errors.push(format!("{}: {}", decoder.name(), e)),
+            }
+        }
+
+        DecodedValue {
+            key: key.to_string(),
+            decoder: None,
+            tree: Value::String(raw.to_string()),
+            errors,
+        }
+    }
+
+    /// 解码新旧两个值并计算字段级差异
+    pub fn diff(&self, key: &str, old_raw: &str, new_raw: &str) -> Vec<FieldChange> {
+        let old = self.decode(key, old_raw);
+        let new = self.decode(key, new_raw);
+        diff_trees(&old.tree, &new.tree)
+    }
+}
+
+impl Default for DecoderRegistry {
+    fn default() -> Self {
+        Self::with_defaults()
+    }
+}
+
+/// 全局解码器注册表
+static GLOBAL_REGISTRY: OnceLock<DecoderRegistry> = OnceLock::new();
+
+/// 获取全局解码器注册表
+pub fn get_decoder_registry() -> &'static DecoderRegistry {
+    GLOBAL_REGISTRY.get_or_init(DecoderRegistry::with_defaults)
+}
+
+/// 比较两棵 JSON 树，返回字段级差异
+pub fn diff_trees(old: &Value, new: &Value) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    diff_recursive("", old, new, &mut changes);
+    changes
+}
+
+fn diff_recursive(path: &str, old: &Value, new: &Value, changes: &mut Vec<FieldChange>) {
+    if changes.len() >= MAX_FIELD_CHANGES || old == new {
+        return;
+    }
+
+    match (old, new) {
+        (Value::Object(old_obj), Value::Object(new_obj)) => {
+            for (key, new_value) in new_obj {
+                let child = format!("{}/{}", path, escape_pointer(key));
+                match old_obj.get(key) {
+                    Some(old_value) => diff_recursive(&child, old_value, new_value, changes),
+                    None => push_change(changes, child, "added", None, Some(new_value)),
+                }
+            }
+            for (key, old_value) in old_obj {
+                if !new_obj.contains_key(key) {
+                    let child = format!("{}/{}", path, escape_pointer(key));
+                    push_change(changes, child, "removed", Some(old_value), None);
+                }
+            }
+        }
+        (Value::Array(old_arr), Value::Array(new_arr)) => {
+            for (i, new_value) in new_arr.iter().enumerate() {
+                let child = format!("{}/{}", path, i);
+                match old_arr.get(i) {
+                    Some(old_value) => diff_recursive(&child, old_value, new_value, changes),
+                    None => push_change(changes, child, "added", None, Some(new_value)),
+                }
+            }
+            for (i, old_value) in old_arr.iter().enumerate().skip(new_arr.len()) {
+                push_change(changes, format!("{}/{}", path, i), "removed", Some(old_value), None);
+            }
+        }
+        _ => push_change(changes, path.to_string(), "changed", Some(old), Some(new)),
+    }
+}
+
+fn push_change(
+    changes: &mut Vec<FieldChange>,
+    path: String,
+    change_type: &str,
+    old_value: Option<&Value>,
+    new_value: Option<&Value>,
+) {
+    if changes.len() >= MAX_FIELD_CHANGES {
+        return;
+    }
+    changes.push(FieldChange {
+        path: if path.is_empty() { "/".to_string() } else { path },
+        change_type: change_type.to_string(),
+        old_value: old_value.cloned(),
+        new_value: new_value.cloned(),
+    });
+}
+
+/// JSON Pointer 转义（RFC 6901）
+pub(crate) fn escape_pointer(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+// ========== 内部辅助函数 ==========
+
+fn decode_base64(raw: &str) -> Result<Vec<u8>, String> {
+    let trimmed = raw.trim().trim_matches('"');
+    STANDARD
+        .decode(trimmed)
+        .or_else(|_| base64::engine::general_purpose::URL_SAFE.decode(trimmed))
+        .map_err(|e| format!("Base64 解码失败: {}", e))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 读取 varint，返回 (值, 读取字节数)
+fn read_varint(data: &[u8]) -> Option<(u64, usize)> {
+    let mut value: u64 = 0;
+    for (i, byte) in data.iter().enumerate().take(10) {
+        value |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// 解析 protobuf 消息，输出 `{ "<field>": [values...] }`
+///
+/// 同一字段号可能重复出现（repeated），因此统一用数组保存。
+fn parse_protobuf_message(data: &[u8], depth: usize) -> Option<Value> {
+    if data.is_empty() || depth > MAX_PROTOBUF_DEPTH {
+        return None;
+    }
+
+    let mut fields: Map<String, Value> = Map::new();
+    let mut cursor = 0;
+
+    while cursor < data.len() {
+        let (tag, tag_len) = read_varint(&data[cursor..])?;
+        cursor += tag_len;
+
+        let field_number = tag >> 3;
+        let wire_type = tag & 0x7;
+        if field_number == 0 {
+            return None;
+        }
+
+        let value = match wire_type {
+            0 => {
+                let (v, n) = read_varint(&data[cursor..])?;
+                cursor += n;
+                json!({ "type": "varint", "value": v })
+            }
+            1 => {
+                let bytes: [u8; 8] = data.get(cursor..cursor + 8)?.try_into().ok()?;
+                cursor += 8;
+                json!({ "type": "fixed64", "value": u64::from_le_bytes(bytes) })
+            }
+            2 => {
+                let (len, n) = read_varint(&data[cursor..])?;
+                cursor += n;
+                let end = cursor.checked_add(len as usize)?;
+                let payload = data.get(cursor..end)?;
+                cursor = end;
+                decode_length_delimited(payload, depth)
+            }
+            5 => {
+                let bytes: [u8; 4] = data.get(cursor..cursor + 4)?.try_into().ok()?;
+                cursor += 4;
+                json!({ "type": "fixed32", "value": u32::from_le_bytes(bytes) })
+            }
+            _ => return None,
+        };
+
+        match fields
+            .entry(field_number.to_string())
+            .or_insert_with(|| Value::Array(Vec::new()))
+        {
+            Value::Array(values) => values.push(value),
+            _ => unreachable!(),
+        }
+    }
+
+    Some(Value::Object(fields))
+}
+
+fn decode_length_delimited(payload: &[u8], depth: usize) -> Value {
+    // 可打印的 UTF-8 文本优先按字符串处理，避免把短文本误判为嵌套消息
+    if let Ok(text) = std::str::from_utf8(payload) {
+        if !text.is_empty() && text.chars().all(|c| !c.is_control() || c.is_whitespace()) {
+            // 文本本身可能是 JSON 或 Base64 包裹的嵌套数据
+            if let Ok(parsed) = serde_json::from_str::<Value>(text) {
+                if parsed.is_object() || parsed.is_array() {
+                    return json!({ "type": "json", "value": parsed });
+                }
+            }
+            return json!({ "type": "string", "value": text });
+        }
+    }
+
+    if let Some(message) = parse_protobuf_message(payload, depth + 1) {
+        return json!({ "type": "message", "value": message });
+    }
+
+    json!({ "type": "bytes", "length": payload.len(), "hex": to_hex(payload) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(bytes: &[u8]) -> String {
+        STANDARD.encode(bytes)
+    }
+
+    #[test]
+    fn decodes_protobuf_wire_format() {
+        // 1: varint 150, 2: "hi", 3: { 1: varint 1 }, 4: fixed32 7
+        let raw = encode(&[
+            0x08, 0x96, 0x01, 0x12, 0x02, b'h', b'i', 0x1a, 0x02, 0x08, 0x01, 0x25, 0x07, 0x00, 0x00, 0x00,
+        ]);
+
+        let tree = ProtobufWireDecoder.decode(&raw).unwrap();
+
+        assert_eq!(tree["1"][0], json!({ "type": "varint", "value": 150 }));
+        assert_eq!(tree["2"][0], json!({ "type": "string", "value": "hi" }));
+        assert_eq!(tree["3"][0]["type"], "message");
+        assert_eq!(tree["3"][0]["value"]["1"][0]["value"], 1);
+        assert_eq!(tree["4"][0], json!({ "type": "fixed32", "value": 7 }));
+    }
+
+    #[test]
+    fn repeated_fields_and_undecodable_payloads() {
+        // 字段 1 重复出现；字段 2 的内容既不是文本也不是合法消息
+        let raw = encode(&[0x08, 0x01, 0x08, 0x02, 0x12, 0x02, 0xff, 0xff]);
+
+        let tree = ProtobufWireDecoder.decode(&raw).unwrap();
+
+        assert_eq!(tree["1"].as_array().unwrap().len(), 2);
+        assert_eq!(tree["2"][0], json!({ "type": "bytes", "length": 2, "hex": "ffff" }));
+    }
+
+    #[test]
+    fn rejects_truncated_protobuf() {
+        // 声明长度 5，实际只有 1 字节
+        assert!(ProtobufWireDecoder.decode(&encode(&[0x12, 0x05, 0x01])).is_err());
+        assert!(ProtobufWireDecoder.decode("not base64!").is_err());
+    }
+
+    #[test]
+    fn diffs_trees_field_by_field() {
+        let old = json!({ "a": 1, "b": { "c": [1, 2] }, "gone": true, "x/y": "old" });
+        let new = json!({ "a": 2, "b": { "c": [1] }, "added": null, "x/y": "new" });
+
+        let mut changes: Vec<(String, String)> = diff_trees(&old, &new)
+            .into_iter()
+            .map(|c| (c.path, c.change_type))
+            .collect();
+        changes.sort();
+
+        assert_eq!(
+            changes,
+            [
+                ("/a", "changed"),
+                ("/added", "added"),
+                ("/b/c/1", "removed"),
+                ("/gone", "removed"),
+                ("/x~1y", "changed"),
+            ]
+            .map(|(p, t)| (p.to_string(), t.to_string()))
+        );
+        assert!(diff_trees(&old, &old).is_empty());
+        assert_eq!(diff_trees(&json!(1), &json!(2))[0].path, "/");
+    }
+}
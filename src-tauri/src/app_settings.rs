@@ -6,6 +6,9 @@ use tauri::AppHandle;
 use tauri::Manager;
 
 use crate::config_manager::ConfigManager;
+use crate::platform::ProcessRules;
+use crate::quota::QuotaAlertRule;
+use crate::resource_monitor::ResourceMonitorSettings;
 
 /// 应用程序设置
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,6 +17,25 @@ pub struct AppSettings {
     pub system_tray_enabled: bool,
     /// 是否启用静默启动（启动时最小化到托盘或后台）
     pub silent_start_enabled: bool,
+    /// 检测到在 Antigravity 中登录新账户时是否自动备份账户
+    #[serde(default)]
+    pub auto_backup_on_login: bool,
+    /// 配额采样间隔（秒），0 表示关闭采样
+    #[serde(default = "default_quota_sample_interval_secs")]
+    pub quota_sample_interval_secs: u64,
+    /// 低配额告警规则
+    #[serde(default)]
+    pub quota_alert_rules: Vec<QuotaAlertRule>,
+    /// Antigravity 进程识别的包含/排除规则
+    #[serde(default)]
+    pub process_rules: ProcessRules,
+    /// Antigravity 资源监控与告警
+    #[serde(default)]
+    pub resource_monitor: ResourceMonitorSettings,
+}
+
+fn default_quota_sample_interval_secs() -> u64 {
+    300
 }
 
 impl Default for AppSettings {
@@ -21,6 +43,11 @@ impl Default for AppSettings {
         Self {
             system_tray_enabled: false, // 默认不启用，避免打扰用户
             silent_start_enabled: false, // 默认不启用静默启动，让用户看到应用界面
+            auto_backup_on_login: false, // 默认不启用，由用户主动开启
+            quota_sample_interval_secs: default_quota_sample_interval_secs(),
+            quota_alert_rules: Vec::new(),
+            process_rules: ProcessRules::default(),
+            resource_monitor: ResourceMonitorSettings::default(),
         }
     }
 }
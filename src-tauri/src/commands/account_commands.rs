@@ -9,6 +9,8 @@ use std::fs;
 use std::path::PathBuf;
 use chrono::{DateTime, Local};
 
+use crate::antigravity::status_snapshot::AccountStatusSnapshot;
+
 /// 切换 Antigravity 账户
 #[tauri::command]
 #[instrument(fields(account_id = %account_id))]
@@ -263,6 +265,12 @@ fn parse_backup_to_account(
         .unwrap_or("")
         .to_string();
 
+    // 最近一次的用户状态快照
+    let status_snapshot = AccountStatusSnapshot::summary_from_backup(
+        backup_data,
+        chrono::Local::now().timestamp_millis(),
+    );
+
     Ok(crate::AntigravityAccount {
         id,
         name,
@@ -273,6 +281,7 @@ fn parse_backup_to_account(
         created_at,
         last_switched,
         remark,
+        status_snapshot,
     })
 }
 
@@ -465,6 +474,7 @@ pub async fn clear_all_antigravity_data() -> Result<String, String> {
 #[tauri::command]
 pub async fn restore_antigravity_account(account_name: String) -> Result<String, String> {
     tracing::debug!(target: "account::restore", account_name = %account_name, "调用 restore_antigravity_account");
+    let _suppression = crate::db_monitor::account_events::suppress_account_events();
 
     // 1. 构建备份文件路径
     let config_dir = dirs::config_dir()
@@ -485,12 +495,22 @@ pub async fn restore_antigravity_account(account_name: String) -> Result<String,
     Ok(result)
 }
 
+/// 切换账户后等待语言服务器就绪的最长时间
+const SWITCH_READY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
 /// 切换到 Antigravity 账户（调用 restore_antigravity_account）
 #[tauri::command]
 pub async fn switch_to_antigravity_account(account_name: String) -> Result<String, String> {
     crate::log_async_command!("switch_to_antigravity_account", async {
+        let switch_started_at = chrono::Local::now().timestamp_millis();
+        // 关闭、恢复、重新启动期间登录状态的变化都由本次切换造成（等待就绪前释放）
+        let suppression = crate::db_monitor::account_events::suppress_account_events();
+
         // 1. 关闭 Antigravity 进程 (如果存在)
-        let kill_result = match crate::platform::kill_antigravity_processes() {
+        let kill_result = match tokio::task::spawn_blocking(crate::platform::kill_antigravity_processes)
+            .await
+            .map_err(|e| format!("关闭进程任务异常: {}", e))?
+        {
             Ok(result) => {
                 if result.contains("not found") || result.contains("未找到") {
                     tracing::debug!(target: "account::switch::step1", "Antigravity 进程未运行，跳过关闭步骤");
@@ -511,9 +531,6 @@ pub async fn switch_to_antigravity_account(account_name: String) -> Result<Strin
             }
         };
 
-        // 等待一秒确保进程完全关闭
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-
         // 2. 恢复指定账户到 Antigravity 数据库
         let restore_result = restore_antigravity_account(account_name.clone()).await?;
         tracing::debug!(target: "account::switch::step2", result = %restore_result, "账户数据恢复完成");
@@ -523,6 +540,7 @@ pub async fn switch_to_antigravity_account(account_name: String) -> Result<Strin
 
         // 3. 重新启动 Antigravity 进程
         let start_result = crate::antigravity::starter::start_antigravity();
+        let start_result_ok = start_result.is_ok();
         let start_message = match start_result {
             Ok(result) => {
                 tracing::debug!(target: "account::switch::step3", result = %result, "Antigravity 启动成功");
@@ -534,7 +552,29 @@ pub async fn switch_to_antigravity_account(account_name: String) -> Result<Strin
             }
         };
 
-        let final_message = format!("{} -> {} -> {}", kill_result, restore_result, start_message);
+        // 启动之后的登录状态变化（如用户在新窗口中重新登录）需要正常处理
+        drop(suppression);
+
+        // 4. 等待语言服务器就绪（启动失败时不等待）
+        let ready_message = if start_result_ok {
+            match crate::language_server::readiness::wait_until_ready_since(switch_started_at, SWITCH_READY_TIMEOUT).await {
+                Ok(_) => {
+                    tracing::debug!(target: "account::switch::step4", "语言服务器已就绪");
+                    "语言服务器已就绪".to_string()
+                }
+                Err(e) => {
+                    tracing::warn!(target: "account::switch::step4", error = %e, "等待语言服务器就绪失败");
+                    e
+                }
+            }
+        } else {
+            "跳过就绪等待".to_string()
+        };
+
+        let final_message = format!(
+            "{} -> {} -> {} -> {}",
+            kill_result, restore_result, start_message, ready_message
+        );
 
         Ok(final_message)
     })
@@ -562,6 +602,7 @@ pub async fn update_account_remark(
         if !backup_file.exists() {
             return Err(format!("账户文件不存在: {}", email));
         }
+        let _guard = crate::antigravity::backup::lock_backup_files();
         
         // 2. 读取现有数据
         let content = fs::read_to_string(&backup_file)
@@ -577,8 +618,7 @@ pub async fn update_account_remark(
         let updated_content = serde_json::to_string_pretty(&backup_data)
             .map_err(|e| format!("序列化 JSON 失败: {}", e))?;
         
-        fs::write(&backup_file, updated_content)
-            .map_err(|e| format!("写入文件失败: {}", e))?;
+        crate::antigravity::backup::write_backup_file(&backup_file, &updated_content)?;
         
         tracing::info!("✅ 账户备注更新成功");
         Ok(format!("账户 {} 的备注已更新", email))
@@ -1,6 +1,8 @@
 //! 数据库监控相关命令
 //! 提供数据库监控状态的查询和控制功能
 
+use crate::db_monitor::journal::{get_journal, JournalEntry, JournalQuery};
+use crate::db_monitor::subscription::Subscription;
 use crate::db_monitor::DatabaseMonitor;
 use std::sync::Arc;
 use tauri::{AppHandle, Manager};
@@ -40,3 +42,78 @@ pub async fn stop_database_monitoring(
         Ok("数据库监控已停止".to_string())
     })
 }
+
+/// 订阅数据库变化（按 key 模式过滤，支持 `*` 通配符）
+///
+/// 返回的订阅中包含专属事件名，前端监听该事件即可只接收匹配字段的变化
+#[tauri::command]
+pub async fn subscribe_database_changes(
+    app: AppHandle,
+    patterns: Vec<String>,
+    max_payload_bytes: Option<usize>,
+) -> Result<Subscription, String> {
+    crate::log_async_command!("subscribe_database_changes", async {
+        let monitor = app.state::<Arc<DatabaseMonitor>>();
+        Ok::<_, String>(monitor.subscribe(patterns, max_payload_bytes).await)
+    })
+}
+
+/// 取消数据库变化订阅
+#[tauri::command]
+pub async fn unsubscribe_database_changes(
+    app: AppHandle,
+    subscription_id: String,
+) -> Result<bool, String> {
+    crate::log_async_command!("unsubscribe_database_changes", async {
+        let monitor = app.state::<Arc<DatabaseMonitor>>();
+        Ok::<_, String>(monitor.unsubscribe(&subscription_id).await)
+    })
+}
+
+/// 列出当前所有数据库变化订阅
+#[tauri::command]
+pub async fn list_database_subscriptions(
+    app: AppHandle,
+) -> Result<Vec<Subscription>, String> {
+    crate::log_async_command!("list_database_subscriptions", async {
+        let monitor = app.state::<Arc<DatabaseMonitor>>();
+        Ok::<_, String>(monitor.list_subscriptions().await)
+    })
+}
+
+/// 查询数据库变化日志（按时间范围与 key 过滤）
+#[tauri::command]
+pub async fn query_database_journal(
+    query: JournalQuery,
+) -> Result<Vec<JournalEntry>, String> {
+    crate::log_async_command!("query_database_journal", async {
+        tokio::task::spawn_blocking(move || {
+            let journal = get_journal().ok_or_else(|| "变化日志不可用".to_string())?;
+            journal
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .query(&query)
+                .map_err(|e| format!("查询变化日志失败: {}", e))
+        })
+        .await
+        .map_err(|e| format!("查询变化日志任务异常: {}", e))?
+    })
+}
+
+/// 清空数据库变化日志
+#[tauri::command]
+pub async fn clear_database_journal() -> Result<String, String> {
+    crate::log_async_command!("clear_database_journal", async {
+        let removed = tokio::task::spawn_blocking(|| {
+            let journal = get_journal().ok_or_else(|| "变化日志不可用".to_string())?;
+            journal
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clear()
+                .map_err(|e| format!("清空变化日志失败: {}", e))
+        })
+        .await
+        .map_err(|e| format!("清空变化日志任务异常: {}", e))??;
+        Ok(format!("已清空 {} 条变化记录", removed))
+    })
+}
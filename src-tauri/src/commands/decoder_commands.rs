@@ -0,0 +1,126 @@
+//! 编码值解码命令
+//! 将 allUserSettings、agentManagerInitState 等不透明字段渲染成可读的树，并比较账户间差异
+
+use rusqlite::{Connection, OptionalExtension};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::antigravity::value_decoder::{get_decoder_registry, DecodedValue, FieldChange};
+use crate::constants::database;
+use crate::path_utils::AppPaths;
+
+/// 解码数据库字段
+///
+/// 未传入 `value` 时读取当前 Antigravity 数据库中的值
+#[tauri::command]
+pub async fn decode_database_value(
+    key: String,
+    value: Option<String>,
+) -> Result<DecodedValue, String> {
+    crate::log_async_command!("decode_database_value", async {
+        let raw = match value {
+            Some(v) => v,
+            None => read_current_value(&key)?
+                .ok_or_else(|| format!("数据库中不存在字段: {}", key))?,
+        };
+
+        Ok(get_decoder_registry().decode(&key, &raw))
+    })
+}
+
+/// 比较两个账户备份中编码字段的字段级差异
+///
+/// 未传入 `key` 时比较所有已注册解码器的备份字段
+#[tauri::command]
+pub async fn diff_account_values(
+    account_a: String,
+    account_b: String,
+    key: Option<String>,
+) -> Result<BTreeMap<String, Vec<FieldChange>>, String> {
+    crate::log_async_command!("diff_account_values", async {
+        let backup_a = read_backup(&account_a)?;
+        let backup_b = read_backup(&account_b)?;
+        let registry = get_decoder_registry();
+
+        let keys: Vec<String> = match key {
+            Some(k) => vec![k],
+            None => database::ALL_KEYS
+                .iter()
+                .filter(|k| registry.has_decoder(k))
+                .map(|k| k.to_string())
+                .collect(),
+        };
+
+        let mut result = BTreeMap::new();
+        for key in keys {
+            let old = backup_a.get(&key).and_then(|v| v.as_str()).unwrap_or("");
+            let new = backup_b.get(&key).and_then(|v| v.as_str()).unwrap_or("");
+            if old == new {
+                continue;
+            }
+
+            let changes = registry.diff(&key, old, new);
+            if !changes.is_empty() {
+                result.insert(key, changes);
+            }
+        }
+
+        Ok(result)
+    })
+}
+
+/// 读取当前数据库中指定字段的原始值
+fn read_current_value(key: &str) -> Result<Option<String>, String> {
+    let db_path = crate::platform::get_antigravity_db_path()
+        .ok_or_else(|| "未找到 Antigravity 数据库路径".to_string())?;
+
+    if !db_path.exists() {
+        return Err(format!("数据库文件不存在: {}", db_path.display()));
+    }
+
+    let conn = Connection::open(&db_path).map_err(|e| format!("连接数据库失败: {}", e))?;
+    conn.query_row("SELECT value FROM ItemTable WHERE key = ?", [key], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("查询字段失败: {}", e))
+}
+
+/// 读取账户备份文件
+fn read_backup(account: &str) -> Result<Value, String> {
+    let backup_file = AppPaths::backup_dir()
+        .ok_or_else(|| "无法获取备份目录".to_string())?
+        .join(format!("{}.json", validate_account_name(account)?));
+
+    let content = fs::read_to_string(&backup_file)
+        .map_err(|e| format!("读取备份文件失败 {}: {}", account, e))?;
+
+    serde_json::from_str(&content).map_err(|e| format!("解析备份文件失败 {}: {}", account, e))
+}
+
+/// 账户名来自前端，只允许单个文件名，防止 `../` 等路径读取备份目录之外的文件
+fn validate_account_name(account: &str) -> Result<&str, String> {
+    let is_plain_name = !account.trim().is_empty()
+        && !account.contains(['/', '\\', '\0'])
+        && !account.contains("..")
+        && Path::new(account).file_name().and_then(|n| n.to_str()) == Some(account);
+
+    if is_plain_name {
+        Ok(account)
+    } else {
+        Err(format!("无效的账户名: {}", account))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate_account_name;
+
+    #[test]
+    fn rejects_account_names_that_escape_the_backup_dir() {
+        assert!(validate_account_name("user@example.com").is_ok());
+        for name in ["", "../../x", "..", "a/b", "a\\b", "/etc/passwd", "C:\\x", "x\0"] {
+            assert!(validate_account_name(name).is_err(), "{name:?}");
+        }
+    }
+}
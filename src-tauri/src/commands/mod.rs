@@ -26,6 +26,15 @@ pub mod settings_commands;
 
 // 数据库监控命令
 pub mod db_monitor_commands;
+
+// 编码值解码命令
+pub mod decoder_commands;
+
+// 配额历史命令
+pub mod quota_commands;
+
+// 资源监控命令
+pub mod resource_monitor_commands;
 // 语言服务器相关命令（在 src/language_server 下）
 
 
@@ -33,9 +42,12 @@ pub mod db_monitor_commands;
 pub use account_commands::*;
 pub use backup_commands::*;
 pub use db_monitor_commands::*;
+pub use decoder_commands::*;
 pub use logging_commands::*;
 pub use platform_commands::*;
 pub use process_commands::*;
+pub use quota_commands::*;
+pub use resource_monitor_commands::*;
 pub use settings_commands::*;
 pub use tray_commands::*;
 pub use crate::language_server::*;
@@ -4,7 +4,43 @@ use rusqlite::Connection;
 /// 关闭 Antigravity 进程
 #[tauri::command]
 pub async fn kill_antigravity() -> Result<String, String> {
-    crate::platform::kill_antigravity_processes()
+    tokio::task::spawn_blocking(crate::platform::kill_antigravity_processes)
+        .await
+        .map_err(|e| format!("关闭进程任务异常: {}", e))?
+}
+
+/// 优雅关闭 Antigravity，返回每个进程的结束方式
+#[tauri::command]
+pub async fn shutdown_antigravity(
+    timeout_ms: Option<u64>,
+) -> Result<crate::platform::ShutdownReport, String> {
+    let timeout = timeout_ms
+        .map(std::time::Duration::from_millis)
+        .unwrap_or(crate::platform::GRACEFUL_SHUTDOWN_TIMEOUT);
+    tokio::task::spawn_blocking(move || crate::platform::shutdown_antigravity_processes(timeout))
+        .await
+        .map_err(|e| format!("关闭进程任务异常: {}", e))?
+}
+
+/// 重启 Antigravity：优雅关闭后重新启动（用于资源告警的重启提示）
+#[tauri::command]
+pub async fn restart_antigravity() -> Result<String, String> {
+    crate::log_async_command!("restart_antigravity", async {
+        let shutdown = tokio::task::spawn_blocking(|| {
+            crate::platform::shutdown_antigravity_processes(crate::platform::GRACEFUL_SHUTDOWN_TIMEOUT)
+        })
+        .await
+        .map_err(|e| format!("关闭进程任务异常: {}", e))?;
+
+        // 未运行时直接启动；仍有进程未退出时不再启动，避免出现两个实例
+        if let Ok(report) = &shutdown {
+            if !report.all_exited() {
+                return Err(format!("部分进程未能关闭: {}", report.summary()));
+            }
+        }
+
+        crate::antigravity::starter::start_antigravity()
+    })
 }
 
 /// 启动 Antigravity 应用
@@ -16,7 +52,9 @@ pub async fn start_antigravity() -> Result<String, String> {
 /// 检查 Antigravity 进程是否正在运行
 #[tauri::command]
 pub async fn is_antigravity_running() -> bool {
-    crate::platform::is_antigravity_running()
+    tokio::task::spawn_blocking(crate::platform::is_antigravity_running)
+        .await
+        .unwrap_or(false)
 }
 
 /// 列出所有 Antigravity 相关的进程（用于调试）
@@ -26,36 +64,42 @@ pub async fn list_antigravity_processes() -> Result<Vec<serde_json::Value>, Stri
 
   tracing::info!("🔍 搜索所有 Antigravity 相关进程");
 
-    let mut system = sysinfo::System::new_all();
-    system.refresh_all();
-
-    let mut found_processes = Vec::new();
-    let process_patterns = crate::platform::get_antigravity_process_patterns_for_debug();
-
-    for (pid, process) in system.processes() {
-        let process_name = process.name();
-        let process_cmd = process.cmd().join(" ");
-
-        for (i, pattern) in process_patterns.iter().enumerate() {
-            if crate::platform::matches_antigravity_process_for_debug(
-                process_name, &process_cmd, pattern
-            ) {
-                found_processes.push(json!({
-                    "pid": pid.to_string(),
-                    "name": process_name,
-                    "command": process_cmd,
-                    "matched_pattern": i,
-                    "pattern_description": format!("{:?}", pattern)
-                }));
-                break; // 每个进程只记录一次
+    let found_processes = tokio::task::spawn_blocking(|| {
+        crate::platform::snapshot::with_snapshot(crate::platform::snapshot::freshness::NOW, |snapshot| {
+            let matcher = snapshot.matcher();
+
+            let mut found_processes = Vec::new();
+            for (pid, process) in snapshot.processes() {
+                if let Some(reason) = matcher.matches(*pid, process) {
+                    found_processes.push(json!({
+                        "pid": pid.to_string(),
+                        "name": process.name(),
+                        "command": process.cmd().join(" "),
+                        "exe": process.exe().map(|p| p.display().to_string()),
+                        "match_reason": reason
+                    }));
+                }
             }
-        }
-    }
+            found_processes
+        })
+    })
+    .await
+    .map_err(|e| format!("搜索进程任务异常: {}", e))?;
 
   tracing::info!("📊 找到 {} 个 Antigravity 相关进程", found_processes.len());
     Ok(found_processes)
 }
 
+/// 获取 Antigravity 进程树（含角色、CPU 和内存占用）
+#[tauri::command]
+pub async fn get_antigravity_process_tree() -> Result<crate::platform::ProcessTree, String> {
+    crate::log_async_command!("get_antigravity_process_tree", async {
+        tokio::task::spawn_blocking(crate::platform::process_tree::collect_process_tree)
+            .await
+            .map_err(|e| format!("采集进程树任务异常: {}", e))
+    })
+}
+
 /// 备份并重启 Antigravity
 #[tauri::command]
 pub async fn backup_and_restart_antigravity() -> Result<String, String> {
@@ -63,7 +107,10 @@ pub async fn backup_and_restart_antigravity() -> Result<String, String> {
 
     // 1. 关闭进程 (如果存在)
     println!("🛑 步骤1: 检查并关闭 Antigravity 进程");
-    let kill_result = match crate::platform::kill_antigravity_processes() {
+    let kill_result = match tokio::task::spawn_blocking(crate::platform::kill_antigravity_processes)
+        .await
+        .map_err(|e| format!("关闭进程任务异常: {}", e))?
+    {
         Ok(result) => {
             if result.contains("not found") || result.contains("未找到") {
                 println!("ℹ️ Antigravity 进程未运行，跳过关闭步骤");
@@ -83,9 +130,6 @@ pub async fn backup_and_restart_antigravity() -> Result<String, String> {
         }
     };
 
-    // 等待500ms确保进程完全关闭（缩短等待时间避免前端超时）
-    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-
     // 2. 备份当前账户信息（使用统一的智能备份函数）
     println!("💾 步骤2: 尝试备份当前账户信息");
 
@@ -205,7 +249,10 @@ pub async fn clear_and_restart_antigravity() -> Result<String, String> {
 
     // 1. 关闭进程 (如果存在)
     println!("🛑 步骤1: 检查并关闭 Antigravity 进程");
-    let kill_result = match crate::platform::kill_antigravity_processes() {
+    let kill_result = match tokio::task::spawn_blocking(crate::platform::kill_antigravity_processes)
+        .await
+        .map_err(|e| format!("关闭进程任务异常: {}", e))?
+    {
         Ok(result) => {
             if result.contains("not found") || result.contains("未找到") {
                 println!("ℹ️ Antigravity 进程未运行，跳过关闭步骤");
@@ -225,9 +272,6 @@ pub async fn clear_and_restart_antigravity() -> Result<String, String> {
         }
     };
 
-    // 等待500ms确保进程完全关闭
-    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-
     // 2. 清除所有备份 (根据需求，过期/重置时应清除所有数据)
     println!("🗑️ 步骤2: 清除所有备份文件");
     if let Err(e) = crate::antigravity::backup::clear_all_backups() {
@@ -0,0 +1,109 @@
+//! 配额历史命令
+//! 查询按账户/模型保存的配额历史、积分历史、消耗速率和耗尽时间预测、模型目录，以及当前告警
+
+use tauri::AppHandle;
+
+use crate::quota::{
+    get_quota_store, project_all, CreditSample, ModelCatalog, ModelQuotaSample, QuotaAlert,
+    QuotaProjection, QuotaSnapshot,
+};
+
+/// 立即采样一次当前账户的配额
+#[tauri::command]
+pub async fn sample_quota_now(app: AppHandle) -> Result<QuotaSnapshot, String> {
+    crate::log_async_command!("sample_quota_now", async {
+        crate::quota::sample_once(&app).await
+    })
+}
+
+/// 查询模型配额历史
+#[tauri::command]
+pub async fn get_quota_history(
+    email: String,
+    model: Option<String>,
+    from: Option<i64>,
+    to: Option<i64>,
+) -> Result<Vec<ModelQuotaSample>, String> {
+    crate::log_async_command!("get_quota_history", async {
+        let store = get_quota_store().ok_or("配额历史库不可用")?;
+        let store = store.lock().map_err(|e| format!("配额历史库锁异常: {}", e))?;
+        store
+            .model_history(&email, model.as_deref(), from, to)
+            .map_err(|e| format!("查询配额历史失败: {}", e))
+    })
+}
+
+/// 查询套餐积分历史
+#[tauri::command]
+pub async fn get_credit_history(
+    email: String,
+    from: Option<i64>,
+    to: Option<i64>,
+) -> Result<Vec<CreditSample>, String> {
+    crate::log_async_command!("get_credit_history", async {
+        let store = get_quota_store().ok_or("配额历史库不可用")?;
+        let store = store.lock().map_err(|e| format!("配额历史库锁异常: {}", e))?;
+        store
+            .credit_history(&email, from, to)
+            .map_err(|e| format!("查询积分历史失败: {}", e))
+    })
+}
+
+/// 计算账户各模型的消耗速率与预计耗尽时间
+#[tauri::command]
+pub async fn get_quota_projections(email: String) -> Result<Vec<QuotaProjection>, String> {
+    crate::log_async_command!("get_quota_projections", async {
+        let since = chrono::Local::now().timestamp_millis() - 7 * 24 * 3600 * 1000;
+        let store = get_quota_store().ok_or("配额历史库不可用")?;
+        let samples = {
+            let store = store.lock().map_err(|e| format!("配额历史库锁异常: {}", e))?;
+            store
+                .model_history(&email, None, Some(since), None)
+                .map_err(|e| format!("查询配额历史失败: {}", e))?
+        };
+
+        Ok(project_all(&samples))
+    })
+}
+
+/// 获取当前仍处于触发状态的配额告警
+#[tauri::command]
+pub async fn get_active_quota_alerts() -> Result<Vec<QuotaAlert>, String> {
+    crate::log_async_command!("get_active_quota_alerts", async {
+        Ok::<_, String>(crate::quota::get_active_alerts())
+    })
+}
+
+/// 获取当前账户的模型目录
+///
+/// 语言服务器可用时重新获取并更新目录（变化时推送 `model-catalog-changed` 事件），
+/// 否则返回最近一次保存的目录
+#[tauri::command]
+pub async fn get_model_catalog(app: AppHandle) -> Result<Option<ModelCatalog>, String> {
+    crate::log_async_command!("get_model_catalog", async {
+        let auth = tokio::task::spawn_blocking(crate::quota::sampler::read_current_auth)
+            .await
+            .map_err(|e| format!("读取登录状态任务异常: {}", e))??;
+
+        match crate::language_server::commands::fetch_user_status(&auth.api_key).await {
+            Ok(status) => {
+                let updated = tokio::task::spawn_blocking(move || {
+                    crate::quota::catalog::update(Some(&app), &status)
+                })
+                .await
+                .map_err(|e| format!("更新模型目录任务异常: {}", e))??;
+                if let Some((catalog, _)) = updated {
+                    return Ok(Some(catalog));
+                }
+            }
+            Err(e) => {
+                tracing::warn!("获取用户状态失败，返回已保存的模型目录: {}", e);
+            }
+        }
+
+        let email = auth.email;
+        tokio::task::spawn_blocking(move || crate::quota::catalog::load(&email))
+            .await
+            .map_err(|e| format!("读取模型目录任务异常: {}", e))?
+    })
+}
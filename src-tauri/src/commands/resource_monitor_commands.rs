@@ -0,0 +1,20 @@
+//! 资源监控命令
+//! 查询 Antigravity 各进程角色的 CPU / 内存历史，以及当前的资源告警
+
+use crate::resource_monitor::{ResourceAlert, ResourceSample};
+
+/// 获取资源占用历史（按时间升序），`since` 为毫秒时间戳
+#[tauri::command]
+pub async fn get_resource_history(since: Option<i64>) -> Result<Vec<ResourceSample>, String> {
+    crate::log_async_command!("get_resource_history", async {
+        Ok::<_, String>(crate::resource_monitor::history::history(since))
+    })
+}
+
+/// 获取当前仍处于触发状态的资源告警
+#[tauri::command]
+pub async fn get_active_resource_alerts() -> Result<Vec<ResourceAlert>, String> {
+    crate::log_async_command!("get_active_resource_alerts", async {
+        Ok::<_, String>(crate::resource_monitor::alerts::get_active_alerts())
+    })
+}
@@ -34,6 +34,165 @@ pub async fn save_silent_start_state(
     })
 }
 
+/// 获取登录后自动备份状态
+#[tauri::command]
+pub async fn is_auto_backup_on_login_enabled(
+    app: AppHandle,
+) -> Result<bool, String> {
+    crate::log_async_command!("is_auto_backup_on_login_enabled", async {
+        let settings_manager = app.state::<crate::app_settings::AppSettingsManager>();
+        let settings = settings_manager.get_settings();
+        Ok(settings.auto_backup_on_login)
+    })
+}
+
+/// 保存登录后自动备份状态
+#[tauri::command]
+pub async fn save_auto_backup_on_login_state(
+    app: AppHandle,
+    enabled: bool,
+) -> Result<String, String> {
+    crate::log_async_command!("save_auto_backup_on_login_state", async {
+        let settings_manager = app.state::<crate::app_settings::AppSettingsManager>();
+
+        settings_manager.update_settings(|settings| {
+            settings.auto_backup_on_login = enabled;
+        })?;
+
+        let status_text = if enabled { "已启用" } else { "已禁用" };
+        Ok(format!("登录后自动备份{}", status_text))
+    })
+}
+
+/// 获取配额采样间隔（秒）
+#[tauri::command]
+pub async fn get_quota_sample_interval(
+    app: AppHandle,
+) -> Result<u64, String> {
+    crate::log_async_command!("get_quota_sample_interval", async {
+        let settings_manager = app.state::<crate::app_settings::AppSettingsManager>();
+        let settings = settings_manager.get_settings();
+        Ok(settings.quota_sample_interval_secs)
+    })
+}
+
+/// 保存配额采样间隔（秒），0 表示关闭采样
+#[tauri::command]
+pub async fn save_quota_sample_interval(
+    app: AppHandle,
+    interval_secs: u64,
+) -> Result<String, String> {
+    crate::log_async_command!("save_quota_sample_interval", async {
+        let min = crate::quota::sampler::MIN_SAMPLE_INTERVAL_SECS;
+        if interval_secs != 0 && interval_secs < min {
+            return Err(format!("采样间隔不能小于 {} 秒", min));
+        }
+
+        let settings_manager = app.state::<crate::app_settings::AppSettingsManager>();
+        settings_manager.update_settings(|settings| {
+            settings.quota_sample_interval_secs = interval_secs;
+        })?;
+
+        if interval_secs == 0 {
+            Ok("配额采样已关闭".to_string())
+        } else {
+            Ok(format!("配额采样间隔已设置为 {} 秒", interval_secs))
+        }
+    })
+}
+
+/// 获取低配额告警规则
+#[tauri::command]
+pub async fn get_quota_alert_rules(
+    app: AppHandle,
+) -> Result<Vec<crate::quota::QuotaAlertRule>, String> {
+    crate::log_async_command!("get_quota_alert_rules", async {
+        let settings_manager = app.state::<crate::app_settings::AppSettingsManager>();
+        let settings = settings_manager.get_settings();
+        Ok(settings.quota_alert_rules)
+    })
+}
+
+/// 保存低配额告警规则（整体替换）
+#[tauri::command]
+pub async fn save_quota_alert_rules(
+    app: AppHandle,
+    rules: Vec<crate::quota::QuotaAlertRule>,
+) -> Result<String, String> {
+    crate::log_async_command!("save_quota_alert_rules", async {
+        crate::quota::alerts::validate_rules(&rules)?;
+
+        let count = rules.len();
+        let settings_manager = app.state::<crate::app_settings::AppSettingsManager>();
+        settings_manager.update_settings(|settings| {
+            settings.quota_alert_rules = rules;
+        })?;
+
+        Ok(format!("已保存 {} 条告警规则", count))
+    })
+}
+
+/// 获取 Antigravity 进程识别规则
+#[tauri::command]
+pub async fn get_process_rules(
+    app: AppHandle,
+) -> Result<crate::platform::ProcessRules, String> {
+    crate::log_async_command!("get_process_rules", async {
+        let settings_manager = app.state::<crate::app_settings::AppSettingsManager>();
+        let settings = settings_manager.get_settings();
+        Ok(settings.process_rules)
+    })
+}
+
+/// 保存 Antigravity 进程识别规则（整体替换，立即生效）
+#[tauri::command]
+pub async fn save_process_rules(
+    app: AppHandle,
+    rules: crate::platform::ProcessRules,
+) -> Result<String, String> {
+    crate::log_async_command!("save_process_rules", async {
+        crate::platform::matcher::validate_rules(&rules)?;
+
+        let settings_manager = app.state::<crate::app_settings::AppSettingsManager>();
+        settings_manager.update_settings(|settings| {
+            settings.process_rules = rules.clone();
+        })?;
+        crate::platform::matcher::set_process_rules(rules);
+
+        Ok("进程识别规则已保存".to_string())
+    })
+}
+
+/// 获取资源监控设置
+#[tauri::command]
+pub async fn get_resource_monitor_settings(
+    app: AppHandle,
+) -> Result<crate::resource_monitor::ResourceMonitorSettings, String> {
+    crate::log_async_command!("get_resource_monitor_settings", async {
+        let settings_manager = app.state::<crate::app_settings::AppSettingsManager>();
+        let settings = settings_manager.get_settings();
+        Ok(settings.resource_monitor)
+    })
+}
+
+/// 保存资源监控设置（整体替换，下一轮采样生效）
+#[tauri::command]
+pub async fn save_resource_monitor_settings(
+    app: AppHandle,
+    settings: crate::resource_monitor::ResourceMonitorSettings,
+) -> Result<String, String> {
+    crate::log_async_command!("save_resource_monitor_settings", async {
+        settings.validate()?;
+
+        let settings_manager = app.state::<crate::app_settings::AppSettingsManager>();
+        settings_manager.update_settings(|current| {
+            current.resource_monitor = settings;
+        })?;
+
+        Ok("资源监控设置已保存".to_string())
+    })
+}
+
 /// 获取所有应用设置
 #[tauri::command]
 pub async fn get_all_settings(
@@ -45,7 +204,12 @@ pub async fn get_all_settings(
 
         Ok(serde_json::json!({
             "system_tray_enabled": settings.system_tray_enabled,
-            "silent_start_enabled": settings.silent_start_enabled
+            "silent_start_enabled": settings.silent_start_enabled,
+            "auto_backup_on_login": settings.auto_backup_on_login,
+            "quota_sample_interval_secs": settings.quota_sample_interval_secs,
+            "quota_alert_rules": settings.quota_alert_rules,
+            "process_rules": settings.process_rules,
+            "resource_monitor": settings.resource_monitor
         }))
     })
 }
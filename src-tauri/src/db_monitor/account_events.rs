@@ -0,0 +1,227 @@
+//! 账户语义事件
+//!
+//! 根据 `antigravityAuthStatus` 的前后变化推导登录/登出/切换事件，
+//! 并在用户开启自动备份时为新登录的账户调用智能备份、刷新托盘菜单。
+//!
+//! 本程序恢复或切换账户时自己会改写登录状态，这期间（以及结束后的一小段时间内，
+//! 等待数据库监控读到最后一次写入）产生的变化不推导任何事件。
+
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{debug, error, info, warn};
+
+use crate::app_settings::AppSettingsManager;
+use crate::system_tray::SystemTrayManager;
+
+/// 抑制结束后继续忽略变化的时长（覆盖监控的去抖与轮询间隔）
+const SUPPRESSION_GRACE: Duration = Duration::from_secs(10);
+
+/// 正在进行的恢复/切换操作数
+static ACTIVE_SUPPRESSIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// 最近一次恢复/切换操作结束的时间
+static LAST_SUPPRESSION_END: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// 抑制账户事件的守卫，释放后再经过 `SUPPRESSION_GRACE` 才恢复
+pub struct AccountEventSuppression(());
+
+impl Drop for AccountEventSuppression {
+    fn drop(&mut self) {
+        *LAST_SUPPRESSION_END.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+        ACTIVE_SUPPRESSIONS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 在本程序改写登录状态（恢复、切换账户）期间抑制账户事件
+pub fn suppress_account_events() -> AccountEventSuppression {
+    ACTIVE_SUPPRESSIONS.fetch_add(1, Ordering::SeqCst);
+    AccountEventSuppression(())
+}
+
+fn is_suppressed() -> bool {
+    ACTIVE_SUPPRESSIONS.load(Ordering::SeqCst) > 0
+        || LAST_SUPPRESSION_END
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some_and(|t| t.elapsed() < SUPPRESSION_GRACE)
+}
+
+/// 登录状态中与账户身份相关的字段
+#[derive(Debug, Clone, PartialEq)]
+struct AuthIdentity {
+    email: String,
+    name: Option<String>,
+}
+
+/// 账户事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountEventKind {
+    /// 从未登录状态登录
+    SignedIn,
+    /// 登出
+    SignedOut,
+    /// 在 Antigravity 中从一个账户切换到另一个账户
+    Changed,
+    /// 同一账户的登录信息被刷新（如令牌更新）
+    Refreshed,
+}
+
+impl AccountEventKind {
+    /// 是否为用户在 Antigravity 中完成的登录（需要自动备份）
+    pub fn is_sign_in(&self) -> bool {
+        matches!(self, Self::SignedIn | Self::Changed)
+    }
+
+    /// 对应的 Tauri 事件名，`Refreshed` 不单独推送
+    pub fn event_name(&self) -> Option<&'static str> {
+        match self {
+            Self::SignedIn => Some("account-signed-in"),
+            Self::SignedOut => Some("account-signed-out"),
+            Self::Changed => Some("account-changed"),
+            Self::Refreshed => None,
+        }
+    }
+}
+
+/// 推送给前端的账户事件（不包含任何令牌）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountEvent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_email: Option<String>,
+    pub timestamp: i64,
+}
+
+/// 由登录状态的新旧原始值推导事件
+pub fn derive_event(old_raw: Option<&str>, new_raw: Option<&str>) -> Option<(AccountEventKind, AccountEvent)> {
+    let old = old_raw.and_then(parse_identity);
+    let new = new_raw.and_then(parse_identity);
+
+    let kind = match (&old, &new) {
+        (None, Some(_)) => AccountEventKind::SignedIn,
+        (Some(_), None) => AccountEventKind::SignedOut,
+        (Some(o), Some(n)) if o.email != n.email => AccountEventKind::Changed,
+        (Some(_), Some(_)) if old_raw != new_raw => AccountEventKind::Refreshed,
+        _ => return None,
+    };
+
+    let event = AccountEvent {
+        email: new.as_ref().map(|n| n.email.clone()),
+        name: new.and_then(|n| n.name),
+        previous_email: old.map(|o| o.email),
+        timestamp: chrono::Local::now().timestamp_millis(),
+    };
+
+    Some((kind, event))
+}
+
+/// 处理登录状态变化：推送语义事件，新登录时按设置自动备份
+pub fn handle_auth_change(app_handle: &AppHandle, old_raw: Option<&str>, new_raw: Option<&str>) {
+    let Some((kind, event)) = derive_event(old_raw, new_raw) else {
+        return;
+    };
+
+    if is_suppressed() {
+        debug!(target: "db_monitor::account", kind = ?kind, "恢复或切换账户期间的登录状态变化，忽略");
+        return;
+    }
+
+    info!(target: "db_monitor::account", kind = ?kind, "检测到账户状态变化");
+
+    if let Some(event_name) = kind.event_name() {
+        if let Err(e) = app_handle.emit(event_name, &event) {
+            error!(target: "db_monitor::account", "❌ 推送 {} 事件失败: {}", event_name, e);
+        }
+    }
+
+    // 令牌刷新和登出都不备份：前者会用同一账户的新令牌反复覆盖备份
+    if !kind.is_sign_in() {
+        return;
+    }
+
+    let auto_backup = app_handle
+        .state::<AppSettingsManager>()
+        .get_settings()
+        .auto_backup_on_login;
+    if !auto_backup {
+        return;
+    }
+
+    if let Some(email) = event.email {
+        let app_handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            auto_backup_account(&app_handle, email).await;
+        });
+    }
+}
+
+/// 自动备份新登录的账户并刷新托盘菜单
+async fn auto_backup_account(app_handle: &AppHandle, email: String) {
+    let backup_email = email.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        crate::antigravity::backup::smart_backup_antigravity_account(&backup_email)
+    })
+    .await;
+
+    match result {
+        Ok(Ok((backup_name, is_overwrite))) => {
+            let action = if is_overwrite { "更新" } else { "创建" };
+            info!(target: "db_monitor::account", backup_name = %backup_name, action = %action, "✅ 自动备份完成");
+
+            let system_tray = app_handle.state::<SystemTrayManager>();
+            if let Err(e) = system_tray.update_menu(app_handle).await {
+                warn!(target: "db_monitor::account", "刷新托盘菜单失败: {}", e);
+            }
+        }
+        Ok(Err(e)) => error!(target: "db_monitor::account", "❌ 自动备份失败: {}", e),
+        Err(e) => error!(target: "db_monitor::account", "❌ 自动备份任务异常: {}", e),
+    }
+}
+
+/// 从登录状态 JSON 中提取身份信息，未登录时返回 `None`
+fn parse_identity(raw: &str) -> Option<AuthIdentity> {
+    let value: Value = serde_json::from_str(raw).ok()?;
+    let email = value.get("email")?.as_str()?.trim();
+    if email.is_empty() {
+        return None;
+    }
+
+    Some(AuthIdentity {
+        email: email.to_string(),
+        name: value.get("name").and_then(|v| v.as_str()).map(str::to_string),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_sign_ins_trigger_backup_and_restores_are_suppressed() {
+        let a = r#"{"email":"a@example.com","apiKey":"one"}"#;
+        let a_refreshed = r#"{"email":"a@example.com","apiKey":"two"}"#;
+        let b = r#"{"email":"b@example.com","apiKey":"three"}"#;
+
+        let kind = |old, new| derive_event(old, new).map(|(kind, _)| kind);
+        assert_eq!(kind(None, Some(a)), Some(AccountEventKind::SignedIn));
+        assert_eq!(kind(Some(a), Some(b)), Some(AccountEventKind::Changed));
+        assert_eq!(kind(Some(a), Some(a_refreshed)), Some(AccountEventKind::Refreshed));
+        assert!(!AccountEventKind::Refreshed.is_sign_in());
+        assert!(!AccountEventKind::SignedOut.is_sign_in());
+
+        assert!(!is_suppressed());
+        let guard = suppress_account_events();
+        assert!(is_suppressed());
+        drop(guard);
+        // 释放后仍在宽限期内
+        assert!(is_suppressed());
+    }
+}
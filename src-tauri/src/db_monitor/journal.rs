@@ -0,0 +1,293 @@
+//! 数据库变化日志
+//!
+//! 把监控到的每个字段变化（时间、key、变化类型、大小、值哈希、脱敏预览）
+//! 追加到本地 SQLite 日志库，按条数与保存天数自动清理，
+//! 便于在切换账户失败后回溯 Antigravity 在什么时间写了什么。
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use crate::path_utils::AppPaths;
+
+use super::patch::decode_and_redact;
+
+/// 日志文件名
+const JOURNAL_FILE_NAME: &str = "db_journal.sqlite";
+
+/// 最多保留的记录条数
+///
+/// 保留策略刻意不开放为设置项：日志只用于排查问题，不是用户数据；
+/// 条数与天数双重上限（预览最多 240 字符）让日志库大小始终有固定上界
+const MAX_JOURNAL_ENTRIES: i64 = 50_000;
+
+/// 最多保留的天数（理由同上）
+const MAX_JOURNAL_AGE_DAYS: i64 = 14;
+
+/// 预览文本的最大字符数
+const MAX_PREVIEW_CHARS: usize = 240;
+
+/// 每写入多少批次执行一次清理
+const PRUNE_EVERY_BATCHES: u64 = 20;
+
+/// 日志记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    pub id: i64,
+    /// 毫秒时间戳
+    pub timestamp: i64,
+    pub key: String,
+    /// added / removed / changed
+    pub change_type: String,
+    pub size: i64,
+    /// 新值哈希（删除时为空）
+    pub value_hash: Option<String>,
+    /// 脱敏后的新值预览
+    pub preview: Option<String>,
+}
+
+/// 待写入的变化
+pub struct JournalRecord<'a> {
+    pub key: &'a str,
+    pub change_type: &'a str,
+    pub size: usize,
+    /// 新值 SHA-256 的前 8 字节，记录为 16 位十六进制
+    pub value_hash: Option<u64>,
+    pub raw: Option<&'a str>,
+}
+
+/// 日志查询条件
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalQuery {
+    /// 起始时间（毫秒，含）
+    pub from: Option<i64>,
+    /// 结束时间（毫秒，含）
+    pub to: Option<i64>,
+    /// key 模式，支持 `*` 通配符，区分大小写（与订阅的匹配规则一致）
+    pub key: Option<String>,
+    pub limit: Option<u32>,
+}
+
+/// 变化日志
+pub struct ChangeJournal {
+    conn: Connection,
+    batches_written: u64,
+}
+
+impl ChangeJournal {
+    /// 打开（或创建）日志库
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS changes (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 timestamp INTEGER NOT NULL,
+                 key TEXT NOT NULL,
+                 change_type TEXT NOT NULL,
+                 size INTEGER NOT NULL,
+                 value_hash TEXT,
+                 preview TEXT
+             );
+             CREATE INDEX IF NOT EXISTS idx_changes_timestamp ON changes(timestamp);
+             CREATE INDEX IF NOT EXISTS idx_changes_key ON changes(key, timestamp);",
+        )?;
+
+        Ok(Self {
+            conn,
+            batches_written: 0,
+        })
+    }
+
+    /// 批量追加一次检测到的所有变化
+    pub fn append(&mut self, timestamp: i64, records: &[JournalRecord]) -> rusqlite::Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO changes (timestamp, key, change_type, size, value_hash, preview)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for record in records {
+                let preview = record.raw.map(|raw| build_preview(record.key, raw));
+                stmt.execute(params![
+                    timestamp,
+                    record.key,
+                    record.change_type,
+                    record.size as i64,
+                    record.value_hash.map(|h| format!("{:016x}", h)),
+                    preview,
+                ])?;
+            }
+        }
+        tx.commit()?;
+
+        self.batches_written += 1;
+        if self.batches_written % PRUNE_EVERY_BATCHES == 1 {
+            self.prune()?;
+        }
+        Ok(())
+    }
+
+    /// 按保存天数和条数清理旧记录
+    pub fn prune(&self) -> rusqlite::Result<usize> {
+        let cutoff = chrono::Local::now().timestamp_millis() - MAX_JOURNAL_AGE_DAYS * 24 * 3600 * 1000;
+        let by_age = self.conn.execute("DELETE FROM changes WHERE timestamp < ?1", [cutoff])?;
+        let by_count = self.conn.execute(
+            "DELETE FROM changes WHERE id <= (SELECT id FROM changes ORDER BY id DESC LIMIT 1 OFFSET ?1)",
+            [MAX_JOURNAL_ENTRIES],
+        )?;
+
+        let removed = by_age + by_count;
+        if removed > 0 {
+            tracing::debug!(target: "db_monitor::journal", removed, "清理变化日志");
+        }
+        Ok(removed)
+    }
+
+    /// 按时间范围与 key 查询（按时间倒序）
+    pub fn query(&self, query: &JournalQuery) -> rusqlite::Result<Vec<JournalEntry>> {
+        let key_pattern = query.key.as_deref().map(key_glob).unwrap_or_else(|| "*".to_string());
+        let limit = query.limit.unwrap_or(500).min(5000);
+
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, timestamp, key, change_type, size, value_hash, preview FROM changes
+             WHERE timestamp >= ?1 AND timestamp <= ?2 AND key GLOB ?3
+             ORDER BY timestamp DESC, id DESC LIMIT ?4",
+        )?;
+
+        let rows = stmt.query_map(
+            params![
+                query.from.unwrap_or(i64::MIN),
+                query.to.unwrap_or(i64::MAX),
+                key_pattern,
+                limit
+            ],
+            |row| {
+                Ok(JournalEntry {
+                    id: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    key: row.get(2)?,
+                    change_type: row.get(3)?,
+                    size: row.get(4)?,
+                    value_hash: row.get(5)?,
+                    preview: row.get(6)?,
+                })
+            },
+        )?;
+
+        rows.collect()
+    }
+
+    /// 清空日志
+    pub fn clear(&self) -> rusqlite::Result<usize> {
+        self.conn.execute("DELETE FROM changes", [])
+    }
+}
+
+/// 全局日志实例（打开失败时为 `None`，监控照常工作）
+static GLOBAL_JOURNAL: OnceLock<Option<Mutex<ChangeJournal>>> = OnceLock::new();
+
+/// 获取全局变化日志
+pub fn get_journal() -> Option<&'static Mutex<ChangeJournal>> {
+    GLOBAL_JOURNAL
+        .get_or_init(|| {
+            let config_dir = AppPaths::config_dir()?;
+            if let Err(e) = std::fs::create_dir_all(&config_dir) {
+                tracing::warn!(target: "db_monitor::journal", "创建配置目录失败: {}", e);
+            }
+            let path = config_dir.join(JOURNAL_FILE_NAME);
+            match ChangeJournal::open(&path) {
+                Ok(journal) => Some(Mutex::new(journal)),
+                Err(e) => {
+                    tracing::error!(target: "db_monitor::journal", "打开变化日志失败 {}: {}", path.display(), e);
+                    None
+                }
+            }
+        })
+        .as_ref()
+}
+
+/// 把 key 模式转换为 SQLite GLOB 模式：只保留 `*` 通配符，其余字符（`?`、`[`）按字面匹配
+///
+/// 不使用 LIKE：LIKE 对 ASCII 不区分大小写，而订阅的 `*` 匹配区分大小写
+fn key_glob(pattern: &str) -> String {
+    let mut glob = String::with_capacity(pattern.len());
+    for c in pattern.chars() {
+        match c {
+            '?' => glob.push_str("[?]"),
+            '[' => glob.push_str("[[]"),
+            c => glob.push(c),
+        }
+    }
+    glob
+}
+
+/// 脱敏并截断的值预览
+fn build_preview(key: &str, raw: &str) -> String {
+    let tree = decode_and_redact(key, raw);
+    let text = match tree {
+        serde_json::Value::String(s) => s,
+        other => other.to_string(),
+    };
+
+    if text.chars().count() > MAX_PREVIEW_CHARS {
+        let truncated: String = text.chars().take(MAX_PREVIEW_CHARS).collect();
+        format!("{}…", truncated)
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_filter_is_case_sensitive_and_only_expands_stars() {
+        let dir = std::env::temp_dir().join(format!("journal-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut journal = ChangeJournal::open(&dir.join(JOURNAL_FILE_NAME)).unwrap();
+
+        let keys = ["auth.Status", "auth.status", "auth_status", "auth%status", "auth\\status", "auth?status", "auth[1]"];
+        let records: Vec<JournalRecord> = keys
+            .iter()
+            .map(|key| JournalRecord {
+                key,
+                change_type: "added",
+                size: 0,
+                value_hash: None,
+                raw: None,
+            })
+            .collect();
+        journal.append(chrono::Local::now().timestamp_millis(), &records).unwrap();
+
+        let find = |pattern: &str| {
+            let mut keys: Vec<String> = journal
+                .query(&JournalQuery {
+                    key: Some(pattern.to_string()),
+                    ..Default::default()
+                })
+                .unwrap()
+                .into_iter()
+                .map(|e| e.key)
+                .collect();
+            keys.sort();
+            keys
+        };
+
+        assert_eq!(find("auth.status"), ["auth.status"]);
+        assert_eq!(find("auth.S*"), ["auth.Status"]);
+        assert_eq!(find("auth_status"), ["auth_status"]);
+        assert_eq!(find("auth%status"), ["auth%status"]);
+        assert_eq!(find("auth\\status"), ["auth\\status"]);
+        assert_eq!(find("auth?status"), ["auth?status"]);
+        assert_eq!(find("auth[1]"), ["auth[1]"]);
+        assert_eq!(find("auth*").len(), keys.len());
+
+        drop(journal);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
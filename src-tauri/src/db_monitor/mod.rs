@@ -0,0 +1,464 @@
+//! 数据库监控模块 - 事件驱动版本：按字段推送脱敏后的 JSON Patch
+//!
+//! 通过文件系统通知监听 `state.vscdb` 及其 `-wal` 文件，去抖后再读取数据库；
+//! 无法使用文件通知时回退到轮询。每次读取先用 `PRAGMA data_version` 判断
+//! 是否有其他连接提交过写入，再按字段哈希比较，只解析真正变化的字段。
+//! 数据库文件被替换（设备号、inode 变化或文件变小）时重新打开连接。
+//! SQLite 读取与字段哈希在阻塞线程中执行，不占用异步运行时。
+
+pub mod account_events;
+pub mod journal;
+pub mod patch;
+pub mod subscription;
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use rusqlite::{Connection, OpenFlags};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::AppHandle;
+use tokio::sync::oneshot::error::TryRecvError;
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::time::{interval, sleep, Duration};
+use tracing::{debug, info, warn};
+
+use crate::constants::database;
+use patch::KeyChange;
+use subscription::{Subscription, SubscriptionRegistry};
+
+/// 文件事件去抖时间（Antigravity 一次保存会连续触发多次写入）
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(500);
+
+/// 文件通知模式下的兜底检查间隔（防止漏掉事件）
+const SAFETY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 无法使用文件通知时的轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// 超过该大小的值只保留哈希，不保留原文（差异中不再提供旧值）
+const MAX_RETAINED_VALUE_BYTES: usize = 256 * 1024;
+
+/// 单个字段的快照：哈希 + （可选的）原始值
+#[derive(Debug, Clone)]
+struct ValueSnapshot {
+    hash: u64,
+    size: usize,
+    raw: Option<Arc<str>>,
+}
+
+impl ValueSnapshot {
+    fn new(raw: String) -> Self {
+        let hash = hash_value(&raw);
+        let size = raw.len();
+        let raw = (size <= MAX_RETAINED_VALUE_BYTES).then(|| Arc::from(raw));
+        Self { hash, size, raw }
+    }
+}
+
+/// 数据库快照：key -> 字段快照
+type DbSnapshot = HashMap<String, ValueSnapshot>;
+
+// 数据库监控器
+pub struct DatabaseMonitor {
+    app_handle: AppHandle,
+    last_data: Arc<Mutex<Option<DbSnapshot>>>,
+    /// 当前监控循环的停止信号；`stop_monitoring` 发送后旧循环立即退出，
+    /// 之后再启动的循环使用新的信号，不会让旧循环复活
+    stop_tx: Arc<Mutex<Option<oneshot::Sender<()>>>>,
+    subscriptions: Arc<Mutex<SubscriptionRegistry>>,
+}
+
+impl DatabaseMonitor {
+    /// 创建新的数据库监控器
+    pub fn new(app_handle: AppHandle) -> Self {
+        Self {
+            app_handle,
+            last_data: Arc::new(Mutex::new(None)),
+            stop_tx: Arc::new(Mutex::new(None)),
+            subscriptions: Arc::new(Mutex::new(SubscriptionRegistry::new())),
+        }
+    }
+
+    /// 启动数据库监控
+    pub async fn start_monitoring(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let db_path = crate::platform::get_antigravity_db_path()
+            .ok_or("未找到 Antigravity 数据库路径")?;
+
+        let mut stop_rx = {
+            let mut stop_tx = self.stop_tx.lock().await;
+            // 循环自行退出后接收端已释放，此时允许重新启动
+            if stop_tx.as_ref().is_some_and(|tx| !tx.is_closed()) {
+                info!("ℹ️ 数据库监控已在运行，跳过重复启动");
+                return Ok(());
+            }
+            let (tx, rx) = oneshot::channel();
+            *stop_tx = Some(tx);
+            rx
+        };
+
+        info!("🔧 启动数据库自动监控（事件驱动）");
+
+        let last_data = self.last_data.clone();
+        let app_handle = self.app_handle.clone();
+        let subscriptions = self.subscriptions.clone();
+
+        tokio::spawn(async move {
+            let (tx, mut rx) = mpsc::unbounded_channel::<()>();
+
+            // 文件监听器需要在整个循环期间存活
+            let watcher = match Self::create_watcher(&db_path, tx) {
+                Ok(w) => {
+                    info!("👀 已启用文件通知监听: {}", db_path.display());
+                    Some(w)
+                }
+                Err(e) => {
+                    warn!("⚠️ 文件通知不可用，回退到 {:?} 轮询: {}", POLL_INTERVAL, e);
+                    None
+                }
+            };
+
+            let mut poll = interval(POLL_INTERVAL);
+            let reader = Arc::new(std::sync::Mutex::new(DbReader::new(db_path)));
+
+            loop {
+                if watcher.is_some() {
+                    tokio::select! {
+                        _ = &mut stop_rx => break,
+                        signal = rx.recv() => {
+                            if signal.is_none() {
+                                break;
+                            }
+                            // 去抖：等待写入平静后再读取，并丢弃期间积累的事件
+                            sleep(DEBOUNCE_DELAY).await;
+                            while rx.try_recv().is_ok() {}
+                        }
+                        _ = sleep(SAFETY_CHECK_INTERVAL) => {}
+                    }
+                } else {
+                    tokio::select! {
+                        _ = &mut stop_rx => break,
+                        _ = poll.tick() => {}
+                    }
+                }
+
+                // 去抖期间可能已收到停止信号
+                if !matches!(stop_rx.try_recv(), Err(TryRecvError::Empty)) {
+                    break;
+                }
+
+                // 读取当前快照（数据库未变化时跳过）
+                let read = {
+                    let reader = reader.clone();
+                    tokio::task::spawn_blocking(move || {
+                        reader.lock().unwrap_or_else(|e| e.into_inner()).read_if_changed()
+                    })
+                    .await
+                };
+                let new_data = match read {
+                    Ok(Ok(Some(data))) => data,
+                    Ok(Ok(None)) => continue,
+                    Ok(Err(e)) => {
+                        warn!("⚠️ 读取数据库快照失败: {}", e);
+                        continue;
+                    }
+                    Err(e) => {
+                        warn!("⚠️ 读取数据库快照任务异常: {}", e);
+                        continue;
+                    }
+                };
+
+                let mut last = last_data.lock().await;
+
+                // 检查是否有数据变化
+                if let Some(ref old_data) = *last {
+                    let changed_keys = Self::changed_keys(old_data, &new_data);
+
+                    if !changed_keys.is_empty() {
+                        info!("📢 检测到数据库变化: {} fields changed", changed_keys.len());
+
+                        let changes = Self::build_changes(old_data, &new_data, &changed_keys);
+                        let timestamp = chrono::Local::now().timestamp_millis();
+                        subscriptions.lock().await.dispatch(&app_handle, timestamp, &changes);
+
+                        // 写入持久化变化日志
+                        Self::record_journal(&new_data, &changed_keys, timestamp).await;
+
+                        // 登录状态变化时推导账户语义事件
+                        if changed_keys.iter().any(|(key, _)| key == database::AUTH_STATUS) {
+                            account_events::handle_auth_change(
+                                &app_handle,
+                                old_data.get(database::AUTH_STATUS).and_then(|v| v.raw.as_deref()),
+                                new_data.get(database::AUTH_STATUS).and_then(|v| v.raw.as_deref()),
+                            );
+                        }
+                    }
+                }
+
+                *last = Some(new_data);
+            }
+
+            info!("⏹️ 数据库监控已停止");
+            drop(watcher);
+        });
+
+        Ok(())
+    }
+
+    /// 停止数据库监控
+    pub async fn stop_monitoring(&self) {
+        info!("⏹️ 停止数据库自动监控");
+        if let Some(tx) = self.stop_tx.lock().await.take() {
+            let _ = tx.send(());
+        }
+        *self.last_data.lock().await = None;
+    }
+
+    /// 新增按 key 模式过滤的订阅
+    pub async fn subscribe(&self, patterns: Vec<String>, max_payload_bytes: Option<usize>) -> Subscription {
+        let subscription = Subscription::new(patterns, max_payload_bytes);
+        info!("➕ 新增数据库变化订阅: {} {:?}", subscription.id, subscription.patterns);
+        self.subscriptions.lock().await.add(subscription.clone());
+        subscription
+    }
+
+    /// 取消订阅
+    pub async fn unsubscribe(&self, id: &str) -> bool {
+        self.subscriptions.lock().await.remove(id)
+    }
+
+    /// 列出所有订阅
+    pub async fn list_subscriptions(&self) -> Vec<Subscription> {
+        self.subscriptions.lock().await.list()
+    }
+
+    /// 创建文件监听器，监听数据库所在目录中的 `state.vscdb` 与 `state.vscdb-wal`
+    fn create_watcher(
+        db_path: &Path,
+        tx: mpsc::UnboundedSender<()>,
+    ) -> notify::Result<RecommendedWatcher> {
+        let file_name = db_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let wal_name = format!("{}-wal", file_name);
+
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            match res {
+                Ok(event) => {
+                    let relevant = event.paths.iter().any(|p| {
+                        p.file_name()
+                            .map(|n| n.to_string_lossy())
+                            .is_some_and(|n| n == file_name || n == wal_name)
+                    });
+                    if relevant {
+                        let _ = tx.send(());
+                    }
+                }
+                Err(e) => debug!("文件监听事件错误: {}", e),
+            }
+        })?;
+
+        // 监听目录而不是文件本身：-wal 文件会被反复创建和删除
+        let dir = db_path.parent().unwrap_or(db_path);
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+        Ok(watcher)
+    }
+
+    /// 找出哈希不同的字段，返回 (key, 变化类型)
+    fn changed_keys(old: &DbSnapshot, new: &DbSnapshot) -> Vec<(String, &'static str)> {
+        let mut changed = Vec::new();
+
+        for (key, new_value) in new {
+            match old.get(key) {
+                Some(old_value) if old_value.hash == new_value.hash => {}
+                Some(_) => changed.push((key.clone(), "changed")),
+                None => changed.push((key.clone(), "added")),
+            }
+        }
+
+        for key in old.keys() {
+            if !new.contains_key(key) {
+                changed.push((key.clone(), "removed"));
+            }
+        }
+
+        changed.sort();
+        changed
+    }
+
+    /// 把变化追加到持久化日志（在阻塞线程中写入，失败只记录警告，不影响监控）
+    async fn record_journal(new: &DbSnapshot, changed_keys: &[(String, &'static str)], timestamp: i64) {
+        let changes: Vec<(String, &'static str, Option<ValueSnapshot>)> = changed_keys
+            .iter()
+            .map(|(key, change_type)| (key.clone(), *change_type, new.get(key).cloned()))
+            .collect();
+
+        let result = tokio::task::spawn_blocking(move || {
+            let Some(journal) = journal::get_journal() else {
+                return Ok(());
+            };
+
+            let records: Vec<journal::JournalRecord> = changes
+                .iter()
+                .map(|(key, change_type, snapshot)| journal::JournalRecord {
+                    key,
+                    change_type,
+                    size: snapshot.as_ref().map(|v| v.size).unwrap_or(0),
+                    value_hash: snapshot.as_ref().map(|v| v.hash),
+                    raw: snapshot.as_ref().and_then(|v| v.raw.as_deref()),
+                })
+                .collect();
+
+            journal.lock().unwrap_or_else(|e| e.into_inner()).append(timestamp, &records)
+        })
+        .await;
+
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("⚠️ 写入数据库变化日志失败: {}", e),
+            Err(e) => warn!("⚠️ 写入数据库变化日志任务异常: {}", e),
+        }
+    }
+
+    /// 为变化的字段生成脱敏后的 patch（只解码发生变化的字段）
+    fn build_changes(
+        old: &DbSnapshot,
+        new: &DbSnapshot,
+        changed_keys: &[(String, &'static str)],
+    ) -> Vec<KeyChange> {
+        changed_keys
+            .iter()
+            .map(|(key, change_type)| {
+                let old_raw = old.get(key).and_then(|v| v.raw.as_deref());
+                let new_raw = new.get(key).and_then(|v| v.raw.as_deref());
+                KeyChange::build(key, *change_type, old_raw, new_raw)
+            })
+            .collect()
+    }
+}
+
+/// 数据库文件标识：设备号与 inode（Unix），变化说明文件被替换
+///
+/// 不比较修改时间：每次写入都会改变修改时间，会让每次读取都重新打开连接
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileIdentity {
+    dev: Option<u64>,
+    inode: Option<u64>,
+}
+
+impl FileIdentity {
+    /// 返回文件标识与当前大小
+    fn of(path: &Path) -> Option<(Self, u64)> {
+        let metadata = std::fs::metadata(path).ok()?;
+        #[cfg(unix)]
+        let (dev, inode) = {
+            use std::os::unix::fs::MetadataExt;
+            (Some(metadata.dev()), Some(metadata.ino()))
+        };
+        #[cfg(not(unix))]
+        let (dev, inode) = (None, None);
+        Some((Self { dev, inode }, metadata.len()))
+    }
+}
+
+/// 数据库读取器：复用只读连接，并通过 `data_version` 跳过未变化的读取
+struct DbReader {
+    db_path: PathBuf,
+    conn: Option<Connection>,
+    identity: Option<FileIdentity>,
+    /// 上次读取时的文件大小，变小说明文件被替换（也覆盖无法获取 inode 的平台）
+    last_size: u64,
+    last_version: Option<i64>,
+}
+
+impl DbReader {
+    fn new(db_path: PathBuf) -> Self {
+        Self {
+            db_path,
+            conn: None,
+            identity: None,
+            last_size: 0,
+            last_version: None,
+        }
+    }
+
+    /// 数据库有新提交时返回新快照，否则返回 `None`
+    fn read_if_changed(&mut self) -> Result<Option<DbSnapshot>, Box<dyn std::error::Error + Send + Sync>> {
+        if !self.db_path.exists() {
+            // 数据库被删除（如清除数据），下次重新打开
+            self.conn = None;
+            self.last_version = None;
+            return Ok(Some(HashMap::new()));
+        }
+
+        // 文件被替换后旧连接仍指向已删除的 inode，data_version 不会再变化
+        let current = FileIdentity::of(&self.db_path);
+        let identity = current.map(|(identity, _)| identity);
+        let size = current.map(|(_, size)| size).unwrap_or(0);
+        if self.conn.is_some() && (identity != self.identity || size < self.last_size) {
+            debug!("数据库文件已变化，重新打开连接: {}", self.db_path.display());
+            self.conn = None;
+        }
+        self.last_size = size;
+
+        if self.conn.is_none() {
+            let conn = Connection::open_with_flags(
+                &self.db_path,
+                OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            )?;
+            self.conn = Some(conn);
+            self.identity = identity;
+            self.last_version = None;
+        }
+        let conn = self.conn.as_ref().unwrap();
+
+        // data_version 只在其他连接提交写入后变化，开销极小
+        let version: i64 = match conn.query_row("PRAGMA data_version", [], |row| row.get(0)) {
+            Ok(v) => v,
+            Err(e) => {
+                self.conn = None;
+                return Err(e.into());
+            }
+        };
+        if self.last_version == Some(version) {
+            return Ok(None);
+        }
+
+        let snapshot = match Self::read_snapshot(conn) {
+            Ok(s) => s,
+            Err(e) => {
+                // 连接可能已失效（数据库文件被替换），下次重新打开
+                self.conn = None;
+                return Err(e);
+            }
+        };
+        self.last_version = Some(version);
+
+        Ok(Some(snapshot))
+    }
+
+    fn read_snapshot(conn: &Connection) -> Result<DbSnapshot, Box<dyn std::error::Error + Send + Sync>> {
+        let mut stmt = conn.prepare_cached("SELECT key, value FROM ItemTable")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+
+        let mut snapshot = HashMap::new();
+        for row in rows {
+            let (key, value) = row?;
+            snapshot.insert(key, ValueSnapshot::new(value));
+        }
+
+        Ok(snapshot)
+    }
+}
+
+/// 计算字段值的哈希：SHA-256 的前 8 字节
+///
+/// 哈希会写入持久化日志，必须跨版本、跨平台稳定，不能使用 `DefaultHasher`
+fn hash_value(value: &str) -> u64 {
+    let digest = Sha256::digest(value.as_bytes());
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(prefix)
+}
@@ -0,0 +1,258 @@
+//! 字段差异的 JSON Patch（RFC 6902）生成与脱敏
+//!
+//! 每个变化字段先经解码器还原成 JSON 树，再对敏感字段脱敏，最后生成 patch 操作。
+//! 脱敏后的值保留短哈希，前端仍能看出「令牌变了」，但看不到令牌本身。
+//!
+//! protobuf 解码后的字段名只是数字，无法按名称识别令牌，因此登录态相关字段（`AUTH_KEYS`）
+//! 下的所有字符串与字节叶子一律脱敏；其他字段中无法解码的字节和解码失败的原始值同样脱敏。
+
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::antigravity::value_decoder::{escape_pointer, get_decoder_registry};
+use crate::constants::database;
+
+/// 单个字段 patch 的最大操作数
+const MAX_OPS_PER_KEY: usize = 200;
+
+/// 名称中包含这些片段的字段会被脱敏（小写比较）
+const SENSITIVE_NAME_PARTS: &[&str] = &[
+    "token",
+    "apikey",
+    "api_key",
+    "secret",
+    "password",
+    "credential",
+    "csrf",
+    "authorization",
+    "cookie",
+];
+
+/// 值以这些前缀开头时视为令牌（Google OAuth / JWT）
+const SENSITIVE_VALUE_PREFIXES: &[&str] = &["ya29.", "1//", "eyJ"];
+
+/// 保存登录态的字段：内容不论能否解码、字段名是什么，所有字符串与字节都脱敏
+const AUTH_KEYS: &[&str] = &[database::AUTH_STATUS, database::AGENT_STATE, database::GOOGLE_DATA];
+
+/// 解码器输出的 protobuf 字段节点类型（`{"type": ..., "value" | "hex": ...}`）
+const WIRE_NODE_TYPES: &[&str] = &["varint", "fixed64", "fixed32", "string", "json", "message", "bytes"];
+
+/// RFC 6902 patch 操作
+#[derive(Debug, Clone, Serialize)]
+pub struct PatchOp {
+    pub op: &'static str,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+/// 单个数据库字段的变化
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyChange {
+    pub key: String,
+    /// added / removed / changed
+    pub change_type: &'static str,
+    /// 新值大小（字节），删除时为 0
+    pub size: usize,
+    /// 相对于字段旧值的 patch；字段新增/删除时作用于根路径
+    pub patch: Vec<PatchOp>,
+    /// patch 因超出大小限制被省略
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+impl KeyChange {
+    /// 构建字段变化：解码、脱敏并生成 patch
+    pub fn build(key: &str, change_type: &'static str, old_raw: Option<&str>, new_raw: Option<&str>) -> Self {
+        let old_tree = old_raw.map(|raw| decode_and_redact(key, raw));
+        let new_tree = new_raw.map(|raw| decode_and_redact(key, raw));
+
+        let (patch, truncated) = match (&old_tree, &new_tree) {
+            (Some(old), Some(new)) => (json_patch(old, new), false),
+            (None, Some(new)) if change_type == "added" => {
+                (vec![PatchOp { op: "add", path: String::new(), value: Some(new.clone()) }], false)
+            }
+            _ if change_type == "removed" => {
+                (vec![PatchOp { op: "remove", path: String::new(), value: None }], false)
+            }
+            // 新值或旧值超出保留上限，无法生成 patch
+            _ => (Vec::new(), true),
+        };
+
+        Self {
+            key: key.to_string(),
+            change_type,
+            size: new_raw.map(str::len).unwrap_or(0),
+            patch,
+            truncated,
+        }
+    }
+
+    /// 丢弃 patch 内容，只保留摘要
+    pub fn truncate(&mut self) {
+        self.patch.clear();
+        self.truncated = true;
+    }
+}
+
+/// 使用注册的解码器解码并脱敏
+pub fn decode_and_redact(key: &str, raw: &str) -> Value {
+    let registry = get_decoder_registry();
+    let decoded = registry.decode(key, raw);
+    let mut tree = decoded.tree;
+
+    // 注册了解码器却解码失败：原始值是不透明数据，整体脱敏
+    if decoded.decoder.is_none() && registry.has_decoder(key) {
+        return Value::String(redacted_marker(raw));
+    }
+
+    redact(&mut tree, AUTH_KEYS.contains(&key) || is_sensitive_name(key));
+    tree
+}
+
+/// 递归脱敏：敏感字段名下的所有字符串、形似令牌的字符串以及无法解码的字节都替换为短哈希
+///
+/// protobuf 字段节点中描述结构的 `type` / `length` 保留，便于看出哪个字段变了
+pub fn redact(value: &mut Value, inherited_sensitive: bool) {
+    match value {
+        Value::Object(map) => {
+            let wire_node = is_wire_node(map);
+            for (name, child) in map.iter_mut() {
+                match name.as_str() {
+                    "type" | "length" if wire_node => {}
+                    // 无法解码的字节内容不透明，无论是否敏感都脱敏
+                    "hex" => redact(child, true),
+                    _ => redact(child, inherited_sensitive || is_sensitive_name(name)),
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                redact(item, inherited_sensitive);
+            }
+        }
+        Value::String(s) => {
+            if inherited_sensitive || looks_like_token(s) {
+                *s = redacted_marker(s);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 生成 RFC 6902 patch（old -> new）
+pub fn json_patch(old: &Value, new: &Value) -> Vec<PatchOp> {
+    let mut ops = Vec::new();
+    patch_recursive("", old, new, &mut ops);
+    ops.truncate(MAX_OPS_PER_KEY);
+    ops
+}
+
+fn patch_recursive(path: &str, old: &Value, new: &Value, ops: &mut Vec<PatchOp>) {
+    if old == new || ops.len() >= MAX_OPS_PER_KEY {
+        return;
+    }
+
+    match (old, new) {
+        (Value::Object(old_obj), Value::Object(new_obj)) => {
+            for (key, old_value) in old_obj {
+                let child = format!("{}/{}", path, escape_pointer(key));
+                match new_obj.get(key) {
+                    Some(new_value) => patch_recursive(&child, old_value, new_value, ops),
+                    None => ops.push(PatchOp { op: "remove", path: child, value: None }),
+                }
+            }
+            for (key, new_value) in new_obj {
+                if !old_obj.contains_key(key) {
+                    let child = format!("{}/{}", path, escape_pointer(key));
+                    ops.push(PatchOp { op: "add", path: child, value: Some(new_value.clone()) });
+                }
+            }
+        }
+        (Value::Array(old_arr), Value::Array(new_arr)) => {
+            let common = old_arr.len().min(new_arr.len());
+            for i in 0..common {
+                patch_recursive(&format!("{}/{}", path, i), &old_arr[i], &new_arr[i], ops);
+            }
+            // 删除必须从尾部开始，保证索引在依次应用时仍然有效
+            for i in (common..old_arr.len()).rev() {
+                ops.push(PatchOp { op: "remove", path: format!("{}/{}", path, i), value: None });
+            }
+            for item in new_arr.iter().skip(common) {
+                ops.push(PatchOp { op: "add", path: format!("{}/-", path), value: Some(item.clone()) });
+            }
+        }
+        _ => ops.push(PatchOp { op: "replace", path: path.to_string(), value: Some(new.clone()) }),
+    }
+}
+
+fn is_wire_node(map: &serde_json::Map<String, Value>) -> bool {
+    map.get("type")
+        .and_then(Value::as_str)
+        .is_some_and(|t| WIRE_NODE_TYPES.contains(&t))
+}
+
+fn is_sensitive_name(name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    SENSITIVE_NAME_PARTS.iter().any(|part| lower.contains(part))
+}
+
+fn looks_like_token(s: &str) -> bool {
+    s.len() >= 20 && SENSITIVE_VALUE_PREFIXES.iter().any(|prefix| s.starts_with(prefix))
+}
+
+fn redacted_marker(s: &str) -> String {
+    let digest = Sha256::digest(s.as_bytes());
+    format!("[REDACTED:{:02x}{:02x}{:02x}{:02x}]", digest[0], digest[1], digest[2], digest[3])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+    const TOKEN: &str = "opaque-refresh-credential-0123456789";
+
+    /// 长度分隔字段（字段号 < 16）
+    fn field(number: u8, payload: &[u8]) -> Vec<u8> {
+        let mut out = vec![(number << 3) | 2, payload.len() as u8];
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn redacts_tokens_nested_in_protobuf_auth_state() {
+        // 1: { 2: "<token>" }，3: 含令牌的不可解码字节，4: varint 1
+        let mut undecodable = vec![0xff, 0x00];
+        undecodable.extend_from_slice(TOKEN.as_bytes());
+        let mut blob = field(1, &field(2, TOKEN.as_bytes()));
+        blob.extend(field(3, &undecodable));
+        blob.extend([0x20, 0x01]);
+
+        let tree = decode_and_redact(database::AGENT_STATE, &STANDARD.encode(&blob));
+        let json = tree.to_string();
+
+        assert!(!json.contains(TOKEN), "{json}");
+        assert!(!json.contains(&to_hex(TOKEN.as_bytes())), "{json}");
+        assert_eq!(tree["1"][0]["value"]["2"][0]["type"], "string");
+        assert_eq!(tree["4"][0]["value"], 1);
+    }
+
+    #[test]
+    fn redacts_undecodable_values_and_bytes_of_other_keys() {
+        let tree = decode_and_redact(database::AGENT_STATE, &format!("{{{}", TOKEN));
+        assert!(tree.as_str().unwrap().starts_with("[REDACTED:"));
+
+        let mut undecodable = vec![0xff, 0x00];
+        undecodable.extend_from_slice(TOKEN.as_bytes());
+        let tree = decode_and_redact(database::USER_SETTINGS, &STANDARD.encode(field(5, &undecodable)));
+        assert!(!tree.to_string().contains(&to_hex(TOKEN.as_bytes())));
+        assert_eq!(tree["5"][0]["type"], "bytes");
+    }
+}
@@ -0,0 +1,157 @@
+//! 数据库变化订阅
+//!
+//! 前端按 key 模式订阅（支持 `*` 通配符），每个订阅有独立的事件名与负载上限，
+//! 例如只监听 `antigravityAuthStatus` 或 `antigravity.notification.*`。
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::{AppHandle, Emitter};
+use tracing::{debug, error};
+
+use super::patch::KeyChange;
+
+/// 默认事件负载上限（序列化后的字节数）
+pub const DEFAULT_MAX_PAYLOAD_BYTES: usize = 64 * 1024;
+
+/// 未订阅时使用的默认事件名（保持与旧版本兼容）
+pub const DEFAULT_EVENT_NAME: &str = "database-changed";
+
+static NEXT_SUBSCRIPTION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// 订阅配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Subscription {
+    pub id: String,
+    /// 推送的事件名：`database-changed:<id>`
+    pub event_name: String,
+    /// key 匹配模式，空列表表示匹配全部
+    pub patterns: Vec<String>,
+    pub max_payload_bytes: usize,
+}
+
+impl Subscription {
+    /// 创建新的订阅
+    pub fn new(patterns: Vec<String>, max_payload_bytes: Option<usize>) -> Self {
+        let id = format!("sub-{}", NEXT_SUBSCRIPTION_ID.fetch_add(1, Ordering::Relaxed));
+        Self {
+            event_name: format!("{}:{}", DEFAULT_EVENT_NAME, id),
+            id,
+            patterns,
+            max_payload_bytes: max_payload_bytes.unwrap_or(DEFAULT_MAX_PAYLOAD_BYTES),
+        }
+    }
+
+    /// 默认订阅：匹配全部字段，使用旧的事件名
+    pub fn default_subscription() -> Self {
+        Self {
+            id: "default".to_string(),
+            event_name: DEFAULT_EVENT_NAME.to_string(),
+            patterns: Vec::new(),
+            max_payload_bytes: DEFAULT_MAX_PAYLOAD_BYTES,
+        }
+    }
+
+    /// key 是否匹配该订阅
+    pub fn matches(&self, key: &str) -> bool {
+        self.patterns.is_empty() || self.patterns.iter().any(|p| glob_match(p, key))
+    }
+}
+
+/// 订阅表
+#[derive(Default)]
+pub struct SubscriptionRegistry {
+    subscriptions: HashMap<String, Subscription>,
+}
+
+impl SubscriptionRegistry {
+    pub fn new() -> Self {
+        let mut registry = Self::default();
+        let default = Subscription::default_subscription();
+        registry.subscriptions.insert(default.id.clone(), default);
+        registry
+    }
+
+    pub fn add(&mut self, subscription: Subscription) {
+        self.subscriptions.insert(subscription.id.clone(), subscription);
+    }
+
+    pub fn remove(&mut self, id: &str) -> bool {
+        self.subscriptions.remove(id).is_some()
+    }
+
+    pub fn list(&self) -> Vec<Subscription> {
+        self.subscriptions.values().cloned().collect()
+    }
+
+    /// 按订阅过滤变化并推送事件
+    pub fn dispatch(&self, app_handle: &AppHandle, timestamp: i64, changes: &[KeyChange]) {
+        for subscription in self.subscriptions.values() {
+            let matched: Vec<KeyChange> = changes
+                .iter()
+                .filter(|c| subscription.matches(&c.key))
+                .cloned()
+                .collect();
+            if matched.is_empty() {
+                continue;
+            }
+
+            let payload = build_bounded_payload(timestamp, matched, subscription.max_payload_bytes);
+            match app_handle.emit(&subscription.event_name, &payload) {
+                Ok(_) => debug!("✅ 推送数据库变化事件: {}", subscription.event_name),
+                Err(e) => error!("❌ 推送数据库变化事件失败 ({}): {}", subscription.event_name, e),
+            }
+        }
+    }
+}
+
+/// 构建不超过上限的事件负载：按顺序保留完整 patch，超出部分只保留摘要
+fn build_bounded_payload(timestamp: i64, mut changes: Vec<KeyChange>, max_bytes: usize) -> serde_json::Value {
+    let mut used = 0usize;
+    for change in changes.iter_mut() {
+        let size = serde_json::to_vec(change).map(|v| v.len()).unwrap_or(usize::MAX);
+        if used.saturating_add(size) > max_bytes {
+            change.truncate();
+        } else {
+            used += size;
+        }
+    }
+
+    let truncated = changes.iter().any(|c| c.truncated);
+    json!({
+        "timestamp": timestamp,
+        "changes": changes,
+        "truncated": truncated,
+    })
+}
+
+/// 简单的 `*` 通配符匹配
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+
+    let mut rest = text;
+    for (i, part) in parts.iter().enumerate() {
+        if part.is_empty() {
+            continue;
+        }
+        if i == 0 {
+            match rest.strip_prefix(part) {
+                Some(r) => rest = r,
+                None => return false,
+            }
+        } else if i == parts.len() - 1 {
+            return rest.ends_with(part);
+        } else {
+            match rest.find(part) {
+                Some(pos) => rest = &rest[pos + part.len()..],
+                None => return false,
+            }
+        }
+    }
+    true
+}
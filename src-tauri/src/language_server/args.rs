@@ -0,0 +1,191 @@
+//! 语言服务器命令行参数解析
+//!
+//! 语言服务器使用 Go 的 flag 包：参数可写成 `--flag value`、`--flag=value`，
+//! 单横线 `-flag` 与双横线等价，布尔参数不带值（或写成 `--flag=false`）。
+//! 这里按 argv 逐个解析，而不是在拼接后的字符串上做正则匹配，
+//! 已知参数放入对应字段，其余参数原样保留在 `unknown` 中；
+//! 名称像凭据的未知参数在序列化和调试输出时只保留名称，值被替换为占位符。
+
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+
+/// 不带值的布尔参数（解析时不会吞掉下一个参数）
+const BOOL_FLAGS: &[&str] = &["enable_lsp", "random_port", "detect_proxy", "run_child"];
+
+/// 参数名包含这些片段时视为凭据（忽略大小写）
+const SENSITIVE_FLAG_PARTS: &[&str] = &["token", "key", "secret", "csrf", "password"];
+
+/// 凭据参数值的占位符
+const REDACTED_VALUE: &str = "[REDACTED]";
+
+/// 语言服务器命令行参数
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageServerArgs {
+    /// 可执行文件路径（argv[0]）
+    pub program: String,
+    /// CSRF Token，不返回给前端
+    #[serde(skip_serializing)]
+    pub csrf_token: Option<String>,
+    /// 部分版本使用的 CSRF Token 别名，不返回给前端
+    #[serde(skip_serializing)]
+    pub manager_token: Option<String>,
+    pub extension_server_port: Option<u16>,
+    pub api_server_port: Option<u16>,
+    pub server_port: Option<u16>,
+    pub lsp_port: Option<u16>,
+    pub workspace_id: Option<String>,
+    pub cloud_code_endpoint: Option<String>,
+    pub app_data_dir: Option<String>,
+    pub parent_pipe_path: Option<String>,
+    pub enable_lsp: bool,
+    pub random_port: bool,
+    /// 未识别的参数（布尔参数的值为 `None`，序列化时隐藏凭据参数的值）
+    #[serde(serialize_with = "serialize_unknown")]
+    pub unknown: BTreeMap<String, Option<String>>,
+    /// 非参数形式的位置参数
+    pub positional: Vec<String>,
+}
+
+impl LanguageServerArgs {
+    /// 解析 argv（第一个元素为可执行文件）
+    pub fn parse<S: AsRef<str>>(argv: &[S]) -> Self {
+        let mut args = Self::default();
+        let mut iter = argv.iter().map(|a| a.as_ref());
+
+        if let Some(program) = iter.next() {
+            args.program = program.to_string();
+        }
+
+        let rest: Vec<&str> = iter.collect();
+        let mut i = 0;
+        while i < rest.len() {
+            let arg = rest[i];
+            i += 1;
+
+            let Some(flag) = arg.strip_prefix("--").or_else(|| arg.strip_prefix('-')) else {
+                args.positional.push(arg.to_string());
+                continue;
+            };
+            if flag.is_empty() {
+                // `--` 之后全部视为位置参数
+                args.positional.extend(rest[i..].iter().map(|s| s.to_string()));
+                break;
+            }
+
+            let (name, value) = match flag.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None if BOOL_FLAGS.contains(&flag) => (flag, None),
+                None => match rest.get(i) {
+                    Some(next) if !next.starts_with('-') => {
+                        i += 1;
+                        (flag, Some(next.to_string()))
+                    }
+                    _ => (flag, None),
+                },
+            };
+
+            args.set(name, value);
+        }
+
+        args
+    }
+
+    /// 实际使用的 CSRF Token（`--csrf_token` 优先，其次 `--manager_token`）
+    pub fn token(&self) -> Option<&str> {
+        self.csrf_token
+            .as_deref()
+            .or(self.manager_token.as_deref())
+            .filter(|t| !t.is_empty())
+    }
+
+    /// 隐藏凭据参数值后的未识别参数（用于日志与调试输出）
+    pub fn redacted_unknown(&self) -> BTreeMap<String, Option<String>> {
+        self.unknown
+            .iter()
+            .map(|(name, value)| (name.clone(), redact_flag_value(name, value)))
+            .collect()
+    }
+
+    fn set(&mut self, name: &str, value: Option<String>) {
+        let port = |value: &Option<String>| value.as_deref().and_then(|v| v.trim().parse::<u16>().ok());
+        let flag = |value: &Option<String>| !matches!(value.as_deref(), Some("false" | "0"));
+
+        match name {
+            "csrf_token" => self.csrf_token = value,
+            "manager_token" => self.manager_token = value,
+            "extension_server_port" => self.extension_server_port = port(&value),
+            "api_server_port" => self.api_server_port = port(&value),
+            "server_port" => self.server_port = port(&value),
+            "lsp_port" => self.lsp_port = port(&value),
+            "workspace_id" => self.workspace_id = value,
+            "cloud_code_endpoint" => self.cloud_code_endpoint = value,
+            "app_data_dir" => self.app_data_dir = value,
+            "parent_pipe_path" => self.parent_pipe_path = value,
+            "enable_lsp" => self.enable_lsp = flag(&value),
+            "random_port" => self.random_port = flag(&value),
+            _ => {
+                self.unknown.insert(name.to_string(), value);
+            }
+        }
+    }
+}
+
+fn is_sensitive_flag(name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    SENSITIVE_FLAG_PARTS.iter().any(|part| lower.contains(part))
+}
+
+fn redact_flag_value(name: &str, value: &Option<String>) -> Option<String> {
+    match value {
+        Some(_) if is_sensitive_flag(name) => Some(REDACTED_VALUE.to_string()),
+        _ => value.clone(),
+    }
+}
+
+fn serialize_unknown<S: Serializer>(
+    unknown: &BTreeMap<String, Option<String>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(unknown.iter().map(|(name, value)| (name, redact_flag_value(name, value))))
+}
+
+/// 把单个命令行字符串拆成 argv（用于只能拿到整行命令的平台）
+///
+/// 支持双引号/单引号包裹和反斜杠转义的引号；无法还原原本未加引号的含空格路径
+pub fn split_command_line(cmdline: &str) -> Vec<String> {
+    let mut argv = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut quote: Option<char> = None;
+    let mut chars = cmdline.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\\', _) if matches!(chars.peek(), Some('"') | Some('\'')) => {
+                current.push(chars.next().unwrap_or(c));
+                in_token = true;
+            }
+            ('"' | '\'', None) => {
+                quote = Some(c);
+                in_token = true;
+            }
+            (c, Some(q)) if c == q => quote = None,
+            (c, None) if c.is_whitespace() => {
+                if in_token {
+                    argv.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            (c, _) => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+    if in_token {
+        argv.push(current);
+    }
+
+    argv
+}
@@ -1,20 +1,94 @@
 //! 语言服务器缓存系统
 //!
 //! 提供 CSRF Token 和端口信息的缓存管理
-//! 使用 moka 实现异步缓存，手动管理失效
-
-use std::sync::Arc;
+//! 使用 moka 存储，失效由缓存自身管理：
+//! - 每个条目记录所属语言服务器进程的 PID 与启动时间，每次读取时检查进程是否仍是同一个，
+//!   进程退出或重启（如切换账户后）立即失效，而不是等待固定 TTL
+//! - 无法关联进程的条目（日志中的端口）仍按 `CacheConfig::ttl_seconds` 失效
+//!
+//! 同时运行多个语言服务器实例时，实例列表缓存在 `instances` 下，并记录检测时的
+//! `language_server*` 进程集合；进程集合变化（如新开窗口启动了新实例）时重新检测，
+//! 每个实例的 Token 与已验证端口分别缓存在 `csrf_token:<实例>` / `ports_info:<实例>` 下。
+//! 最新实例的身份发生变化时推送 `language-server-identity-changed` 事件。
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use anyhow::{Result, anyhow};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use super::cmdline_detector::{
+    language_server_identities, CmdLineDetector, ProcessInfo, ProcessSource, SystemProcessSource,
+};
+use super::types::{PortInfo, CacheStats, CacheConfig, CacheEntryInfo, MemoryScanOptions, ServerIdentity};
+
+/// 命令行中找不到 Token 时内存扫描的总时长上限
+const MEMORY_SCAN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 语言服务器身份变化事件名
+pub const IDENTITY_CHANGED_EVENT: &str = "language-server-identity-changed";
+
+/// 带所属进程标记的缓存条目
+#[derive(Debug, Clone)]
+struct CacheEntry<T> {
+    value: T,
+    owners: Vec<ServerIdentity>,
+    inserted_at: Instant,
+}
 
-use super::types::{PortInfo, CacheStats, CacheConfig};
-use super::utils::{find_latest_antigravity_log, parse_ports_from_log};
+impl<T> CacheEntry<T> {
+    fn new(value: T, owners: Vec<ServerIdentity>) -> Self {
+        Self {
+            value,
+            owners,
+            inserted_at: Instant::now(),
+        }
+    }
+
+    fn info(&self, key: &str) -> CacheEntryInfo {
+        CacheEntryInfo {
+            key: key.to_string(),
+            owners: self.owners.clone(),
+            age_secs: self.inserted_at.elapsed().as_secs(),
+        }
+    }
+}
+
+/// 缓存的实例列表
+#[derive(Debug, Clone)]
+struct InstanceList {
+    instances: Vec<ProcessInfo>,
+    /// 检测时存在的 `language_server*` 进程（按 PID 排序）
+    process_set: Vec<ServerIdentity>,
+}
+
+/// 身份变化事件载荷
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdentityChangedEvent {
+    pub previous: Option<ServerIdentity>,
+    pub current: Option<ServerIdentity>,
+    pub timestamp: i64,
+}
 
 /// 缓存管理器
 pub struct CacheManager {
-    /// CSRF Token 缓存 (手动管理失效)
-    csrf_cache: Arc<moka::future::Cache<String, String>>,
-    /// 端口信息缓存 (手动管理失效)
-    ports_cache: Arc<moka::future::Cache<String, PortInfo>>,
+    /// CSRF Token 缓存
+    csrf_cache: Arc<moka::future::Cache<String, CacheEntry<String>>>,
+    /// 端口信息缓存
+    ports_cache: Arc<moka::future::Cache<String, CacheEntry<PortInfo>>>,
+    /// 语言服务器实例列表缓存
+    instances_cache: Arc<moka::future::Cache<String, CacheEntry<InstanceList>>>,
+    /// 无法关联进程的条目有效期
+    untagged_ttl: Duration,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    invalidations: AtomicU64,
+    /// 最近一次观察到的最新实例身份
+    last_identity: Mutex<Option<ServerIdentity>>,
+    /// 实例检测与存活检查使用的进程信息来源
+    source: Arc<dyn ProcessSource>,
 }
 
 impl CacheManager {
@@ -24,62 +98,271 @@ impl CacheManager {
     }
 
     pub fn with_config(config: CacheConfig) -> Self {
+        Self::with_source(config, Arc::new(SystemProcessSource))
+    }
+
+    /// 使用指定的进程信息来源（测试中传入伪造进程）
+    pub fn with_source(config: CacheConfig, source: Arc<dyn ProcessSource>) -> Self {
         Self {
-            csrf_cache: Arc::new(
-                moka::future::CacheBuilder::new(config.max_cache_entries)
-                    .time_to_live(std::time::Duration::from_secs(config.ttl_seconds))
-                    .build()
-            ),
-            ports_cache: Arc::new(
-                moka::future::CacheBuilder::new(config.max_cache_entries)
-                    .time_to_live(std::time::Duration::from_secs(config.ttl_seconds))
-                    .build()
-            ),
+            csrf_cache: Arc::new(moka::future::CacheBuilder::new(config.max_cache_entries).build()),
+            ports_cache: Arc::new(moka::future::CacheBuilder::new(config.max_cache_entries).build()),
+            instances_cache: Arc::new(moka::future::CacheBuilder::new(1).build()),
+            untagged_ttl: Duration::from_secs(config.ttl_seconds),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
+            last_identity: Mutex::new(None),
+            source,
         }
     }
 
+    /// 进程信息来源
+    pub fn source(&self) -> &Arc<dyn ProcessSource> {
+        &self.source
+    }
+
+    /// 在阻塞线程中查询进程信息来源（系统实现会同步刷新进程快照，不能在异步任务中直接调用）
+    async fn query_source<R, F>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&dyn ProcessSource) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let source = self.source.clone();
+        tokio::task::spawn_blocking(move || f(source.as_ref()))
+            .await
+            .inspect_err(|e| tracing::warn!("查询进程信息任务异常: {}", e))
+            .ok()
+    }
+
     /// 获取 CSRF token (如果有效)
     pub async fn get_csrf_token(&self, cache_key: &str) -> Option<String> {
-        self.csrf_cache.get(cache_key).await
+        let entry = self.csrf_cache.get(cache_key).await;
+        match self.validate(entry).await {
+            Some(value) => Some(value),
+            None => {
+                self.csrf_cache.invalidate(cache_key).await;
+                None
+            }
+        }
     }
 
-    /// 缓存 CSRF token
+    /// 缓存 CSRF token（无法关联进程，按 TTL 失效）
     pub async fn set_csrf_token(&self, cache_key: &str, token: String) {
-        self.csrf_cache.insert(cache_key.to_string(), token).await;
+        self.set_csrf_token_for(cache_key, token, None).await;
+    }
+
+    /// 缓存 CSRF token，并标记所属进程
+    pub async fn set_csrf_token_for(&self, cache_key: &str, token: String, owner: Option<ServerIdentity>) {
+        let entry = CacheEntry::new(token, owner.into_iter().collect());
+        self.csrf_cache.insert(cache_key.to_string(), entry).await;
         tracing::info!("CSRF token 已缓存");
     }
 
     /// 获取端口信息 (如果有效)
     pub async fn get_ports(&self, cache_key: &str) -> Option<PortInfo> {
-        self.ports_cache.get(cache_key).await
+        let entry = self.ports_cache.get(cache_key).await;
+        match self.validate(entry).await {
+            Some(value) => Some(value),
+            None => {
+                self.ports_cache.invalidate(cache_key).await;
+                None
+            }
+        }
     }
 
-    /// 缓存端口信息
+    /// 缓存端口信息（无法关联进程，按 TTL 失效）
     pub async fn set_ports(&self, cache_key: &str, ports: PortInfo) {
-        self.ports_cache.insert(cache_key.to_string(), ports).await;
+        self.set_ports_for(cache_key, ports, None).await;
+    }
+
+    /// 缓存端口信息，并标记所属进程
+    pub async fn set_ports_for(&self, cache_key: &str, ports: PortInfo, owner: Option<ServerIdentity>) {
+        let entry = CacheEntry::new(ports, owner.into_iter().collect());
+        self.ports_cache.insert(cache_key.to_string(), entry).await;
         tracing::info!("端口信息已缓存");
     }
 
-    
+    /// 删除单个端口条目（端口失效但进程仍在运行时使用）
+    pub async fn invalidate_ports(&self, cache_key: &str) {
+        self.ports_cache.invalidate(cache_key).await;
+    }
+
+    /// 获取实例列表 (所有实例仍在运行且没有新的语言服务器进程时有效)
+    pub async fn get_instances(&self) -> Option<Vec<ProcessInfo>> {
+        let current = self.query_source(language_server_identities).await?;
+        let entry = self
+            .instances_cache
+            .get(INSTANCES_CACHE_KEY)
+            .await
+            .filter(|entry| {
+                let unchanged = entry.value.process_set == current;
+                if !unchanged {
+                    tracing::info!(
+                        cached = entry.value.process_set.len(),
+                        current = current.len(),
+                        "语言服务器进程集合已变化，重新检测实例"
+                    );
+                    self.invalidations.fetch_add(1, Ordering::Relaxed);
+                }
+                unchanged
+            });
+        match self.validate(entry).await {
+            Some(value) => Some(value.instances),
+            None => {
+                self.instances_cache.invalidate(INSTANCES_CACHE_KEY).await;
+                None
+            }
+        }
+    }
+
+    /// 缓存实例列表，以所有实例作为所属进程
+    ///
+    /// `process_set` 为检测前采集的 `language_server*` 进程集合，之后集合变化即视为失效
+    pub async fn set_instances(&self, instances: Vec<ProcessInfo>, process_set: Vec<ServerIdentity>) {
+        let owners = instances.iter().map(ProcessInfo::identity).collect();
+        let list = InstanceList { instances, process_set };
+        self.instances_cache
+            .insert(INSTANCES_CACHE_KEY.to_string(), CacheEntry::new(list, owners))
+            .await;
+        tracing::info!("实例列表已缓存");
+    }
+
+    /// 获取所有语言服务器实例（带自动缓存，按启动时间倒序）
+    ///
+    /// 同时按实例缓存各自的 CSRF Token
+    pub async fn instances(&self) -> Result<Vec<ProcessInfo>> {
+        if let Some(instances) = self.get_instances().await {
+            return Ok(instances);
+        }
+
+        tracing::info!("缓存未命中，开始检测语言服务器实例");
+        let source = self.source.clone();
+        let (process_set, detected) = tokio::task::spawn_blocking(move || {
+            // 在检测前采集进程集合：检测期间新启动的实例会在下次读取时触发重新检测
+            let process_set = language_server_identities(source.as_ref());
+            (process_set, CmdLineDetector::with_source(source).detect_all_instances())
+        }).await?;
+
+        let instances = match detected {
+            Ok(instances) => instances,
+            Err(e) => {
+                self.observe_identity(None);
+                return Err(e);
+            }
+        };
+
+        tracing::info!("✅ 检测到 {} 个语言服务器实例", instances.len());
+
+        for instance in &instances {
+            self.set_csrf_token_for(&instance_csrf_key(&instance.id), instance.csrf_token.clone(), Some(instance.identity()))
+                .await;
+        }
+        self.observe_identity(instances.first().map(ProcessInfo::identity));
+        self.set_instances(instances.clone(), process_set).await;
+
+        Ok(instances)
+    }
+
     /// 清空所有缓存
     pub fn clear_all(&self) {
         self.csrf_cache.invalidate_all();
         self.ports_cache.invalidate_all();
+        self.instances_cache.invalidate_all();
         tracing::info!("所有缓存已清空");
     }
 
     /// 获取缓存统计信息
     pub fn get_stats(&self) -> CacheStats {
+        let mut entries: Vec<CacheEntryInfo> = Vec::new();
+        entries.extend(self.csrf_cache.iter().map(|(k, v)| v.info(&k)));
+        entries.extend(self.ports_cache.iter().map(|(k, v)| v.info(&k)));
+        entries.extend(self.instances_cache.iter().map(|(k, v)| v.info(&k)));
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+
         CacheStats {
             csrf_cache_size: self.csrf_cache.entry_count(),
             ports_cache_size: self.ports_cache.entry_count(),
+            instances_cache_size: self.instances_cache.entry_count(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+            entries,
         }
     }
+
+    /// 校验条目：所属进程全部仍在运行（且未重启），或无所属进程且未超过 TTL
+    async fn validate<T>(&self, entry: Option<CacheEntry<T>>) -> Option<T> {
+        let Some(entry) = entry else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+
+        let valid = if entry.owners.is_empty() {
+            entry.inserted_at.elapsed() < self.untagged_ttl
+        } else {
+            let owners = entry.owners.clone();
+            self.query_source(move |source| owners.iter().all(|owner| source.is_alive(owner)))
+                .await
+                .unwrap_or(false)
+        };
+
+        if valid {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            Some(entry.value)
+        } else {
+            tracing::info!(owners = ?entry.owners, "缓存条目所属进程已退出或重启，丢弃缓存");
+            self.invalidations.fetch_add(1, Ordering::Relaxed);
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+    }
+
+    /// 记录最新实例身份，变化时推送事件
+    fn observe_identity(&self, current: Option<ServerIdentity>) {
+        let previous = {
+            let Ok(mut last) = self.last_identity.lock() else {
+                return;
+            };
+            if *last == current {
+                return;
+            }
+            std::mem::replace(&mut *last, current)
+        };
+
+        tracing::info!(?previous, ?current, "🔄 语言服务器身份发生变化");
+
+        if let Some(app_handle) = APP_HANDLE.get() {
+            let event = IdentityChangedEvent {
+                previous,
+                current,
+                timestamp: chrono::Local::now().timestamp_millis(),
+            };
+            if let Err(e) = app_handle.emit(IDENTITY_CHANGED_EVENT, &event) {
+                tracing::error!("❌ 推送 {} 事件失败: {}", IDENTITY_CHANGED_EVENT, e);
+            }
+        }
+    }
+}
+
+/// 实例列表缓存 key
+const INSTANCES_CACHE_KEY: &str = "instances";
+
+/// 单个实例的 CSRF Token 缓存 key
+pub fn instance_csrf_key(instance_id: &str) -> String {
+    format!("csrf_token:{}", instance_id)
+}
+
+/// 单个实例的已验证端口缓存 key
+pub fn instance_ports_key(instance_id: &str) -> String {
+    format!("ports_info:{}", instance_id)
 }
 
 /// 全局缓存管理器实例
 static GLOBAL_CACHE: std::sync::OnceLock<Arc<CacheManager>> = std::sync::OnceLock::new();
 
+/// 用于推送身份变化事件的应用句柄
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
 /// 获取全局缓存管理器
 pub fn get_cache_manager() -> &'static Arc<CacheManager> {
     GLOBAL_CACHE.get_or_init(|| {
@@ -87,35 +370,47 @@ pub fn get_cache_manager() -> &'static Arc<CacheManager> {
     })
 }
 
+/// 注册应用句柄，之后语言服务器身份变化时推送事件
+pub fn set_app_handle(app_handle: AppHandle) {
+    let _ = APP_HANDLE.set(app_handle);
+}
+
 // ========== 高级 API（业务逻辑）==========
 
 /// 获取 CSRF token（带自动缓存）
 ///
 /// 逻辑：
-/// 1. 有缓存 -> 返回缓存
-/// 2. 无缓存 -> 扫描内存 -> 缓存 -> 返回
+/// 1. 有缓存且所属进程未变 -> 返回缓存
+/// 2. 否则 -> 检测进程 -> 缓存 -> 返回
 pub async fn get_csrf_token() -> Result<String> {
     let cache = get_cache_manager();
     let cache_key = "csrf_token";
     
     // 1. 尝试从缓存获取
     if let Some(cached_token) = cache.get_csrf_token(cache_key).await {
-        tracing::info!("✅ 使用缓存的 CSRF Token (TTL: 5分钟)");
+        tracing::info!("✅ 使用缓存的 CSRF Token");
         return Ok(cached_token);
     }
     
-    // 2. 缓存未命中或已过期，重新扫描
+    // 2. 缓存未命中或进程已变化，重新检测
     tracing::info!("缓存未命中，开始扫描进程获取 CSRF Token");
-    let token = tokio::task::spawn_blocking(|| {
+    let process_info = tokio::task::spawn_blocking(|| {
         find_csrf_token_from_memory()
     }).await??;
 
-    tracing::info!("✅ 成功获取 CSRF Token: {}", token);
+    tracing::info!("✅ 成功获取 CSRF Token: {}...", &process_info.csrf_token[..8.min(process_info.csrf_token.len())]);
     
     // 3. 更新缓存
-    cache.set_csrf_token(cache_key, token.clone()).await;
+    cache
+        .set_csrf_token_for(cache_key, process_info.csrf_token.clone(), Some(process_info.identity()))
+        .await;
+
+    Ok(process_info.csrf_token)
+}
 
-    Ok(token)
+/// 获取所有语言服务器实例（带自动缓存，按启动时间倒序）
+pub async fn get_instances() -> Result<Vec<ProcessInfo>> {
+    get_cache_manager().instances().await
 }
 
 /// 获取端口信息（带自动缓存）
@@ -129,13 +424,14 @@ pub async fn get_ports() -> Result<PortInfo> {
     
     // 1. 尝试从缓存获取
     if let Some(cached_ports) = cache.get_ports(cache_key).await {
-        tracing::info!("✅ 使用缓存的端口信息 (TTL: 5分钟)");
+        tracing::info!("✅ 使用缓存的端口信息");
         return Ok(cached_ports);
     }
     
     // 2. 缓存未命中或已过期，重新解析
     tracing::info!("缓存未命中，开始解析日志文件获取端口信息");
-    let ports = parse_ports_from_log_sync();
+    // 追赶日志最多读取 16 × 4MB，必须在阻塞线程池中执行
+    let ports = tokio::task::spawn_blocking(parse_ports_from_log_sync).await?;
 
     tracing::info!("✅ 成功获取端口信息: https_port={:?}, http_port={:?}, extension_port={:?}", 
         ports.https_port, ports.http_port, ports.extension_port);
@@ -160,15 +456,21 @@ pub fn get_stats() -> CacheStats {
 
 // ========== 内部辅助函数 ==========
 
-/// 从进程命令行参数中查找 CSRF token
-fn find_csrf_token_from_memory() -> Result<String> {
-    use super::cmdline_detector::CmdLineDetector;
-    
+/// 从进程命令行参数中查找 CSRF token（返回所属进程信息）
+///
+/// Linux 上命令行中没有 Token 时回退到有时间上限的内存扫描
+fn find_csrf_token_from_memory() -> Result<ProcessInfo> {
     tracing::info!("使用命令行参数检测 CSRF Token...");
     
     let detector = CmdLineDetector::new();
-    let process_info = detector.detect_process_info()
-        .map_err(|e| anyhow!("从进程命令行提取 CSRF Token 失败: {}", e))?;
+    let process_info = match detector.detect_process_info() {
+        Ok(info) => info,
+        Err(e) => {
+            tracing::warn!("从进程命令行提取 CSRF Token 失败: {}，尝试内存扫描", e);
+            return scan_memory_for_token(&MemoryScanOptions::with_timeout(MEMORY_SCAN_TIMEOUT))
+                .map_err(|scan_err| anyhow!("从进程命令行提取 CSRF Token 失败: {}；{}", e, scan_err));
+        }
+    };
     
     tracing::info!(
         "成功从进程命令行提取 CSRF Token (PID: {}, extension_port: {:?})",
@@ -176,48 +478,58 @@ fn find_csrf_token_from_memory() -> Result<String> {
         process_info.extension_port
     );
     
-    Ok(process_info.csrf_token)
+    Ok(process_info)
 }
 
-/// 从日志文件解析端口信息
-fn parse_ports_from_log_sync() -> PortInfo {
-    match find_latest_antigravity_log() {
-        Some(log_path) => {
-            match std::fs::read_to_string(&log_path) {
-                Ok(content) => {
-                    let (https_port, http_port, extension_port) = parse_ports_from_log(&content);
-                    PortInfo {
-                        https_port,
-                        http_port,
-                        extension_port,
-                        log_path: Some(log_path.to_string_lossy().to_string()),
-                        csrf_token: None,
-                    }
+/// 在语言服务器进程内存中查找 CSRF Token（最新启动的进程优先，所有进程共用同一截止时间）
+#[cfg(target_os = "linux")]
+fn scan_memory_for_token(options: &MemoryScanOptions) -> Result<ProcessInfo> {
+    use super::args::LanguageServerArgs;
+    use super::types::MemoryScanOutcome;
+
+    let source = get_cache_manager().source().clone();
+    let mut candidates: Vec<_> = source
+        .processes()
+        .into_iter()
+        .filter(|p| p.name.to_lowercase().contains("language_server"))
+        .collect();
+    candidates.sort_by(|a, b| b.start_time.cmp(&a.start_time));
+
+    let uuid_re = regex::Regex::new(r"(?i)[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}")?;
+    let patterns = get_search_patterns();
+
+    for entry in candidates {
+        match super::linux::scan_process_for_token(entry.pid, &uuid_re, &patterns, options) {
+            Ok((Some(token), stats)) => {
+                tracing::info!("✅ 内存扫描找到 CSRF Token (PID: {}, 耗时 {}ms)", entry.pid, stats.elapsed_ms);
+                let args = source
+                    .cmdline(entry.pid)
+                    .map(|argv| LanguageServerArgs::parse(&argv))
+                    .unwrap_or_default();
+                return Ok(ProcessInfo::new(entry.pid, entry.start_time, token, args));
+            }
+            Ok((None, stats)) => {
+                if matches!(stats.outcome, MemoryScanOutcome::DeadlineExceeded | MemoryScanOutcome::Cancelled) {
+                    return Err(anyhow!("内存扫描未完成: {:?}", stats.outcome));
                 }
-                Err(_) => PortInfo::default(),
+            }
+            Err(e) => {
+                tracing::warn!("扫描 PID {} 内存失败: {}", entry.pid, e);
             }
         }
-        None => PortInfo::default(),
     }
-}
-
-/// 收集 Antigravity 进程 PID
-fn collect_antigravity_pids() -> Vec<u32> {
-    use sysinfo::System;
-
-    let mut system = System::new();
-    system.refresh_processes();
 
-    let mut pids = Vec::new();
+    Err(anyhow!("内存扫描未找到 CSRF Token"))
+}
 
-    for (pid, process) in system.processes() {
-        let name = process.name();
-        if name == "Antigravity.exe" ||name == "antigravity" || name.contains("Antigravity") {
-            pids.push(pid.as_u32());
-        }
-    }
+#[cfg(not(target_os = "linux"))]
+fn scan_memory_for_token(_options: &MemoryScanOptions) -> Result<ProcessInfo> {
+    Err(anyhow!("当前平台不支持有时限的内存扫描"))
+}
 
-    pids
+/// 从日志跟踪器获取端口信息（只读取新追加的日志）
+fn parse_ports_from_log_sync() -> PortInfo {
+    super::log_follower::current_ports()
 }
 
 /// 获取搜索模式（UTF-8 和 UTF-16）
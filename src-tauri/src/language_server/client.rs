@@ -0,0 +1,547 @@
+//! 语言服务器 Connect 协议客户端
+//!
+//! 统一负责端点发现（端口 + CSRF Token）、TLS 证书固定、连接复用、超时和带退避的重试，
+//! 对外只暴露 `call::<Req, Resp>(method, &req)`，新增 `LanguageServerService` 方法只需几行。
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use super::cache::{get_cache_manager, instance_ports_key, CacheManager};
+use super::cmdline_detector::ProcessInfo;
+use super::tls::{format_fingerprint, pinned_client_config, CertificatePin, Fingerprint};
+use super::types::{HttpConfig, PortInfo};
+
+/// Connect 服务路径
+pub(crate) const SERVICE_PATH: &str = "exa.language_server_pb.LanguageServerService";
+
+/// `LanguageServerService` 方法名
+pub mod methods {
+    pub const GET_USER_STATUS: &str = "GetUserStatus";
+    pub const GET_UNLEASH_DATA: &str = "GetUnleashData";
+}
+
+/// 语言服务器调用错误
+#[derive(Debug, Clone)]
+pub enum LanguageServerError {
+    /// 无法确定端口或 CSRF Token
+    Discovery(String),
+    /// 请求体序列化失败
+    Encode(String),
+    /// 连接失败（端口未监听、TLS 握手失败等）
+    Connect(String),
+    /// 请求超时
+    Timeout,
+    /// CSRF Token 不被接受（401/403）
+    Unauthorized(String),
+    /// 其他非 2xx 响应
+    Status { status: u16, body: String },
+    /// 响应体解析失败
+    Decode(String),
+    /// 证书与该实例首次连接时固定的证书不一致，请求未发送
+    CertificateMismatch {
+        instance_id: String,
+        expected: Fingerprint,
+        actual: Fingerprint,
+    },
+}
+
+impl LanguageServerError {
+    /// 是否值得重试
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Connect(_) | Self::Timeout | Self::Unauthorized(_) | Self::Discovery(_) => true,
+            Self::Status { status, .. } => *status >= 500,
+            Self::Encode(_) | Self::Decode(_) | Self::CertificateMismatch { .. } => false,
+        }
+    }
+
+    /// 是否说明缓存的端点已失效（语言服务器重启、端口或 Token 变化）
+    fn invalidates_endpoint(&self) -> bool {
+        matches!(
+            self,
+            Self::Connect(_) | Self::Unauthorized(_) | Self::Discovery(_) | Self::Status { status: 404, .. }
+        )
+    }
+
+    fn from_reqwest(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            Self::Timeout
+        } else {
+            Self::Connect(e.to_string())
+        }
+    }
+}
+
+impl fmt::Display for LanguageServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Discovery(e) => write!(f, "发现语言服务器端点失败: {}", e),
+            Self::Encode(e) => write!(f, "序列化请求体失败: {}", e),
+            Self::Connect(e) => write!(f, "连接语言服务器失败: {}", e),
+            Self::Timeout => write!(f, "请求语言服务器超时"),
+            Self::Unauthorized(body) => write!(f, "CSRF Token 无效: {}", body),
+            Self::Status { status, body } => write!(f, "请求返回错误状态码: {}, Body: {}", status, body),
+            Self::Decode(e) => write!(f, "解析响应失败: {}", e),
+            Self::CertificateMismatch { instance_id, expected, actual } => write!(
+                f,
+                "语言服务器实例 {} 的证书与固定证书不一致（期望 {}，实际 {}），已拒绝发送请求",
+                instance_id,
+                format_fingerprint(expected),
+                format_fingerprint(actual)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LanguageServerError {}
+
+impl From<LanguageServerError> for String {
+    fn from(e: LanguageServerError) -> Self {
+        e.to_string()
+    }
+}
+
+/// 已验证可用的端点
+#[derive(Debug, Clone)]
+pub struct Endpoint {
+    /// 所属实例（见 `ProcessInfo::id`）
+    pub instance_id: String,
+    pub port: u16,
+    pub csrf_token: String,
+}
+
+impl Endpoint {
+    fn url(&self, method: &str) -> String {
+        format!("https://127.0.0.1:{}/{}/{}", self.port, SERVICE_PATH, method)
+    }
+}
+
+/// 单个实例的连接：证书固定状态，以及按端口划分的连接池
+///
+/// 证书校验器需要知道握手发生在哪个端口（未固定前按端口暂存证书），因此每个端口一个 HTTP 客户端
+#[derive(Clone)]
+struct InstanceConnection {
+    pin: Arc<CertificatePin>,
+    clients: Arc<std::sync::Mutex<HashMap<u16, reqwest::Client>>>,
+}
+
+impl InstanceConnection {
+    fn new() -> Self {
+        Self {
+            pin: Arc::new(CertificatePin::default()),
+            clients: Arc::new(std::sync::Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// 获取某个端口的 HTTP 客户端，首次使用时创建
+    fn http(&self, port: u16, request_timeout_ms: u64) -> Result<reqwest::Client, LanguageServerError> {
+        let mut clients = self
+            .clients
+            .lock()
+            .map_err(|e| LanguageServerError::Connect(format!("连接表锁异常: {e}")))?;
+        if let Some(client) = clients.get(&port) {
+            return Ok(client.clone());
+        }
+
+        // 语言服务器使用自签名证书，只监听 127.0.0.1；按实例固定证书而不是接受任意证书
+        let tls = pinned_client_config(self.pin.clone(), port)
+            .map_err(|e| LanguageServerError::Connect(format!("构建 TLS 配置失败: {e}")))?;
+        let client = reqwest::Client::builder()
+            .use_preconfigured_tls(tls)
+            .timeout(Duration::from_millis(request_timeout_ms))
+            .pool_idle_timeout(Duration::from_secs(90))
+            .build()
+            .map_err(|e| LanguageServerError::Connect(format!("构建 HTTP 客户端失败: {e}")))?;
+        clients.insert(port, client.clone());
+        Ok(client)
+    }
+
+    /// 请求成功后固定证书；首次固定时丢弃其他端口上在固定前建立的连接
+    fn confirm(&self, port: u16) {
+        if self.pin.confirm(port) {
+            if let Ok(mut clients) = self.clients.lock() {
+                clients.retain(|p, _| *p == port);
+            }
+        }
+    }
+}
+
+/// 语言服务器客户端
+pub struct LanguageServerClient {
+    /// 按实例划分的连接（实例 ID -> 连接）
+    connections: std::sync::Mutex<HashMap<String, InstanceConnection>>,
+    config: HttpConfig,
+    /// 实例、CSRF Token 与端口缓存
+    cache: Arc<CacheManager>,
+    /// 串行化端点发现，避免并发请求同时探测端口
+    discovery_lock: Mutex<()>,
+}
+
+impl LanguageServerClient {
+    pub fn new(config: HttpConfig) -> Result<Self, LanguageServerError> {
+        Self::with_cache(config, get_cache_manager().clone())
+    }
+
+    /// 使用指定的缓存（及其进程信息来源）构建客户端
+    pub fn with_cache(config: HttpConfig, cache: Arc<CacheManager>) -> Result<Self, LanguageServerError> {
+        Ok(Self {
+            connections: std::sync::Mutex::new(HashMap::new()),
+            config,
+            cache,
+            discovery_lock: Mutex::new(()),
+        })
+    }
+
+    /// 调用最新启动的语言服务器实例上的 `LanguageServerService` 方法
+    pub async fn call<Req, Resp>(&self, method: &str, request: &Req) -> Result<Resp, LanguageServerError>
+    where
+        Req: Serialize + ?Sized,
+        Resp: DeserializeOwned,
+    {
+        self.call_on(None, method, request).await
+    }
+
+    /// 调用指定实例（`None` 为最新启动的实例）上的 `LanguageServerService` 方法
+    ///
+    /// 可重试错误按指数退避重试；端点失效类错误只丢弃该实例的端口缓存并重新发现端点，
+    /// 进程退出或重启由缓存按 PID/启动时间自行失效
+    pub async fn call_on<Req, Resp>(
+        &self,
+        instance_id: Option<&str>,
+        method: &str,
+        request: &Req,
+    ) -> Result<Resp, LanguageServerError>
+    where
+        Req: Serialize + ?Sized,
+        Resp: DeserializeOwned,
+    {
+        let body = serde_json::to_vec(request).map_err(|e| LanguageServerError::Encode(e.to_string()))?;
+
+        let mut attempt = 0;
+        loop {
+            let mut used_instance = None;
+            let result = match self.endpoint(instance_id).await {
+                Ok(endpoint) => {
+                    used_instance = Some(endpoint.instance_id.clone());
+                    self.send(&endpoint, method, body.clone(), self.config.request_timeout_ms)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+
+            let err = match result {
+                Ok(bytes) => {
+                    return serde_json::from_slice(&bytes).map_err(|e| {
+                        LanguageServerError::Decode(format!("{e}; body={}", String::from_utf8_lossy(&bytes)))
+                    });
+                }
+                Err(e) => e,
+            };
+
+            if !err.is_retryable() || attempt >= self.config.max_retries {
+                tracing::error!("❌ {} 调用失败（第 {} 次尝试）: {}", method, attempt + 1, err);
+                return Err(err);
+            }
+
+            if err.invalidates_endpoint() {
+                if let Some(id) = used_instance {
+                    self.cache.invalidate_ports(&instance_ports_key(&id)).await;
+                }
+            }
+
+            let backoff = self.config.retry_backoff_ms * 2u64.pow(attempt);
+            tracing::warn!("{} 调用失败: {}，{}ms 后重试", method, err, backoff);
+            tokio::time::sleep(Duration::from_millis(backoff)).await;
+            attempt += 1;
+        }
+    }
+
+    /// 获取所有语言服务器实例（按启动时间倒序）
+    pub async fn instances(&self) -> Result<Vec<ProcessInfo>, LanguageServerError> {
+        let instances = self
+            .cache
+            .instances()
+            .await
+            .map_err(|e| LanguageServerError::Discovery(e.to_string()))?;
+
+        // 已退出实例的连接与固定证书一并丢弃
+        if let Ok(mut connections) = self.connections.lock() {
+            connections.retain(|id, _| instances.iter().any(|i| &i.id == id));
+        }
+
+        Ok(instances)
+    }
+
+    /// 实例已固定的证书指纹（尚未连接过时为 `None`）
+    pub fn pinned_fingerprint(&self, instance_id: &str) -> Option<String> {
+        let connections = self.connections.lock().ok()?;
+        let fingerprint = connections.get(instance_id)?.pin.fingerprint()?;
+        Some(format_fingerprint(&fingerprint))
+    }
+
+    /// 获取实例的连接，首次使用时创建（证书在首次请求成功时固定）
+    fn connection(&self, instance_id: &str) -> Result<InstanceConnection, LanguageServerError> {
+        let mut connections = self
+            .connections
+            .lock()
+            .map_err(|e| LanguageServerError::Connect(format!("连接表锁异常: {e}")))?;
+        Ok(connections
+            .entry(instance_id.to_string())
+            .or_insert_with(InstanceConnection::new)
+            .clone())
+    }
+
+    /// 获取实例的端点（必要时重新发现），`None` 为最新启动的实例
+    pub async fn endpoint(&self, instance_id: Option<&str>) -> Result<Endpoint, LanguageServerError> {
+        let instance = self.resolve_instance(instance_id).await?;
+        let cache = &self.cache;
+        let ports_key = instance_ports_key(&instance.id);
+
+        if let Some(port) = cache.get_ports(&ports_key).await.and_then(|p| p.https_port) {
+            return Ok(Endpoint {
+                instance_id: instance.id,
+                port,
+                csrf_token: instance.csrf_token,
+            });
+        }
+
+        let _guard = self.discovery_lock.lock().await;
+        if let Some(port) = cache.get_ports(&ports_key).await.and_then(|p| p.https_port) {
+            return Ok(Endpoint {
+                instance_id: instance.id,
+                port,
+                csrf_token: instance.csrf_token,
+            });
+        }
+
+        let endpoint = self.discover(&instance).await?;
+        cache
+            .set_ports_for(
+                &ports_key,
+                PortInfo {
+                    https_port: Some(endpoint.port),
+                    extension_port: instance.extension_port,
+                    ..Default::default()
+                },
+                Some(instance.identity()),
+            )
+            .await;
+        Ok(endpoint)
+    }
+
+    /// 确认实例当前可用：获取端点后再发送一次探测请求
+    ///
+    /// 探测失败时丢弃该实例的端口缓存，下次重新发现
+    pub async fn ping(&self, instance_id: Option<&str>) -> Result<Endpoint, LanguageServerError> {
+        let endpoint = self.endpoint(instance_id).await?;
+        match self.probe(&endpoint).await {
+            Ok(()) => Ok(endpoint),
+            Err(e) => {
+                self.cache.invalidate_ports(&instance_ports_key(&endpoint.instance_id)).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// 实例已知的端口（不探测，仅用于展示）：优先使用进程监听端口，否则使用命令行中的端口
+    pub async fn known_ports(&self, instance: &ProcessInfo) -> Vec<u16> {
+        let source = self.cache.source().clone();
+        let pid = instance.pid;
+        let listening = tokio::task::spawn_blocking(move || source.listening_ports(pid)).await;
+        if let Ok(Ok(Some(ports))) = listening {
+            return ports;
+        }
+
+        let mut ports: Vec<u16> = Vec::new();
+        for port in [instance.api_port, instance.extension_port].into_iter().flatten() {
+            if !ports.contains(&port) {
+                ports.push(port);
+            }
+        }
+        ports
+    }
+
+    /// 丢弃全部实例列表以及端口/CSRF 缓存，下次调用时重新发现
+    pub async fn invalidate(&self) {
+        self.cache.clear_all();
+    }
+
+    /// 按 ID 查找实例，未指定时返回最新启动的实例
+    async fn resolve_instance(&self, instance_id: Option<&str>) -> Result<ProcessInfo, LanguageServerError> {
+        let instances = self.instances().await?;
+        match instance_id {
+            Some(id) => instances
+                .into_iter()
+                .find(|i| i.id == id)
+                .ok_or_else(|| LanguageServerError::Discovery(format!("语言服务器实例 {} 不存在或已退出", id))),
+            None => instances
+                .into_iter()
+                .next()
+                .ok_or_else(|| LanguageServerError::Discovery("未找到语言服务器实例".to_string())),
+        }
+    }
+
+    /// 发现端点：只探测确认属于该进程的监听端口
+    ///
+    /// 无法确认端口归属时（`lsof` / `netstat` / `/proc` 不可用）直接报错：
+    /// 未经验证的端口上可能是任意本地程序，CSRF Token 不能发过去，证书也不能被固定
+    async fn discover(&self, instance: &ProcessInfo) -> Result<Endpoint, LanguageServerError> {
+        let source = self.cache.source().clone();
+        let pid = instance.pid;
+        let listening = tokio::task::spawn_blocking(move || source.listening_ports(pid))
+            .await
+            .map_err(|e| LanguageServerError::Discovery(format!("检测任务异常: {e}")))?
+            .map_err(|e| LanguageServerError::Discovery(e.to_string()))?;
+
+        match listening {
+            Some(ports) => self.discover_listening(instance, ports).await,
+            None => Err(LanguageServerError::Discovery(format!(
+                "无法确认语言服务器进程 {} 监听的端口，拒绝向未验证的端口发送 CSRF Token",
+                pid
+            ))),
+        }
+    }
+
+    /// 只探测语言服务器进程实际监听的端口
+    ///
+    /// Linux 上通过 `/proc/<pid>/fd` 的 socket inode 匹配 `/proc/<pid>/net/tcp{,6}` 中的 LISTEN 条目，
+    /// macOS / Windows 上使用 `lsof` / `netstat`；没有可用端口时直接报错，不回退到未验证的端口
+    async fn discover_listening(
+        &self,
+        instance: &ProcessInfo,
+        mut ports: Vec<u16>,
+    ) -> Result<Endpoint, LanguageServerError> {
+        let pid = instance.pid;
+
+        if ports.is_empty() {
+            return Err(LanguageServerError::Discovery(format!(
+                "语言服务器进程 {} 没有监听中的 TCP 端口",
+                pid
+            )));
+        }
+
+        // 命令行中声明的 API 端口优先
+        if let Some(api_port) = instance.api_port {
+            if let Some(pos) = ports.iter().position(|p| *p == api_port) {
+                ports.swap(0, pos);
+            }
+        }
+
+        tracing::info!("✅ 语言服务器 PID {} 监听端口: {:?}", pid, ports);
+
+        let mut mismatch = None;
+        for port in &ports {
+            let endpoint = Endpoint {
+                instance_id: instance.id.clone(),
+                port: *port,
+                csrf_token: instance.csrf_token.clone(),
+            };
+            match self.probe(&endpoint).await {
+                Ok(()) => {
+                    tracing::info!("🎯 找到可用端口: {}", port);
+                    return Ok(endpoint);
+                }
+                Err(e @ LanguageServerError::CertificateMismatch { .. }) => mismatch = Some(e),
+                Err(_) => {}
+            }
+        }
+
+        // 证书不一致比“未响应”更值得报告
+        if let Some(e) = mismatch {
+            return Err(e);
+        }
+
+        Err(LanguageServerError::Discovery(format!(
+            "语言服务器进程 {} 的监听端口 {:?} 均未响应",
+            pid, ports
+        )))
+    }
+
+    /// 用 GetUnleashData 探测端口是否可用
+    async fn probe(&self, endpoint: &Endpoint) -> Result<(), LanguageServerError> {
+        let request = serde_json::json!({
+            "context": {
+                "properties": {
+                    "devMode": "false",
+                    "extensionVersion": "",
+                    "hasAnthropicModelAccess": "true",
+                    "ide": "antigravity",
+                    "ideVersion": "1.11.2",
+                    "installationId": "test-detection",
+                    "language": "UNSPECIFIED",
+                    "os": std::env::consts::OS,
+                    "requestedModelId": "MODEL_UNSPECIFIED"
+                }
+            }
+        });
+        let body = serde_json::to_vec(&request).map_err(|e| LanguageServerError::Encode(e.to_string()))?;
+
+        tracing::debug!("测试端口 {} 连接性...", endpoint.port);
+        self.send(endpoint, methods::GET_UNLEASH_DATA, body, self.config.probe_timeout_ms)
+            .await
+            .map(|_| ())
+            .inspect_err(|e| tracing::debug!("端口 {} 测试失败: {}", endpoint.port, e))
+    }
+
+    /// 发送一次 Connect unary 请求，返回响应体
+    async fn send(
+        &self,
+        endpoint: &Endpoint,
+        method: &str,
+        body: Vec<u8>,
+        timeout_ms: u64,
+    ) -> Result<Vec<u8>, LanguageServerError> {
+        let connection = self.connection(&endpoint.instance_id)?;
+        let result = connection
+            .http(endpoint.port, self.config.request_timeout_ms)?
+            .post(endpoint.url(method))
+            .timeout(Duration::from_millis(timeout_ms))
+            .header("content-type", "application/json")
+            .header("connect-protocol-version", "1")
+            .header("X-Codeium-Csrf-Token", &endpoint.csrf_token)
+            .body(body)
+            .send()
+            .await;
+
+        // 证书不匹配时握手即中断，CSRF Token 不会发出
+        if let Some((expected, actual)) = connection.pin.take_mismatch(endpoint.port) {
+            return Err(LanguageServerError::CertificateMismatch {
+                instance_id: endpoint.instance_id.clone(),
+                expected,
+                actual,
+            });
+        }
+        let resp = result.map_err(LanguageServerError::from_reqwest)?;
+
+        let status = resp.status();
+        let bytes = resp.bytes().await.map_err(LanguageServerError::from_reqwest)?;
+
+        if status.is_success() {
+            connection.confirm(endpoint.port);
+            return Ok(bytes.to_vec());
+        }
+
+        let body = String::from_utf8_lossy(&bytes).to_string();
+        match status.as_u16() {
+            401 | 403 => Err(LanguageServerError::Unauthorized(body)),
+            code => Err(LanguageServerError::Status { status: code, body }),
+        }
+    }
+}
+
+/// 全局客户端实例
+static GLOBAL_CLIENT: OnceLock<LanguageServerClient> = OnceLock::new();
+
+/// 获取全局语言服务器客户端
+pub fn get_client() -> Result<&'static LanguageServerClient, LanguageServerError> {
+    if let Some(client) = GLOBAL_CLIENT.get() {
+        return Ok(client);
+    }
+    let client = LanguageServerClient::new(HttpConfig::default())?;
+    Ok(GLOBAL_CLIENT.get_or_init(|| client))
+}
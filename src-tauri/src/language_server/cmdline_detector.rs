@@ -1,16 +1,76 @@
 use anyhow::{anyhow, Result};
-use regex::Regex;
+use serde::Serialize;
 use std::process::Command;
-use sysinfo::System;
+use std::sync::Arc;
+
+use super::args::{split_command_line, LanguageServerArgs};
+use crate::platform::snapshot::{self, freshness};
+use super::types::ServerIdentity;
+
+/// 候选进程
+#[derive(Debug, Clone)]
+pub struct ProcessEntry {
+    pub pid: u32,
+    pub name: String,
+    /// 启动时间（Unix 秒）
+    pub start_time: u64,
+}
+
+/// 进程信息来源
+///
+/// 语言服务器发现所需的进程查询（进程列表、命令行、监听端口、存活检查）都经过这里，
+/// 正式运行时使用 `SystemProcessSource`，测试中可替换为伪造实现。
+pub trait ProcessSource: Send + Sync {
+    /// 列出当前所有进程（只包含真实进程，不包含线程）
+    fn processes(&self) -> Vec<ProcessEntry>;
+
+    /// 读取进程命令行参数（argv，第一个元素为可执行文件）
+    fn cmdline(&self, pid: u32) -> Result<Vec<String>>;
+
+    /// 进程正在监听的 TCP 端口，平台不支持时返回 `None`
+    fn listening_ports(&self, pid: u32) -> Result<Option<Vec<u16>>>;
+
+    /// 进程是否仍在运行且启动时间未变（PID 复用时启动时间不同）
+    fn is_alive(&self, identity: &ServerIdentity) -> bool;
+}
+
+/// 当前所有 `language_server*` 进程的身份（按 PID 排序），用于判断实例列表是否需要重新检测
+///
+/// 只包含线程组 leader（`ProcessSource::processes` 不列出线程），
+/// 语言服务器线程的创建和退出不会让实例列表失效
+pub fn language_server_identities(source: &dyn ProcessSource) -> Vec<ServerIdentity> {
+    let mut identities: Vec<ServerIdentity> = source
+        .processes()
+        .into_iter()
+        .filter(|p| p.name.to_lowercase().starts_with("language_server"))
+        .map(|p| ServerIdentity {
+            pid: p.pid,
+            start_time: p.start_time,
+        })
+        .collect();
+    identities.sort_by_key(|i| (i.pid, i.start_time));
+    identities
+}
+
+/// 基于操作系统的进程信息来源
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemProcessSource;
 
 /// 从进程命令行参数中提取 CSRF Token 和端口信息
 pub struct CmdLineDetector {
     process_name_patterns: Vec<String>,
+    source: Arc<dyn ProcessSource>,
 }
 
 impl CmdLineDetector {
     pub fn new() -> Self {
+        Self::with_source(Arc::new(SystemProcessSource))
+    }
+
+    /// 使用指定的进程信息来源
+    pub fn with_source(source: Arc<dyn ProcessSource>) -> Self {
         Self {
+            source,
             process_name_patterns: vec![
                 "language_server_windows_x64".to_string(),  // Windows 完整进程名
                 "language_server_macos".to_string(),        // macOS 进程名
@@ -22,99 +82,159 @@ impl CmdLineDetector {
         }
     }
 
-    /// 检测进程信息（端口和 CSRF Token）
+    /// 检测进程信息（端口和 CSRF Token），多个实例时返回最新启动的一个
     pub fn detect_process_info(&self) -> Result<ProcessInfo> {
+        self.detect_all_instances()?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("未能从任何进程命令行中提取 CSRF Token"))
+    }
+
+    /// 检测所有语言服务器实例（按启动时间倒序）
+    pub fn detect_all_instances(&self) -> Result<Vec<ProcessInfo>> {
         tracing::info!("开始从进程命令行参数检测 CSRF Token 和端口...");
 
         // 1. 查找目标进程
-        let pids = self.find_target_processes()?;
-        if pids.is_empty() {
+        let processes = self.find_target_processes()?;
+        if processes.is_empty() {
             return Err(anyhow!("未找到运行中的 Antigravity/Windsurf 进程"));
         }
 
-        tracing::info!("找到 {} 个候选进程", pids.len());
+        tracing::info!("找到 {} 个候选进程", processes.len());
 
         // 2. 遍历进程，提取命令行参数
-        for pid in pids {
-            match self.extract_from_cmdline(pid) {
+        let mut instances = Vec::new();
+        for (pid, start_time) in processes {
+            match self.extract_from_cmdline(pid, start_time) {
                 Ok(Some(info)) => {
                     tracing::info!(
-                        "[CmdLineDetector] 成功从 PID {} 提取信息: extension_port={:?}, csrf_token=[present]",
+                        "[CmdLineDetector] 成功从 PID {} 提取信息: extension_port={:?}, workspace={:?}, csrf_token=[present]",
                         pid,
-                        info.extension_port
+                        info.extension_port,
+                        info.workspace_hint
                     );
-                    return Ok(info);
+                    instances.push(info);
                 }
                 Ok(None) => {
                     tracing::debug!("PID {} 的命令行中未找到所需信息", pid);
-                    continue;
                 }
                 Err(e) => {
                     tracing::warn!("处理 PID {} 失败: {}", pid, e);
-                    continue;
                 }
             }
         }
 
-        Err(anyhow!("未能从任何进程命令行中提取 CSRF Token"))
-    }
+        if instances.is_empty() {
+            return Err(anyhow!("未能从任何进程命令行中提取 CSRF Token"));
+        }
 
-    /// 查找目标进程的 PID 列表
-    fn find_target_processes(&self) -> Result<Vec<u32>> {
-        let mut system = System::new();
-        system.refresh_processes();
+        Ok(instances)
+    }
 
-        let mut pids: Vec<u32> = system
+    /// 查找目标进程，返回 `(PID, 启动时间)` 列表
+    fn find_target_processes(&self) -> Result<Vec<(u32, u64)>> {
+        let mut processes: Vec<(u32, u64)> = self
+            .source
             .processes()
-            .iter()
-            .filter_map(|(pid, proc_)| {
-                let name = proc_.name().to_string().to_lowercase();
+            .into_iter()
+            .filter_map(|entry| {
+                let name = entry.name.to_lowercase();
                 
                 // 检查进程名是否匹配
                 for pattern in &self.process_name_patterns {
                     if name.contains(pattern) {
-                        return Some(pid.as_u32());
+                        return Some((entry.pid, entry.start_time));
                     }
                 }
                 None
             })
             .collect();
 
-        // 按 PID 倒序排列，优先检查最新的进程
-        pids.sort_unstable_by(|a, b| b.cmp(a));
+        // 按启动时间倒序排列，优先检查最新的进程
+        processes.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(b.0.cmp(&a.0)));
 
-        Ok(pids)
+        Ok(processes)
     }
 
     /// 从进程命令行参数中提取信息
-    fn extract_from_cmdline(&self, pid: u32) -> Result<Option<ProcessInfo>> {
-        let cmdline = self.get_process_cmdline(pid)?;
-        
-        if cmdline.is_empty() {
+    fn extract_from_cmdline(&self, pid: u32, start_time: u64) -> Result<Option<ProcessInfo>> {
+        let argv = self.source.cmdline(pid)?;
+
+        if argv.is_empty() {
             return Ok(None);
         }
 
-        tracing::debug!("PID {} 命令行: {}", pid, &cmdline[..cmdline.len().min(200)]);
+        let args = LanguageServerArgs::parse(&argv);
+        tracing::debug!(
+            "PID {} 命令行: program={}, 已知参数外的参数={:?}",
+            pid,
+            args.program,
+            args.unknown.keys().collect::<Vec<_>>()
+        );
+
+        let Some(csrf_token) = args.token().map(str::to_string) else {
+            tracing::debug!("PID {} 的命令行中没有 --csrf_token / --manager_token", pid);
+            return Ok(None);
+        };
+
+        Ok(Some(ProcessInfo::new(pid, start_time, csrf_token, args)))
+    }
+}
+
+impl ProcessSource for SystemProcessSource {
+    fn processes(&self) -> Vec<ProcessEntry> {
+        snapshot::with_snapshot(freshness::DETECTION, |snapshot| {
+            snapshot
+                .processes()
+                .map(|(pid, proc_)| ProcessEntry {
+                    pid: pid.as_u32(),
+                    name: proc_.name().to_string(),
+                    start_time: proc_.start_time(),
+                })
+                .collect()
+        })
+    }
+
+    fn cmdline(&self, pid: u32) -> Result<Vec<String>> {
+        self.get_process_cmdline(pid)
+    }
+
+    fn listening_ports(&self, pid: u32) -> Result<Option<Vec<u16>>> {
+        #[cfg(target_os = "linux")]
+        {
+            super::linux::listening_ports(pid).map(Some)
+        }
 
-        // 解析命令行参数
-        let extension_port = self.extract_port(&cmdline, "--extension_server_port")?;
-        let api_port = self.extract_port(&cmdline, "--api_server_port")?;
-        let csrf_token = self.extract_csrf_token(&cmdline)?;
+        // lsof / netstat 不可用时返回 `None`，调用方不会向任何端口发送 Token
+        #[cfg(target_os = "macos")]
+        {
+            Ok(super::macos::listening_ports(pid)
+                .inspect_err(|e| tracing::warn!("获取 PID {} 监听端口失败: {}", pid, e))
+                .ok())
+        }
 
-        if let Some(token) = csrf_token {
-            return Ok(Some(ProcessInfo {
-                pid,
-                extension_port,
-                api_port,
-                csrf_token: token,
-            }));
+        #[cfg(target_os = "windows")]
+        {
+            Ok(super::windows::listening_ports(pid)
+                .inspect_err(|e| tracing::warn!("获取 PID {} 监听端口失败: {}", pid, e))
+                .ok())
         }
+    }
 
-        Ok(None)
+    fn is_alive(&self, identity: &ServerIdentity) -> bool {
+        let pid = sysinfo::Pid::from_u32(identity.pid);
+        snapshot::with_snapshot(freshness::DETECTION, |snapshot| {
+            snapshot
+                .system()
+                .process(pid)
+                .is_some_and(|p| p.start_time() == identity.start_time)
+        })
     }
+}
 
-    /// 获取进程命令行（跨平台）
-    fn get_process_cmdline(&self, pid: u32) -> Result<String> {
+impl SystemProcessSource {
+    /// 获取进程命令行参数（跨平台）
+    fn get_process_cmdline(&self, pid: u32) -> Result<Vec<String>> {
         #[cfg(target_os = "macos")]
         {
             self.get_cmdline_macos(pid)
@@ -132,7 +252,7 @@ impl CmdLineDetector {
     }
 
     #[cfg(target_os = "macos")]
-    fn get_cmdline_macos(&self, pid: u32) -> Result<String> {
+    fn get_cmdline_macos(&self, pid: u32) -> Result<Vec<String>> {
         let output = Command::new("ps")
             .args(&["-p", &pid.to_string(), "-o", "command="])
             .output()
@@ -142,37 +262,39 @@ impl CmdLineDetector {
             return Err(anyhow!("ps 命令执行失败"));
         }
 
-        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        // ps 只能给出整行命令，按引号规则拆分
+        Ok(split_command_line(String::from_utf8_lossy(&output.stdout).trim()))
     }
 
     #[cfg(target_os = "linux")]
-    fn get_cmdline_linux(&self, pid: u32) -> Result<String> {
+    fn get_cmdline_linux(&self, pid: u32) -> Result<Vec<String>> {
         let cmdline_path = format!("/proc/{}/cmdline", pid);
-        let content = std::fs::read_to_string(&cmdline_path)
+        let content = std::fs::read(&cmdline_path)
             .map_err(|e| anyhow!("读取 /proc/{}/cmdline 失败: {}", pid, e))?;
 
-        // /proc/*/cmdline 使用 null 字符分隔参数，转换为空格
-        Ok(content.replace('\0', " "))
+        // /proc/*/cmdline 使用 NUL 分隔参数并以 NUL 结尾，参数内的空格和引号原样保留
+        Ok(content
+            .split(|b| *b == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect())
     }
 
     #[cfg(target_os = "windows")]
-    fn get_cmdline_windows(&self, pid: u32) -> Result<String> {
+    fn get_cmdline_windows(&self, pid: u32) -> Result<Vec<String>> {
         tracing::debug!("[CmdLineDetector] Attempting to get command line for PID {}", pid);
         
         // 方法 1: 使用 sysinfo crate（最可靠，跨平台）
         tracing::debug!("[CmdLineDetector] Trying sysinfo method...");
-        let mut system = System::new();
-        system.refresh_processes();
-        
-        if let Some(process) = system.process(sysinfo::Pid::from_u32(pid)) {
-            let cmd = process.cmd();
-            if !cmd.is_empty() {
-                let cmdline = cmd.join(" ");
-                if !cmdline.is_empty() {
-                    tracing::info!("[CmdLineDetector] sysinfo method succeeded");
-                    return Ok(cmdline);
-                }
-            }
+        let cmd = snapshot::with_snapshot(freshness::DETECTION, |snapshot| {
+            snapshot
+                .system()
+                .process(sysinfo::Pid::from_u32(pid))
+                .map(|process| process.cmd().to_vec())
+        });
+        if let Some(cmd) = cmd.filter(|cmd| !cmd.is_empty()) {
+            tracing::info!("[CmdLineDetector] sysinfo method succeeded");
+            return Ok(cmd);
         }
         
         // 方法 2: 使用 Get-CimInstance（推荐，无需管理员权限）
@@ -196,7 +318,7 @@ impl CmdLineDetector {
                     let cmdline = String::from_utf8_lossy(&output.stdout).trim().to_string();
                     if !cmdline.is_empty() && cmdline != "null" {
                         tracing::info!("[CmdLineDetector] Get-CimInstance method succeeded");
-                        return Ok(cmdline);
+                        return Ok(split_command_line(&cmdline));
                     }
                     tracing::debug!("[CmdLineDetector] Get-CimInstance returned empty or null output");
                 }
@@ -230,7 +352,7 @@ impl CmdLineDetector {
                     let cmdline = String::from_utf8_lossy(&output.stdout).trim().to_string();
                     if !cmdline.is_empty() && cmdline != "null" {
                         tracing::info!("[CmdLineDetector] Get-WmiObject method succeeded");
-                        return Ok(cmdline);
+                        return Ok(split_command_line(&cmdline));
                     }
                     tracing::debug!("[CmdLineDetector] Get-WmiObject returned empty or null output");
                 }
@@ -245,59 +367,47 @@ impl CmdLineDetector {
 
         Err(anyhow!("无法获取进程 {} 的命令行参数", pid))
     }
-
-    /// 从命令行中提取端口号
-    fn extract_port(&self, cmdline: &str, flag: &str) -> Result<Option<u16>> {
-        // 匹配 --flag=1234 或 --flag 1234
-        let pattern = format!(r"{}[=\s]+(\d+)", regex::escape(flag));
-        let re = Regex::new(&pattern)?;
-
-        if let Some(caps) = re.captures(cmdline) {
-            if let Some(port_str) = caps.get(1) {
-                let port: u16 = port_str.as_str().parse()
-                    .map_err(|e| anyhow!("解析端口号失败: {}", e))?;
-                return Ok(Some(port));
-            }
-        }
-
-        Ok(None)
-    }
-
-    /// 从命令行中提取 CSRF Token
-    fn extract_csrf_token(&self, cmdline: &str) -> Result<Option<String>> {
-        // 1. 优先尝试匹配明确的参数 --csrf_token=TOKEN 或 --csrf_token TOKEN
-        // 使用宽松的匹配模式，匹配任意十六进制字符串（包括连字符）
-        // 参考 AntigravityQuotaWatcher 的实现: /--csrf_token[=\\s]+([a-f0-9\\-]+)/i
-        let explicit_re = Regex::new(r"(?i)--csrf_token[=\s]+([a-f0-9\-]+)")?;
-        
-        if let Some(caps) = explicit_re.captures(cmdline) {
-            if let Some(token) = caps.get(1) {
-                let token_str = token.as_str().to_string();
-                tracing::info!("通过 --csrf_token 参数找到 Token: {}...", &token_str[..token_str.len().min(8)]);
-                return Ok(Some(token_str));
-            }
-        }
-
-        // 2. 尝试匹配 manager_token (有时用这个名字)
-        let manager_re = Regex::new(r"(?i)--manager_token[=\s]+([a-f0-9\-]+)")?;
-        if let Some(caps) = manager_re.captures(cmdline) {
-            if let Some(token) = caps.get(1) {
-                let token_str = token.as_str().to_string();
-                tracing::info!("通过 --manager_token 参数找到 Token: {}...", &token_str[..token_str.len().min(8)]);
-                return Ok(Some(token_str));
-            }
-        }
-
-        tracing::warn!("未能从命令行中提取 CSRF Token");
-        Ok(None)
-    }
 }
 
-/// 进程信息
-#[derive(Debug, Clone)]
+/// 进程信息（一个语言服务器实例）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ProcessInfo {
+    /// 实例标识：`<pid>-<启动时间>`，进程重启后会变化
+    pub id: String,
     pub pid: u32,
+    /// 启动时间（Unix 秒）
+    pub start_time: u64,
     pub extension_port: Option<u16>,
     pub api_port: Option<u16>,
+    /// 工作区提示（来自 `--workspace_id`）
+    pub workspace_hint: Option<String>,
+    /// CSRF Token 不返回给前端
+    #[serde(skip)]
     pub csrf_token: String,
+    /// 完整的命令行参数（用于诊断）
+    pub args: LanguageServerArgs,
+}
+
+impl ProcessInfo {
+    pub fn new(pid: u32, start_time: u64, csrf_token: String, args: LanguageServerArgs) -> Self {
+        Self {
+            id: format!("{}-{}", pid, start_time),
+            pid,
+            start_time,
+            extension_port: args.extension_server_port,
+            api_port: args.api_server_port,
+            workspace_hint: args.workspace_id.clone(),
+            csrf_token,
+            args,
+        }
+    }
+
+    /// 进程身份（用于缓存失效判断）
+    pub fn identity(&self) -> ServerIdentity {
+        ServerIdentity {
+            pid: self.pid,
+            start_time: self.start_time,
+        }
+    }
 }
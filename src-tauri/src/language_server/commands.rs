@@ -1,219 +1,153 @@
-use std::time::Duration;
+use serde::Serialize;
 
-use super::cache::{get_csrf_token, get_ports, clear_all, get_stats};
+use super::cache::get_stats;
+use super::client::{get_client, methods};
+use super::cmdline_detector::ProcessInfo;
+use super::log_follower::{self, LogEvent};
+use super::readiness::{self, ReadinessStatus};
 use super::types::{
-    RequestMetadata, UserStatusRequest, HttpConfig, CacheInitResult
+    RequestMetadata, UserStatusRequest, CacheInitResult, GetUserStatusResponse
 };
 use super::utils::initialize_cache;
+use crate::antigravity::status_snapshot::record_user_status;
 
-/// 测试端口连接性（使用 GetUnleashData 端点）
-async fn test_port_connectivity(port: u16, csrf_token: &str) -> bool {
-    let client = match reqwest::Client::builder()
-        .danger_accept_invalid_certs(true)
-        .timeout(Duration::from_millis(2000))
-        .build()
-    {
-        Ok(c) => c,
-        Err(_) => return false,
-    };
-
-    let url = format!(
-        "https://127.0.0.1:{}/exa.language_server_pb.LanguageServerService/GetUnleashData",
-        port
-    );
-
-    let request_body = serde_json::json!({
-        "context": {
-            "properties": {
-                "devMode": "false",
-                "extensionVersion": "",
-                "hasAnthropicModelAccess": "true",
-                "ide": "antigravity",
-                "ideVersion": "1.11.2",
-                "installationId": "test-detection",
-                "language": "UNSPECIFIED",
-                "os": "macos",
-                "requestedModelId": "MODEL_UNSPECIFIED"
-            }
-        }
-    });
-
-    tracing::debug!("测试端口 {} 连接性...", port);
-
-    let result = client
-        .post(&url)
-        .header("Content-Type", "application/json")
-        .header("Connect-Protocol-Version", "1")
-        .header("X-Codeium-Csrf-Token", csrf_token)
-        .json(&request_body)
-        .send()
-        .await;
-
-    match result {
-        Ok(resp) => {
-            let status = resp.status();
-            tracing::debug!("端口 {} 响应状态: {}", port, status);
-            status.is_success()
-        }
-        Err(e) => {
-            tracing::debug!("端口 {} 测试失败: {}", port, e);
-            false
-        }
+/// 构造 GetUserStatus 请求体
+fn user_status_request(api_key: &str) -> Result<UserStatusRequest, String> {
+    if api_key.trim().is_empty() {
+        return Err("apiKey 不能为空".to_string());
     }
+
+    // 插件不发送 API Key，只发送基础元数据
+    Ok(UserStatusRequest {
+        metadata: RequestMetadata::default(),
+    })
 }
 
-/// 查找可用的 HTTPS API 端口
-async fn find_working_https_port(extension_port: u16, csrf_token: &str) -> Result<u16, String> {
-    tracing::info!("开始查找可用的 HTTPS API 端口，基础端口: {}", extension_port);
-
-    // 候选端口列表
-    let candidates = vec![
-        extension_port + 1,  // 通常是这个
-        extension_port,
-        extension_port + 2,
-        42100, 42101, 42102, // 常见的固定端口
-    ];
-
-    tracing::info!("候选端口: {:?}", candidates);
-
-    for port in candidates {
-        tracing::info!("测试端口 {}...", port);
-        if test_port_connectivity(port, csrf_token).await {
-            tracing::info!("✅ 找到可用端口: {}", port);
-            return Ok(port);
+/// 把用户状态摘要写入对应账户的备份（后台执行，失败只记录日志）
+fn remember_user_status(status: &GetUserStatusResponse) {
+    let status = status.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = record_user_status(&status) {
+            tracing::warn!("保存账户状态快照失败: {}", e);
         }
-    }
-
-    // 如果所有端口都测试失败，回退到使用 extension_port
-    tracing::warn!("所有端口测试失败，回退使用 extension_port: {}", extension_port);
-    Ok(extension_port)
+    });
 }
 
-/// 执行获取用户状态的核心逻辑
-async fn do_get_user_status(_api_key: &str) -> Result<serde_json::Value, String> {
-    tracing::info!("========== 开始获取用户配额 ==========");
-    
-    // 1) 获取基础端口信息和 CSRF token
-    let port_info = get_ports().await
-        .map_err(|e| format!("获取端口信息失败: {e}"))?;
-    let extension_port = port_info.https_port
-        .ok_or_else(|| "端口信息中未找到端口".to_string())?;
-    
-    let csrf = get_csrf_token().await
-        .map_err(|e| format!("提取 csrf_token 失败: {e}"))?;
-
-    tracing::info!("========== 配额获取诊断信息 ==========");
-    tracing::info!("✅ Extension Port: {}", extension_port);
-    tracing::info!("✅ CSRF Token: {}...", &csrf[..8.min(csrf.len())]);
-    tracing::info!("📋 端口详情: https_port={:?}, http_port={:?}, extension_port={:?}", 
-        port_info.https_port, port_info.http_port, port_info.extension_port);
-    tracing::info!("========================================");
-
-    // 2) 测试并找到可用的 HTTPS API 端口
-    let working_port = find_working_https_port(extension_port, &csrf).await
-        .map_err(|e| format!("查找可用端口失败: {e}"))?;
-
-    tracing::info!("🎯 使用端口 {} 发送 GetUserStatus 请求", working_port);
-
-    // 3) 构造 URL 和请求体
-    let target_url = format!(
-        "https://127.0.0.1:{}/exa.language_server_pb.LanguageServerService/GetUserStatus",
-        working_port
-    );
-
-    let http_config = HttpConfig::default();
-    let client = reqwest::Client::builder()
-        .danger_accept_invalid_certs(true)
-        .timeout(Duration::from_millis(http_config.request_timeout_ms))
-        .build()
-        .map_err(|e| format!("构建 HTTP 客户端失败: {e}"))?;
-
-    let metadata = RequestMetadata {
-        // 插件不发送 API Key，只发送基础元数据
-        // api_key: api_key.to_string(), 
-        ..Default::default()
-    };
-
-    let request_body = UserStatusRequest { metadata };
-    let body_bytes = serde_json::to_vec(&request_body)
-        .map_err(|e| format!("序列化请求体失败: {e}"))?;
-
-    let mut req = client.post(&target_url);
-
-    // 模拟前端请求头 (与插件保持一致)
-    req = req
-        .header("accept", "*/*")
-        .header("accept-language", "en-US")
-        .header("connect-protocol-version", "1")
-        .header("content-type", "application/json")
-        .header("priority", "u=1, i")
-        .header("sec-ch-ua", "\"Not)A;Brand\";v=\"8\", \"Chromium\";v=\"138\"")
-        .header("sec-ch-ua-mobile", "?0")
-        .header("sec-ch-ua-platform", "\"Windows\"")
-        .header("sec-fetch-dest", "empty")
-        .header("sec-fetch-mode", "cors")
-        .header("sec-fetch-site", "cross-site")
-        // 注意：插件使用 X-Codeium-Csrf-Token (首字母大写)
-        .header("X-Codeium-Csrf-Token", csrf.clone());
-
-    let resp = req
-        .body(body_bytes)
-        .send()
-        .await
-        .map_err(|e| format!("请求失败: {e}"))?;
-
-    let status = resp.status();
-    let bytes = resp
-        .bytes()
-        .await
-        .map_err(|e| format!("读取响应失败: {e}"))?;
-
-    if !status.is_success() {
-        tracing::error!("❌ GetUserStatus 请求失败: 状态码={}, Body={}", status, String::from_utf8_lossy(&bytes));
-        return Err(format!("请求返回错误状态码: {}, Body: {}", status, String::from_utf8_lossy(&bytes)));
+/// 获取 GetUserStatus 原始 JSON
+pub async fn fetch_user_status_raw(api_key: &str) -> Result<serde_json::Value, String> {
+    let request = user_status_request(api_key)?;
+    let json: serde_json::Value = get_client()?
+        .call(methods::GET_USER_STATUS, &request)
+        .await?;
+    if let Ok(status) = serde_json::from_value::<GetUserStatusResponse>(json.clone()) {
+        remember_user_status(&status);
     }
+    Ok(json)
+}
 
-    tracing::info!("✅ GetUserStatus 响应成功: 状态码={}", status);
+/// 获取类型化的 GetUserStatus 响应（供托盘、通知、配额历史等 Rust 功能使用）
+pub async fn fetch_user_status(api_key: &str) -> Result<GetUserStatusResponse, String> {
+    fetch_user_status_for(api_key, None).await
+}
 
-    // 直接解析为 JSON
-    let json: serde_json::Value = serde_json::from_slice(&bytes)
-        .map_err(|e| format!("解析 JSON 失败: {e}; body={}", String::from_utf8_lossy(&bytes)))?;
+/// 获取指定语言服务器实例的 GetUserStatus 响应，`None` 为最新启动的实例
+pub async fn fetch_user_status_for(
+    api_key: &str,
+    instance_id: Option<&str>,
+) -> Result<GetUserStatusResponse, String> {
+    let request = user_status_request(api_key)?;
+    let status = get_client()?
+        .call_on(instance_id, methods::GET_USER_STATUS, &request)
+        .await?;
+    remember_user_status(&status);
+    Ok(status)
+}
 
-    Ok(json)
+/// 单个实例的 GetUserStatus 结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceUserStatus {
+    pub instance: ProcessInfo,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<GetUserStatusResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 /// 前端调用 GetUserStatus 的公开命令
+///
+/// 未指定 `instance_id` 时使用最新启动的语言服务器实例
 #[tauri::command]
 pub async fn language_server_get_user_status(
     api_key: String,
-) -> Result<serde_json::Value, String> {
+    instance_id: Option<String>,
+) -> Result<GetUserStatusResponse, String> {
+    fetch_user_status_for(&api_key, instance_id.as_deref()).await
+}
 
-    if api_key.trim().is_empty() {
-        return Err("apiKey 不能为空".to_string());
-    }
+/// 列出所有运行中的语言服务器实例（不包含 CSRF Token）
+#[tauri::command]
+pub async fn language_server_list_instances() -> Result<Vec<ProcessInfo>, String> {
+    let instances = get_client()?.instances().await?;
+    Ok(instances)
+}
+
+/// 查询语言服务器当前就绪状态（变化时另有 `language-server-readiness` 事件）
+#[tauri::command]
+pub async fn language_server_get_readiness() -> Result<ReadinessStatus, String> {
+    Ok(readiness::current())
+}
+
+/// 查询 Antigravity.log 中解析出的事件（序号大于 `since_seq`，最多 `limit` 条最新事件）
+///
+/// 新事件另通过 `antigravity-log-events` 事件实时推送
+#[tauri::command]
+pub async fn get_antigravity_log_events(
+    since_seq: Option<u64>,
+    limit: Option<usize>,
+) -> Result<Vec<LogEvent>, String> {
+    // 后台跟踪器追赶日志时会持有锁，不在异步线程上等待
+    tokio::task::spawn_blocking(move || log_follower::events_since(since_seq.unwrap_or(0), limit.unwrap_or(200)))
+        .await
+        .map_err(|e| format!("查询日志事件任务异常: {}", e))
+}
 
-    // 第一次尝试
-    match do_get_user_status(&api_key).await {
-        Ok(json) => Ok(json),
-        Err(e) => {
-            tracing::warn!("第一次获取配额失败: {}。尝试清空缓存并重试...", e);
-            
-            // 清空缓存
-            clear_all().await;
-            
-            // 第二次尝试 (强制重新扫描)
-            match do_get_user_status(&api_key).await {
-                Ok(json) => {
-                    tracing::info!("重试成功！");
-                    Ok(json)
-                },
-                Err(e2) => {
-                    tracing::error!("重试仍然失败: {}", e2);
-                    Err(format!("获取配额失败 (已重试): {}", e2))
-                }
+/// 依次获取所有语言服务器实例的 GetUserStatus，单个实例失败不影响其他实例
+#[tauri::command]
+pub async fn language_server_get_user_status_all(
+    api_key: String,
+) -> Result<Vec<InstanceUserStatus>, String> {
+    let request = user_status_request(&api_key)?;
+    let client = get_client()?;
+    let instances = client.instances().await?;
+
+    let mut results = Vec::with_capacity(instances.len());
+    for instance in instances {
+        let result: Result<GetUserStatusResponse, _> = client
+            .call_on(Some(&instance.id), methods::GET_USER_STATUS, &request)
+            .await;
+        let (status, error) = match result {
+            Ok(status) => {
+                remember_user_status(&status);
+                (Some(status), None)
             }
-        }
+            Err(e) => {
+                tracing::warn!("实例 {} 获取用户状态失败: {}", instance.id, e);
+                (None, Some(e.to_string()))
+            }
+        };
+        results.push(InstanceUserStatus { instance, status, error });
     }
+
+    Ok(results)
+}
+
+/// 前端调用 GetUserStatus 的公开命令（返回原始 JSON，用于调试或新字段）
+#[tauri::command]
+pub async fn language_server_get_user_status_raw(
+    api_key: String,
+) -> Result<serde_json::Value, String> {
+    fetch_user_status_raw(&api_key).await
 }
 
 
@@ -221,7 +155,7 @@ pub async fn language_server_get_user_status(
 #[tauri::command]
 pub async fn clear_all_cache_command() -> Result<(), String> {
     tracing::info!("收到清空所有缓存请求");
-    clear_all().await;
+    get_client()?.invalidate().await;
     tracing::info!("所有缓存已清空");
     Ok(())
 }
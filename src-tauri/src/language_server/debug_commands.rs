@@ -1,4 +1,4 @@
-use super::cache::{get_csrf_token, get_ports};
+use super::cache::{get_csrf_token, get_instances, get_ports};
 
 /// 调试命令：获取当前缓存的 CSRF Token 和端口信息
 #[tauri::command]
@@ -19,6 +19,55 @@ pub async fn debug_get_cache_info() -> Result<String, String> {
         }
     }
     
+    // 语言服务器实例及其命令行参数
+    match get_instances().await {
+        Ok(instances) => {
+            for instance in instances {
+                let args = &instance.args;
+                info.push_str(&format!("实例 {} (PID: {}):\n", instance.id, instance.pid));
+                info.push_str(&format!("  可执行文件: {}\n", args.program));
+                info.push_str(&format!("  Extension 端口: {:?}\n", args.extension_server_port));
+                info.push_str(&format!("  API 端口: {:?}\n", args.api_server_port));
+                info.push_str(&format!("  Server 端口: {:?}\n", args.server_port));
+                info.push_str(&format!("  LSP 端口: {:?} (enable_lsp={})\n", args.lsp_port, args.enable_lsp));
+                info.push_str(&format!("  random_port: {}\n", args.random_port));
+                info.push_str(&format!("  工作区: {:?}\n", args.workspace_id));
+                info.push_str(&format!("  Cloud Code 端点: {:?}\n", args.cloud_code_endpoint));
+                info.push_str(&format!("  数据目录: {:?}\n", args.app_data_dir));
+                info.push_str(&format!("  父进程管道: {:?}\n", args.parent_pipe_path));
+                for (name, value) in &args.redacted_unknown() {
+                    info.push_str(&format!("  未知参数 --{}: {:?}\n", name, value));
+                }
+                if !args.positional.is_empty() {
+                    info.push_str(&format!("  位置参数: {:?}\n", args.positional));
+                }
+                info.push('\n');
+            }
+        }
+        Err(e) => {
+            info.push_str(&format!("检测语言服务器实例失败: {}\n\n", e));
+        }
+    }
+
+    // 最近一次内存扫描
+    #[cfg(target_os = "linux")]
+    if let Some(stats) = super::linux::last_scan_stats() {
+        info.push_str(&format!(
+            "最近一次内存扫描 (PID: {}): {:?}, 区域 {} (优先 {}), 分块 {}/{} (失败 {}), 读取 {} 字节 / {} 次调用, {} 线程, {}ms\n\n",
+            stats.pid,
+            stats.outcome,
+            stats.regions_total,
+            stats.priority_regions,
+            stats.chunks_scanned,
+            stats.chunks_total,
+            stats.chunks_failed,
+            stats.bytes_read,
+            stats.read_calls,
+            stats.workers,
+            stats.elapsed_ms
+        ));
+    }
+
     // 获取 CSRF Token
     match get_csrf_token().await {
         Ok(token) => {
@@ -1,83 +1,352 @@
 use anyhow::{anyhow, Context, Result};
-use read_process_memory::{CopyAddress, Pid, ProcessHandle};
 use regex::Regex;
+use std::collections::HashSet;
 use std::fs;
-use std::convert::TryInto;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
 
-use crate::language_server::utils::{search_bytes_for_token, CHUNK_SIZE, SCAN_AHEAD, MAX_REGION_BYTES};
+use crate::language_server::types::{MemoryScanOptions, MemoryScanOutcome, MemoryScanStats};
+use crate::language_server::utils::{search_bytes_for_token, CHUNK_SIZE, SCAN_AHEAD};
+
+/// 每次 `process_vm_readv` 读取的分块数
+const READ_BATCH: usize = 16;
+
+/// 最近一次内存扫描的统计（用于诊断）
+static LAST_SCAN: Mutex<Option<MemoryScanStats>> = Mutex::new(None);
 
 #[derive(Debug)]
 struct Region {
     start: u64,
     end: u64,
+    /// 扫描优先级，越小越先扫描
+    priority: u8,
+}
+
+/// 待读取的分块
+#[derive(Debug, Clone, Copy)]
+struct Chunk {
+    address: u64,
+    len: usize,
+}
+
+/// 最近一次内存扫描的统计
+pub fn last_scan_stats() -> Option<MemoryScanStats> {
+    LAST_SCAN.lock().unwrap().clone()
 }
 
+/// 扫描进程内存查找 CSRF Token
+///
+/// 先扫描堆和匿名映射（Token 是运行时数据），再扫描其余可读映射；
+/// 每次系统调用用多个 iovec 批量读取，多个线程并行处理分块，
+/// 并在每批读取前检查截止时间和取消标志。
 pub(super) fn scan_process_for_token(
     pid: u32,
     uuid_re: &Regex,
     patterns: &(Vec<u8>, Vec<u8>),
-) -> Result<Option<String>> {
+    options: &MemoryScanOptions,
+) -> Result<(Option<String>, MemoryScanStats)> {
+    let started = Instant::now();
+    let regions = readable_regions(pid)?;
+
+    let overlap = patterns.0.len().max(patterns.1.len()) + SCAN_AHEAD;
+    let mut chunks = Vec::new();
+    for region in &regions {
+        let cap_end = (region.start + options.max_region_bytes as u64).min(region.end);
+        let mut cursor = region.start;
+        while cursor < cap_end {
+            let len = ((cap_end - cursor) as usize).min(CHUNK_SIZE);
+            chunks.push(Chunk { address: cursor, len });
+            if cursor + len as u64 >= cap_end {
+                break;
+            }
+            // 相邻分块重叠，避免 Token 跨块被截断
+            cursor += len.saturating_sub(overlap).max(1) as u64;
+        }
+    }
+
+    let workers = options.workers.clamp(1, chunks.len().max(1));
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let token: Mutex<Option<String>> = Mutex::new(None);
+    let outcome: Mutex<Option<MemoryScanOutcome>> = Mutex::new(None);
+    let failure: Mutex<Option<io::Error>> = Mutex::new(None);
+    let chunks_scanned = AtomicUsize::new(0);
+    let chunks_failed = AtomicUsize::new(0);
+    let read_calls = AtomicU64::new(0);
+    let bytes_read = AtomicU64::new(0);
+
+    let finish = |result: MemoryScanOutcome| {
+        outcome.lock().unwrap().get_or_insert(result);
+        stop.store(true, Ordering::Relaxed);
+    };
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                let mut buffers: Vec<Vec<u8>> = (0..READ_BATCH).map(|_| vec![0u8; CHUNK_SIZE]).collect();
+
+                while !stop.load(Ordering::Relaxed) {
+                    if let Some(interrupted) = options.interrupted() {
+                        finish(interrupted);
+                        break;
+                    }
+
+                    let first = next.fetch_add(READ_BATCH, Ordering::Relaxed);
+                    if first >= chunks.len() {
+                        break;
+                    }
+                    let batch = &chunks[first..(first + READ_BATCH).min(chunks.len())];
+
+                    let lens = match read_batch(pid, batch, &mut buffers, &read_calls) {
+                        Ok(lens) => lens,
+                        Err(e) if e.raw_os_error() == Some(libc::ESRCH) => {
+                            finish(MemoryScanOutcome::ProcessExited);
+                            break;
+                        }
+                        Err(e) => {
+                            failure.lock().unwrap().get_or_insert(e);
+                            stop.store(true, Ordering::Relaxed);
+                            break;
+                        }
+                    };
+
+                    for (buffer, len) in buffers.iter().zip(lens) {
+                        if len == 0 {
+                            chunks_failed.fetch_add(1, Ordering::Relaxed);
+                            continue;
+                        }
+                        chunks_scanned.fetch_add(1, Ordering::Relaxed);
+                        bytes_read.fetch_add(len as u64, Ordering::Relaxed);
+
+                        if let Some(found) = search_bytes_for_token(&buffer[..len], uuid_re, patterns) {
+                            token.lock().unwrap().get_or_insert(found);
+                            finish(MemoryScanOutcome::Found);
+                            break;
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    let token = token.into_inner().unwrap();
+    let stats = MemoryScanStats {
+        pid,
+        outcome: outcome.into_inner().unwrap().unwrap_or(MemoryScanOutcome::NotFound),
+        regions_total: regions.len(),
+        priority_regions: regions.iter().filter(|r| r.priority <= 1).count(),
+        chunks_total: chunks.len(),
+        chunks_scanned: chunks_scanned.into_inner(),
+        chunks_failed: chunks_failed.into_inner(),
+        read_calls: read_calls.into_inner(),
+        bytes_read: bytes_read.into_inner(),
+        workers,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    };
+
+    tracing::info!(
+        pid,
+        outcome = ?stats.outcome,
+        chunks = stats.chunks_scanned,
+        bytes = stats.bytes_read,
+        calls = stats.read_calls,
+        elapsed_ms = stats.elapsed_ms,
+        "🔍 内存扫描结束"
+    );
+    *LAST_SCAN.lock().unwrap() = Some(stats.clone());
+
+    if token.is_none() {
+        if let Some(e) = failure.into_inner().unwrap() {
+            return Err(anyhow!("读取进程 {pid} 内存失败: {e}"));
+        }
+    }
+
+    Ok((token, stats))
+}
+
+/// 解析 `/proc/<pid>/maps`，返回按优先级排序的可读区域
+fn readable_regions(pid: u32) -> Result<Vec<Region>> {
     let maps_path = format!("/proc/{pid}/maps");
     let maps = fs::read_to_string(&maps_path).with_context(|| format!("读取 {maps_path} 失败"))?;
 
     let mut regions = Vec::new();
     for line in maps.lines() {
-        let mut parts = line.split_whitespace();
-        if let Some(range) = parts.next() {
-            if let Some(perms) = parts.next() {
-                if !perms.contains('r') {
+        // 格式: start-end perms offset dev inode [pathname]
+        let mut fields = line.splitn(6, ' ');
+        let (Some(range), Some(perms)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let path = fields.nth(3).unwrap_or_default().trim();
+
+        if !perms.starts_with('r') || matches!(path, "[vvar]" | "[vsyscall]" | "[vdso]") {
+            continue;
+        }
+        let Some((start, end)) = range.split_once('-') else {
+            continue;
+        };
+        let (Ok(start), Ok(end)) = (u64::from_str_radix(start, 16), u64::from_str_radix(end, 16)) else {
+            continue;
+        };
+        if end <= start {
+            continue;
+        }
+
+        let priority = match path {
+            "[heap]" => 0,
+            "" | "[stack]" => 1,
+            p if p.starts_with("[anon") => 1,
+            _ if perms.as_bytes().get(1) == Some(&b'w') => 2,
+            _ => 3,
+        };
+        regions.push(Region { start, end, priority });
+    }
+
+    regions.sort_by_key(|r| r.priority);
+    Ok(regions)
+}
+
+/// 用 `process_vm_readv` 批量读取分块，返回每个分块实际读取的字节数（0 表示不可读）
+///
+/// 遇到不可读的分块时系统调用会在该处停止，此时跳过该分块继续读取后面的部分
+fn read_batch(
+    pid: u32,
+    batch: &[Chunk],
+    buffers: &mut [Vec<u8>],
+    read_calls: &AtomicU64,
+) -> io::Result<Vec<usize>> {
+    let mut lens = vec![0usize; batch.len()];
+    let mut first = 0;
+
+    while first < batch.len() {
+        let local: Vec<libc::iovec> = buffers[first..batch.len()]
+            .iter_mut()
+            .zip(&batch[first..])
+            .map(|(buffer, chunk)| libc::iovec {
+                iov_base: buffer.as_mut_ptr().cast(),
+                iov_len: chunk.len,
+            })
+            .collect();
+        let remote: Vec<libc::iovec> = batch[first..]
+            .iter()
+            .map(|chunk| libc::iovec {
+                iov_base: chunk.address as *mut libc::c_void,
+                iov_len: chunk.len,
+            })
+            .collect();
+
+        read_calls.fetch_add(1, Ordering::Relaxed);
+        // SAFETY: 本地 iovec 指向长度不小于 chunk.len 的缓冲区，远端地址只由内核读取
+        let read = unsafe {
+            libc::process_vm_readv(
+                pid as libc::pid_t,
+                local.as_ptr(),
+                local.len() as libc::c_ulong,
+                remote.as_ptr(),
+                remote.len() as libc::c_ulong,
+                0,
+            )
+        };
+
+        if read < 0 {
+            let err = io::Error::last_os_error();
+            match err.raw_os_error() {
+                // 第一个分块不可读（映射已变化或被保护）
+                Some(libc::EFAULT) | Some(libc::EIO) => {
+                    first += 1;
                     continue;
                 }
-                let mut segs = range.split('-');
-                if let (Some(s), Some(e)) = (segs.next(), segs.next()) {
-                    if let (Ok(start), Ok(end)) = (u64::from_str_radix(s, 16), u64::from_str_radix(e, 16)) {
-                        if end > start {
-                            regions.push(Region { start, end });
-                        }
-                    }
-                }
+                _ => return Err(err),
             }
         }
+
+        let mut remaining = read as usize;
+        let mut index = first;
+        while index < batch.len() && remaining >= batch[index].len {
+            lens[index] = batch[index].len;
+            remaining -= batch[index].len;
+            index += 1;
+        }
+        if index < batch.len() {
+            // 读取在该分块中途停止，保留已读部分并跳过其余
+            lens[index] = remaining;
+            index += 1;
+        }
+        first = index;
     }
 
-    let handle: ProcessHandle = (pid as Pid).try_into().map_err(|e| anyhow!("打开进程用于读取失败: {e}"))?;
+    Ok(lens)
+}
 
-    let overlap = patterns.0.len().max(patterns.1.len()) + SCAN_AHEAD;
+/// TCP 监听状态（`/proc/net/tcp` 中 `st` 列的取值）
+const TCP_LISTEN: &str = "0A";
 
-    for region in regions {
-        let mut cursor = region.start;
-        let region_cap_end = (region.start + (MAX_REGION_BYTES as u64)).min(region.end);
-        while cursor < region_cap_end {
-            let remaining = (region_cap_end - cursor) as usize;
-            if remaining == 0 {
-                break;
-            }
-            let chunk_size = remaining.min(CHUNK_SIZE);
-
-            let mut buffer = vec![0u8; chunk_size];
-            let read_res = handle
-                .copy_address(cursor as usize, &mut buffer)
-                .map(|_| chunk_size);
-
-            let read = match read_res {
-                Ok(n) => n,
-                Err(e) => {
-                    let step = chunk_size.saturating_sub(overlap).max(1) as u64;
-                    cursor = cursor.saturating_add(step);
-                    tracing::debug!(pid, cursor, "读取 0x{:x} 失败: {e}", cursor);
-                    continue;
-                }
-            };
+/// 获取进程正在监听的 TCP 端口
+///
+/// 先从 `/proc/<pid>/fd` 收集进程持有的 socket inode，
+/// 再在 `/proc/<pid>/net/tcp{,6}` 中找出状态为 LISTEN 且 inode 属于该进程的条目。
+pub(super) fn listening_ports(pid: u32) -> Result<Vec<u16>> {
+    let inodes = socket_inodes(pid)?;
+    if inodes.is_empty() {
+        return Ok(Vec::new());
+    }
 
-            buffer.truncate(read);
-            if let Some(token) = search_bytes_for_token(&buffer, uuid_re, patterns) {
-                return Ok(Some(token));
+    let mut ports = Vec::new();
+    for table in ["tcp", "tcp6"] {
+        let path = format!("/proc/{pid}/net/{table}");
+        let content = match fs::read_to_string(&path) {
+            Ok(c) => c,
+            Err(e) => {
+                tracing::debug!(pid, "读取 {path} 失败: {e}");
+                continue;
             }
+        };
 
-            let step = read.saturating_sub(overlap).max(1) as u64;
-            cursor = cursor.saturating_add(step);
+        // 格式: sl local_address rem_address st tx_queue:rx_queue tr:tm->when retrnsmt uid timeout inode ...
+        for line in content.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 || fields[3] != TCP_LISTEN {
+                continue;
+            }
+            let Ok(inode) = fields[9].parse::<u64>() else {
+                continue;
+            };
+            if !inodes.contains(&inode) {
+                continue;
+            }
+            if let Some(port) = fields[1]
+                .rsplit(':')
+                .next()
+                .and_then(|p| u16::from_str_radix(p, 16).ok())
+            {
+                ports.push(port);
+            }
         }
     }
 
-    Ok(None)
+    ports.sort_unstable();
+    ports.dedup();
+    Ok(ports)
+}
+
+/// 收集进程持有的 socket inode（fd 链接形如 `socket:[12345]`）
+fn socket_inodes(pid: u32) -> Result<HashSet<u64>> {
+    let fd_dir = format!("/proc/{pid}/fd");
+    let entries = fs::read_dir(&fd_dir).with_context(|| format!("读取 {fd_dir} 失败"))?;
+
+    let inodes = entries
+        .flatten()
+        .filter_map(|entry| fs::read_link(entry.path()).ok())
+        .filter_map(|target| {
+            target
+                .to_str()?
+                .strip_prefix("socket:[")?
+                .strip_suffix(']')?
+                .parse::<u64>()
+                .ok()
+        })
+        .collect();
+
+    Ok(inodes)
 }
@@ -0,0 +1,382 @@
+//! Antigravity.log 增量跟踪
+//!
+//! 跟随最新的 `Antigravity.log`（新会话创建新日志时自动切换），每次只读取新追加的字节，
+//! 把关键行解析为类型化事件（语言服务器启动、端口分配、认证错误、崩溃等）放入有界环形缓冲区。
+//! 同时维护日志中最近一次出现的端口，供端口发现使用，不再反复读取整个文件。
+//! 后台任务按 `antigravity-log-events` 事件推送新事件，也可通过命令按序号增量查询。
+
+use regex::Regex;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tracing::{debug, error, info};
+
+use super::types::PortInfo;
+use super::utils::{find_latest_antigravity_log, parse_ports_from_log};
+
+/// 新日志事件推送的事件名
+pub const LOG_EVENTS_EVENT: &str = "antigravity-log-events";
+
+/// 环形缓冲区容量
+const EVENT_CAPACITY: usize = 1000;
+
+/// 重新查找最新日志文件的间隔（查找需要遍历日志目录）
+const REDISCOVER_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 单次读取的最大字节数，首次接入大文件时分多次追上
+const MAX_READ_PER_POLL: u64 = 4 * 1024 * 1024;
+
+/// 后台轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 单行保存的最大长度
+const MAX_LINE_CHARS: usize = 2000;
+
+/// 日志事件类型
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum LogEventKind {
+    /// 开始跟踪新的日志文件（首次接入或日志轮换）
+    #[serde(rename_all = "camelCase")]
+    LogRotated { path: String, previous: Option<String> },
+    /// 语言服务器启动
+    ServerStarted { pid: Option<u32> },
+    /// 端口分配
+    #[serde(rename_all = "camelCase")]
+    PortsAssigned {
+        https_port: Option<u16>,
+        http_port: Option<u16>,
+        extension_port: Option<u16>,
+    },
+    /// 认证错误（CSRF Token 被拒、未登录等）
+    AuthError,
+    /// 崩溃或异常退出
+    Crash,
+    /// 其他 error 级别日志
+    Error,
+}
+
+/// 日志事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEvent {
+    /// 单调递增序号，用于增量查询
+    pub seq: u64,
+    /// 读取到该行的时间（毫秒时间戳）
+    pub received_at: i64,
+    /// 日志行自带的时间
+    pub log_time: Option<String>,
+    #[serde(flatten)]
+    pub kind: LogEventKind,
+    pub line: String,
+}
+
+/// 预编译的事件匹配规则
+struct Patterns {
+    log_time: Regex,
+    server_started: Regex,
+    pid: Regex,
+    auth_error: Regex,
+    crash: Regex,
+    error_level: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        log_time: Regex::new(r"^(\d{4}-\d{2}-\d{2}[ T]\d{2}:\d{2}:\d{2}(?:\.\d+)?)").unwrap(),
+        server_started: Regex::new(r"(?i)language[ _]server.*\b(started|starting|launched)\b").unwrap(),
+        pid: Regex::new(r"(?i)\bpid[=:\s]+(\d+)").unwrap(),
+        auth_error: Regex::new(
+            r"(?i)(unauthenticated|unauthorized|invalid csrf|csrf token (is )?(invalid|missing)|auth(entication)? (error|failed)|\b(401|403)\b)",
+        )
+        .unwrap(),
+        crash: Regex::new(
+            r"(?i)(\bcrash(ed)?\b|\bpanic(ked)?\b|exited with code [1-9]|terminated unexpectedly|killed by signal|sigsegv|fatal error)",
+        )
+        .unwrap(),
+        error_level: Regex::new(r"(?i)\[error\]").unwrap(),
+    })
+}
+
+/// 把一行日志解析为事件类型，无关行返回 `None`
+fn classify(line: &str) -> Option<LogEventKind> {
+    let p = patterns();
+
+    let (https_port, http_port, extension_port) = parse_ports_from_log(line);
+    if https_port.is_some() || http_port.is_some() || extension_port.is_some() {
+        return Some(LogEventKind::PortsAssigned {
+            https_port,
+            http_port,
+            extension_port,
+        });
+    }
+    if p.crash.is_match(line) {
+        return Some(LogEventKind::Crash);
+    }
+    if p.auth_error.is_match(line) {
+        return Some(LogEventKind::AuthError);
+    }
+    if p.server_started.is_match(line) {
+        let pid = p
+            .pid
+            .captures(line)
+            .and_then(|c| c.get(1)?.as_str().parse().ok());
+        return Some(LogEventKind::ServerStarted { pid });
+    }
+    if p.error_level.is_match(line) {
+        return Some(LogEventKind::Error);
+    }
+    None
+}
+
+/// 日志跟踪器
+pub struct LogFollower {
+    path: Option<PathBuf>,
+    /// 已读取到的字节偏移
+    offset: u64,
+    /// 尚未遇到换行符的行尾
+    partial: Vec<u8>,
+    events: VecDeque<LogEvent>,
+    next_seq: u64,
+    /// 当前日志中最近一次出现的端口
+    ports: PortInfo,
+    last_discovery: Option<Instant>,
+    /// 固定跟踪指定文件，不自动查找最新日志
+    fixed_path: bool,
+}
+
+impl LogFollower {
+    pub fn new() -> Self {
+        Self {
+            path: None,
+            offset: 0,
+            partial: Vec::new(),
+            events: VecDeque::with_capacity(EVENT_CAPACITY),
+            next_seq: 1,
+            ports: PortInfo::default(),
+            last_discovery: None,
+            fixed_path: false,
+        }
+    }
+
+    /// 只跟踪指定文件（测试用）
+    #[cfg(test)]
+    pub fn with_path(path: PathBuf) -> Self {
+        Self {
+            ports: PortInfo {
+                log_path: Some(path.to_string_lossy().to_string()),
+                ..Default::default()
+            },
+            path: Some(path),
+            fixed_path: true,
+            ..Self::new()
+        }
+    }
+
+    /// 读取新追加的内容，返回本次产生的事件数
+    pub fn poll(&mut self) -> usize {
+        let before = self.next_seq;
+        self.refresh_path();
+
+        if let Some(path) = self.path.clone() {
+            if let Err(e) = self.read_appended(&path) {
+                debug!(target: "language_server::log_follower", "读取日志 {} 失败: {}", path.display(), e);
+                // 文件可能已被删除，下次轮询时重新查找
+                self.last_discovery = None;
+            }
+        }
+
+        (self.next_seq - before) as usize
+    }
+
+    /// 连续读取直到追上文件末尾（最多若干轮，避免超大文件长时间占用）
+    pub fn catch_up(&mut self) {
+        for _ in 0..16 {
+            let offset = self.offset;
+            self.poll();
+            if self.offset == offset {
+                break;
+            }
+        }
+    }
+
+    /// 序号大于 `since` 的事件（按序号升序），最多 `limit` 条（取最新的）
+    pub fn events_since(&self, since: u64, limit: usize) -> Vec<LogEvent> {
+        let matching: Vec<&LogEvent> = self.events.iter().filter(|e| e.seq > since).collect();
+        let skip = matching.len().saturating_sub(limit);
+        matching.into_iter().skip(skip).cloned().collect()
+    }
+
+    /// 当前日志中最近一次出现的端口
+    pub fn ports(&self) -> PortInfo {
+        self.ports.clone()
+    }
+
+    /// 按间隔重新查找最新日志，文件变化时从头开始跟踪新文件
+    fn refresh_path(&mut self) {
+        if self.fixed_path {
+            return;
+        }
+        let due = self.path.as_ref().map_or(true, |p| !p.exists())
+            || self.last_discovery.map_or(true, |t| t.elapsed() >= REDISCOVER_INTERVAL);
+        if !due {
+            return;
+        }
+        self.last_discovery = Some(Instant::now());
+
+        let newest = find_latest_antigravity_log();
+        if newest.is_none() || newest == self.path {
+            return;
+        }
+
+        let previous = self.path.take();
+        self.path = newest;
+        self.offset = 0;
+        self.partial.clear();
+        self.ports = PortInfo {
+            log_path: self.path.as_ref().map(|p| p.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+
+        let path = self.path.as_ref().map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
+        info!(target: "language_server::log_follower", "📄 开始跟踪日志: {}", path);
+        self.push(
+            LogEventKind::LogRotated {
+                path: path.clone(),
+                previous: previous.map(|p| p.to_string_lossy().to_string()),
+            },
+            None,
+            path,
+        );
+    }
+
+    fn read_appended(&mut self, path: &Path) -> std::io::Result<()> {
+        let mut file = File::open(path)?;
+        let len = file.metadata()?.len();
+
+        // 文件被截断（同名重建）时从头读取
+        if len < self.offset {
+            info!(target: "language_server::log_follower", "日志文件被截断，从头读取: {}", path.display());
+            self.offset = 0;
+            self.partial.clear();
+        }
+        if len == self.offset {
+            return Ok(());
+        }
+
+        let to_read = (len - self.offset).min(MAX_READ_PER_POLL);
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut buf = Vec::with_capacity(to_read as usize);
+        file.take(to_read).read_to_end(&mut buf)?;
+        self.offset += buf.len() as u64;
+
+        self.partial.extend_from_slice(&buf);
+        let Some(last_newline) = self.partial.iter().rposition(|b| *b == b'\n') else {
+            return Ok(());
+        };
+        let complete: Vec<u8> = self.partial.drain(..=last_newline).collect();
+
+        for line in String::from_utf8_lossy(&complete).lines() {
+            self.process_line(line.trim_end_matches('\r'));
+        }
+        Ok(())
+    }
+
+    fn process_line(&mut self, line: &str) {
+        let Some(kind) = classify(line) else {
+            return;
+        };
+
+        if let LogEventKind::PortsAssigned { https_port, http_port, extension_port } = &kind {
+            self.ports.https_port = https_port.or(self.ports.https_port);
+            self.ports.http_port = http_port.or(self.ports.http_port);
+            self.ports.extension_port = extension_port.or(self.ports.extension_port);
+        }
+
+        let log_time = patterns()
+            .log_time
+            .captures(line)
+            .and_then(|c| c.get(1))
+            .map(|m| m.as_str().to_string());
+        self.push(kind, log_time, line.chars().take(MAX_LINE_CHARS).collect());
+    }
+
+    fn push(&mut self, kind: LogEventKind, log_time: Option<String>, line: String) {
+        if self.events.len() >= EVENT_CAPACITY {
+            self.events.pop_front();
+        }
+        self.events.push_back(LogEvent {
+            seq: self.next_seq,
+            received_at: chrono::Local::now().timestamp_millis(),
+            log_time,
+            kind,
+            line,
+        });
+        self.next_seq += 1;
+    }
+}
+
+/// 全局日志跟踪器
+pub fn get_log_follower() -> &'static Mutex<LogFollower> {
+    static FOLLOWER: OnceLock<Mutex<LogFollower>> = OnceLock::new();
+    FOLLOWER.get_or_init(|| Mutex::new(LogFollower::new()))
+}
+
+/// 追上日志后返回其中最近一次出现的端口
+///
+/// 追赶期间持有全局锁并可能读取大量数据，只能在阻塞线程中调用
+pub fn current_ports() -> PortInfo {
+    match get_log_follower().lock() {
+        Ok(mut follower) => {
+            follower.catch_up();
+            follower.ports()
+        }
+        Err(e) => {
+            error!(target: "language_server::log_follower", "日志跟踪器锁异常: {}", e);
+            PortInfo::default()
+        }
+    }
+}
+
+/// 查询序号大于 `since` 的事件
+pub fn events_since(since: u64, limit: usize) -> Vec<LogEvent> {
+    get_log_follower()
+        .lock()
+        .map(|follower| follower.events_since(since, limit))
+        .unwrap_or_default()
+}
+
+/// 启动后台日志跟踪（应用生命周期内只调用一次），新事件按批推送
+pub fn spawn_log_follower(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        info!(target: "language_server::log_follower", "🚀 日志跟踪任务已启动");
+        let mut last_emitted = 0;
+
+        loop {
+            let batch = tokio::task::spawn_blocking(move || {
+                let mut follower = get_log_follower().lock().ok()?;
+                follower.poll();
+                Some(follower.events_since(last_emitted, EVENT_CAPACITY))
+            })
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+
+            if let Some(last) = batch.last() {
+                last_emitted = last.seq;
+                if let Err(e) = app_handle.emit(LOG_EVENTS_EVENT, &batch) {
+                    error!(target: "language_server::log_follower", "❌ 推送 {} 事件失败: {}", LOG_EVENTS_EVENT, e);
+                }
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
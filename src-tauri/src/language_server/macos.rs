@@ -88,3 +88,18 @@ pub(super) fn scan_process_for_token(
 
     Ok(None)
 }
+
+/// 获取进程正在监听的 TCP 端口（通过 `lsof`）
+pub(super) fn listening_ports(pid: u32) -> Result<Vec<u16>> {
+    let output = std::process::Command::new("lsof")
+        .args(["-nP", "-a", "-p", &pid.to_string(), "-iTCP", "-sTCP:LISTEN", "-Fn"])
+        .output()
+        .map_err(|e| anyhow!("执行 lsof 失败: {e}"))?;
+
+    // 进程没有监听端口时 lsof 以状态码 1 退出且没有输出
+    if !output.status.success() && !output.stdout.is_empty() {
+        return Err(anyhow!("lsof 执行失败: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    Ok(crate::language_server::utils::parse_lsof_listen_ports(&String::from_utf8_lossy(&output.stdout)))
+}
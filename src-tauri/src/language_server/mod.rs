@@ -1,15 +1,24 @@
+pub mod args;
+pub mod client;
 pub mod commands;
 pub mod utils;
 pub mod cache;
 pub mod types;
 pub mod cmdline_detector;
 pub mod debug_commands;
+pub mod log_follower;
+pub mod readiness;
+pub mod tls;
 #[cfg(target_os = "windows")]
 pub mod windows;
 #[cfg(target_os = "linux")]
 pub mod linux;
 #[cfg(target_os = "macos")]
 pub mod macos;
+#[cfg(test)]
+pub mod test_support;
+#[cfg(test)]
+mod tests;
 
 pub use commands::*;
 pub use debug_commands::*;
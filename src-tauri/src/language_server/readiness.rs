@@ -0,0 +1,226 @@
+//! 语言服务器就绪状态
+//!
+//! 后台监视器根据进程检测、监听端口/日志端口和轻量探测调用维护一个状态机：
+//! 未运行 → 进程已启动 → 端口已知 → 就绪；启动宽限期过后仍无法推进时进入错误状态。
+//! 状态变化时推送 `language-server-readiness` 事件，也可通过命令查询，
+//! 切换账户流程和配额采样通过 `wait_until_ready` 等待就绪，而不是固定等待。
+
+use serde::Serialize;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{watch, Notify};
+use tracing::{debug, error, info};
+
+use super::client::get_client;
+use super::cmdline_detector::ProcessInfo;
+
+/// 就绪状态变化事件名
+pub const READINESS_EVENT: &str = "language-server-readiness";
+
+/// 尚未就绪时的检查间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 就绪后的复查间隔
+const READY_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Antigravity 未运行时的检查间隔
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 语言服务器启动后的宽限期（秒），期间端口未知或探测失败不视为错误
+const STARTUP_GRACE_SECS: u64 = 60;
+
+/// 就绪状态
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum ReadinessState {
+    /// Antigravity 未运行
+    NotRunning,
+    /// Antigravity 已运行，语言服务器进程已启动（或尚未检测到）但端口未知
+    #[serde(rename_all = "camelCase")]
+    ProcessUp { instance_id: Option<String> },
+    /// 已知端口，探测尚未通过
+    #[serde(rename_all = "camelCase")]
+    PortsKnown { instance_id: String, ports: Vec<u16> },
+    /// 探测通过（CSRF Token 被接受），可以调用
+    #[serde(rename_all = "camelCase")]
+    Ready { instance_id: String, port: u16 },
+    /// 启动宽限期后仍无法就绪
+    Error { message: String },
+}
+
+/// 就绪状态及进入时间
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadinessStatus {
+    #[serde(flatten)]
+    pub state: ReadinessState,
+    /// 进入当前状态的时间（毫秒时间戳）
+    pub since: i64,
+}
+
+impl ReadinessStatus {
+    fn new(state: ReadinessState) -> Self {
+        Self {
+            state,
+            since: chrono::Local::now().timestamp_millis(),
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        matches!(self.state, ReadinessState::Ready { .. })
+    }
+}
+
+struct Readiness {
+    status: watch::Sender<ReadinessStatus>,
+    /// 唤醒监视器立即检查
+    wake: Notify,
+}
+
+static READINESS: OnceLock<Readiness> = OnceLock::new();
+
+fn readiness() -> &'static Readiness {
+    READINESS.get_or_init(|| Readiness {
+        status: watch::channel(ReadinessStatus::new(ReadinessState::NotRunning)).0,
+        wake: Notify::new(),
+    })
+}
+
+/// 当前就绪状态
+pub fn current() -> ReadinessStatus {
+    readiness().status.borrow().clone()
+}
+
+/// 请求监视器立即检查一次（如刚启动 Antigravity）
+pub fn request_check() {
+    readiness().wake.notify_one();
+}
+
+/// 等待语言服务器就绪，已就绪时立即返回
+pub async fn wait_until_ready(timeout: Duration) -> Result<ReadinessStatus, String> {
+    wait_until_ready_since(i64::MIN, timeout).await
+}
+
+/// 等待在 `since`（毫秒时间戳）之后进入的就绪状态
+///
+/// 重启 Antigravity 后使用，避免把重启前的就绪状态误认为新进程已就绪
+pub async fn wait_until_ready_since(since: i64, timeout: Duration) -> Result<ReadinessStatus, String> {
+    let is_fresh_ready = |status: &ReadinessStatus| status.is_ready() && status.since >= since;
+
+    let mut rx = readiness().status.subscribe();
+    if is_fresh_ready(&rx.borrow()) {
+        return Ok(rx.borrow().clone());
+    }
+
+    request_check();
+    let result = match tokio::time::timeout(timeout, rx.wait_for(is_fresh_ready)).await {
+        Ok(Ok(status)) => Ok(status.clone()),
+        Ok(Err(_)) => Err("就绪状态监视器已停止".to_string()),
+        Err(_) => Err(format!(
+            "等待语言服务器就绪超时（{}s），当前状态: {:?}",
+            timeout.as_secs(),
+            current().state
+        )),
+    };
+    result
+}
+
+/// 启动就绪状态监视器（应用生命周期内只调用一次）
+pub fn spawn_watcher(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        info!(target: "language_server::readiness", "🚀 语言服务器就绪状态监视器已启动");
+
+        loop {
+            let state = check().await;
+            let delay = match state {
+                ReadinessState::NotRunning => IDLE_CHECK_INTERVAL,
+                ReadinessState::Ready { .. } => READY_CHECK_INTERVAL,
+                _ => CHECK_INTERVAL,
+            };
+
+            let changed = readiness().status.send_if_modified(|status| {
+                if status.state == state {
+                    return false;
+                }
+                *status = ReadinessStatus::new(state.clone());
+                true
+            });
+
+            if changed {
+                let status = current();
+                info!(target: "language_server::readiness", state = ?status.state, "🔄 语言服务器就绪状态变化");
+                if let Err(e) = app_handle.emit(READINESS_EVENT, &status) {
+                    error!(target: "language_server::readiness", "❌ 推送 {} 事件失败: {}", READINESS_EVENT, e);
+                }
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = readiness().wake.notified() => {}
+            }
+        }
+    });
+}
+
+/// 执行一轮检查：进程检测 → 端口 → 探测
+async fn check() -> ReadinessState {
+    let running = tokio::task::spawn_blocking(crate::platform::is_antigravity_running)
+        .await
+        .unwrap_or(false);
+    if !running {
+        return ReadinessState::NotRunning;
+    }
+
+    let client = match get_client() {
+        Ok(client) => client,
+        Err(e) => return ReadinessState::Error { message: e.to_string() },
+    };
+
+    let instance = match client.instances().await {
+        Ok(instances) => instances.into_iter().next(),
+        Err(e) => {
+            debug!(target: "language_server::readiness", "尚未检测到语言服务器实例: {}", e);
+            None
+        }
+    };
+    let Some(instance) = instance else {
+        return ReadinessState::ProcessUp { instance_id: None };
+    };
+
+    let ports = client.known_ports(&instance).await;
+    if ports.is_empty() {
+        return after_grace(
+            &instance,
+            ReadinessState::ProcessUp {
+                instance_id: Some(instance.id.clone()),
+            },
+            "语言服务器没有可用端口".to_string(),
+        );
+    }
+
+    match client.ping(Some(&instance.id)).await {
+        Ok(endpoint) => ReadinessState::Ready {
+            instance_id: endpoint.instance_id,
+            port: endpoint.port,
+        },
+        Err(e) => after_grace(
+            &instance,
+            ReadinessState::PortsKnown {
+                instance_id: instance.id.clone(),
+                ports,
+            },
+            e.to_string(),
+        ),
+    }
+}
+
+/// 启动宽限期内保持当前阶段，超过后报告错误
+fn after_grace(instance: &ProcessInfo, pending: ReadinessState, message: String) -> ReadinessState {
+    let uptime = (chrono::Utc::now().timestamp().max(0) as u64).saturating_sub(instance.start_time);
+    if uptime > STARTUP_GRACE_SECS {
+        ReadinessState::Error { message }
+    } else {
+        pending
+    }
+}
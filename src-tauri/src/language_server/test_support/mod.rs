@@ -0,0 +1,36 @@
+//! 语言服务器测试支持
+//!
+//! - `process`: 伪造的进程信息来源（进程列表、命令行、监听端口、存活状态）
+//! - `server`: 本地 HTTPS 替身语言服务器（自签名证书，真实 Connect 路径，校验 CSRF 头）
+//!
+//! 两者配合即可在 `cargo test` 中离线验证端口发现、缓存、重试与响应解析。
+
+pub mod process;
+pub mod server;
+
+use std::sync::Arc;
+
+pub use process::{language_server_argv, FakeProcess, FakeProcessSource};
+pub use server::{sample_user_status, FakeLanguageServer, RecordedRequest};
+
+use super::cache::CacheManager;
+use super::client::LanguageServerClient;
+use super::types::{CacheConfig, HttpConfig};
+
+/// 测试用 HTTP 配置：短超时、短退避
+pub fn test_http_config() -> HttpConfig {
+    HttpConfig {
+        request_timeout_ms: 2000,
+        probe_timeout_ms: 1000,
+        max_retries: 2,
+        retry_backoff_ms: 10,
+    }
+}
+
+/// 构建只使用伪造进程的客户端（独立缓存，不影响全局状态）
+pub fn test_client(source: Arc<FakeProcessSource>) -> (LanguageServerClient, Arc<CacheManager>) {
+    let cache = Arc::new(CacheManager::with_source(CacheConfig::default(), source));
+    let client = LanguageServerClient::with_cache(test_http_config(), cache.clone())
+        .expect("构建测试客户端失败");
+    (client, cache)
+}
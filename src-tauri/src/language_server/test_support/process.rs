@@ -0,0 +1,166 @@
+//! 伪造的进程信息来源
+
+use anyhow::{anyhow, Result};
+use std::sync::{Arc, Mutex};
+
+use crate::language_server::cmdline_detector::{ProcessEntry, ProcessSource};
+use crate::language_server::types::ServerIdentity;
+
+/// 伪造进程
+#[derive(Debug, Clone)]
+pub struct FakeProcess {
+    pub pid: u32,
+    pub name: String,
+    pub start_time: u64,
+    /// 命令行参数（argv）
+    pub cmdline: Vec<String>,
+    /// 监听端口，`None` 表示模拟无法获取监听端口的平台
+    pub listening_ports: Option<Vec<u16>>,
+    /// 所属进程 PID，`Some` 表示这是 Linux 上 `/proc/<pid>/task/<tid>` 形式的线程条目
+    pub thread_of: Option<u32>,
+}
+
+impl FakeProcess {
+    /// 携带 CSRF Token 与端口参数的语言服务器进程
+    pub fn language_server(pid: u32, start_time: u64, csrf_token: &str, port: u16) -> Self {
+        Self {
+            pid,
+            name: "language_server_linux_x64".to_string(),
+            start_time,
+            cmdline: language_server_argv(csrf_token, Some(port), Some(port)),
+            listening_ports: Some(vec![port]),
+            thread_of: None,
+        }
+    }
+
+    /// 与语言服务器无关的进程
+    pub fn other(pid: u32, name: &str) -> Self {
+        Self {
+            pid,
+            name: name.to_string(),
+            start_time: 1,
+            cmdline: vec![format!("/usr/bin/{}", name)],
+            listening_ports: Some(Vec::new()),
+            thread_of: None,
+        }
+    }
+
+    /// 进程 `leader` 的线程：与所属进程同名、同命令行，但有自己的 TID 和启动时间
+    pub fn thread(tid: u32, start_time: u64, leader: &FakeProcess) -> Self {
+        Self {
+            pid: tid,
+            start_time,
+            thread_of: Some(leader.pid),
+            ..leader.clone()
+        }
+    }
+
+    fn identity(&self) -> ServerIdentity {
+        ServerIdentity {
+            pid: self.pid,
+            start_time: self.start_time,
+        }
+    }
+}
+
+/// 构造语言服务器命令行参数
+pub fn language_server_argv(
+    csrf_token: &str,
+    extension_port: Option<u16>,
+    api_port: Option<u16>,
+) -> Vec<String> {
+    let mut argv: Vec<String> = [
+        "/opt/antigravity/resources/app/extensions/antigravity/bin/language_server_linux_x64",
+        "--enable_lsp",
+        "--csrf_token",
+        csrf_token,
+        "--workspace_id",
+        "file_home_user_project",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    if let Some(port) = extension_port {
+        argv.push(format!("--extension_server_port={}", port));
+    }
+    if let Some(port) = api_port {
+        argv.extend(["--api_server_port".to_string(), port.to_string()]);
+    }
+    argv
+}
+
+/// 伪造的进程信息来源，可在测试中随时增删、重启进程
+#[derive(Debug, Default)]
+pub struct FakeProcessSource {
+    processes: Mutex<Vec<FakeProcess>>,
+}
+
+impl FakeProcessSource {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    pub fn add(&self, process: FakeProcess) {
+        self.processes.lock().unwrap().push(process);
+    }
+
+    /// 结束进程
+    pub fn kill(&self, pid: u32) {
+        self.processes.lock().unwrap().retain(|p| p.pid != pid);
+    }
+
+    /// 以新的启动时间重启进程（PID 不变，可选更换 CSRF Token 与端口）
+    pub fn restart(&self, pid: u32, start_time: u64, csrf_token: &str, port: u16) {
+        let mut processes = self.processes.lock().unwrap();
+        if let Some(process) = processes.iter_mut().find(|p| p.pid == pid) {
+            *process = FakeProcess {
+                name: process.name.clone(),
+                ..FakeProcess::language_server(pid, start_time, csrf_token, port)
+            };
+        }
+    }
+
+    fn find(&self, pid: u32) -> Option<FakeProcess> {
+        self.processes
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|p| p.pid == pid)
+            .cloned()
+    }
+}
+
+/// 与 `SystemProcessSource` 一致：`processes()` 不列出线程条目，
+/// 但按 TID 查询命令行、存活状态时仍能查到（Linux 上 `/proc/<tid>` 可以直接访问）
+impl ProcessSource for FakeProcessSource {
+    fn processes(&self) -> Vec<ProcessEntry> {
+        self.processes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|p| p.thread_of.is_none())
+            .map(|p| ProcessEntry {
+                pid: p.pid,
+                name: p.name.clone(),
+                start_time: p.start_time,
+            })
+            .collect()
+    }
+
+    fn cmdline(&self, pid: u32) -> Result<Vec<String>> {
+        self.find(pid)
+            .map(|p| p.cmdline)
+            .ok_or_else(|| anyhow!("进程 {} 不存在", pid))
+    }
+
+    fn listening_ports(&self, pid: u32) -> Result<Option<Vec<u16>>> {
+        self.find(pid)
+            .map(|p| p.listening_ports)
+            .ok_or_else(|| anyhow!("进程 {} 不存在", pid))
+    }
+
+    fn is_alive(&self, identity: &ServerIdentity) -> bool {
+        self.find(identity.pid)
+            .is_some_and(|p| p.identity() == *identity)
+    }
+}
@@ -0,0 +1,289 @@
+//! 本地 HTTPS 替身语言服务器
+//!
+//! 使用自签名证书监听 127.0.0.1 的随机端口，按真实 Connect 路径实现
+//! `GetUserStatus` 与 `GetUnleashData`，并像真实服务一样校验 `X-Codeium-Csrf-Token`。
+//! 可注入错误状态码、修改响应内容、更换证书，并记录收到的每个请求。
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tokio_rustls::TlsAcceptor;
+
+use crate::language_server::client::{methods, SERVICE_PATH};
+
+/// 收到的请求
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// 请求是否携带了正确的 CSRF Token
+    pub authorized: bool,
+    pub body: serde_json::Value,
+}
+
+struct ServerState {
+    acceptor: Mutex<TlsAcceptor>,
+    csrf_token: Mutex<String>,
+    user_status: Mutex<serde_json::Value>,
+    /// 接下来的请求依次返回这些状态码（用于模拟故障）
+    injected_failures: Mutex<VecDeque<u16>>,
+    requests: Mutex<Vec<RecordedRequest>>,
+}
+
+/// 替身语言服务器，drop 时停止监听
+pub struct FakeLanguageServer {
+    port: u16,
+    state: Arc<ServerState>,
+    task: JoinHandle<()>,
+}
+
+impl FakeLanguageServer {
+    /// 启动替身服务器，只接受指定的 CSRF Token
+    pub async fn start(csrf_token: &str) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("绑定替身服务器端口失败");
+        let port = listener.local_addr().unwrap().port();
+
+        let state = Arc::new(ServerState {
+            acceptor: Mutex::new(TlsAcceptor::from(Arc::new(self_signed_config()))),
+            csrf_token: Mutex::new(csrf_token.to_string()),
+            user_status: Mutex::new(sample_user_status("tester@example.com")),
+            injected_failures: Mutex::new(VecDeque::new()),
+            requests: Mutex::new(Vec::new()),
+        });
+
+        let task_state = state.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let acceptor = task_state.acceptor.lock().unwrap().clone();
+                let state = task_state.clone();
+                tokio::spawn(async move {
+                    if let Ok(tls) = acceptor.accept(stream).await {
+                        let _ = handle_connection(tls, &state).await;
+                    }
+                });
+            }
+        });
+
+        Self { port, state, task }
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// 更换接受的 CSRF Token（模拟语言服务器重启）
+    pub fn set_csrf_token(&self, csrf_token: &str) {
+        *self.state.csrf_token.lock().unwrap() = csrf_token.to_string();
+    }
+
+    /// 换用新的自签名证书（模拟端口被其他程序占用）
+    pub fn rotate_certificate(&self) {
+        *self.state.acceptor.lock().unwrap() = TlsAcceptor::from(Arc::new(self_signed_config()));
+    }
+
+    /// 替换 GetUserStatus 的响应体
+    pub fn set_user_status(&self, body: serde_json::Value) {
+        *self.state.user_status.lock().unwrap() = body;
+    }
+
+    /// 接下来的 `count` 个请求返回指定状态码
+    pub fn fail_next(&self, status: u16, count: usize) {
+        let mut failures = self.state.injected_failures.lock().unwrap();
+        failures.extend(std::iter::repeat(status).take(count));
+    }
+
+    /// 已收到的请求
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    /// 某个方法收到的请求数
+    pub fn request_count(&self, method: &str) -> usize {
+        self.state
+            .requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.method == method)
+            .count()
+    }
+}
+
+impl Drop for FakeLanguageServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// 与真实服务结构一致的 GetUserStatus 响应（int64 编码为字符串，零值省略）
+pub fn sample_user_status(email: &str) -> serde_json::Value {
+    serde_json::json!({
+        "userStatus": {
+            "email": email,
+            "name": "Tester",
+            "acceptedLatestTermsOfService": true,
+            "planStatus": {
+                "availablePromptCredits": "480",
+                "availableFlowCredits": 950,
+                "planInfo": {
+                    "planName": "Pro",
+                    "teamsTier": "TEAMS_TIER_PRO",
+                    "monthlyPromptCredits": "500",
+                    "monthlyFlowCredits": "1000",
+                    "maxNumChatInputTokens": 16384
+                }
+            },
+            "cascadeModelConfigData": {
+                "clientModelConfigs": [
+                    {
+                        "label": "Gemini 3 Pro",
+                        "modelOrAlias": { "model": "MODEL_PLACEHOLDER_M7" },
+                        "quotaInfo": {
+                            "remainingFraction": 0.75,
+                            "resetTime": "2026-01-01T00:00:00Z"
+                        }
+                    },
+                    {
+                        "label": "Claude Sonnet 4.5",
+                        "modelOrAlias": { "model": "MODEL_PLACEHOLDER_M8" },
+                        "quotaInfo": { "resetTime": "2026-01-01T00:00:00Z" }
+                    }
+                ]
+            },
+            "someFieldFromTheFuture": { "nested": true }
+        }
+    })
+}
+
+/// 生成 127.0.0.1 / localhost 的自签名证书
+fn self_signed_config() -> rustls::ServerConfig {
+    let certified = rcgen::generate_simple_self_signed(vec![
+        "127.0.0.1".to_string(),
+        "localhost".to_string(),
+    ])
+    .expect("生成自签名证书失败");
+    let cert = CertificateDer::from(certified.cert.der().to_vec());
+    let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(certified.key_pair.serialize_der()));
+
+    rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .expect("TLS 协议版本配置失败")
+        .with_no_client_auth()
+        .with_single_cert(vec![cert], key)
+        .expect("TLS 证书配置失败")
+}
+
+/// 处理一个连接上的单个 HTTP/1.1 请求，响应后关闭连接
+async fn handle_connection<S>(stream: S, state: &ServerState) -> std::io::Result<()>
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let mut parts = request_line.split_whitespace();
+    let verb = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let length: usize = headers
+        .get("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0u8; length];
+    reader.read_exact(&mut body).await?;
+
+    let (status, response) = route(state, &verb, &path, &headers, &body);
+    let response = response.to_string();
+    let reply = format!(
+        "HTTP/1.1 {} {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        response.len(),
+        response
+    );
+
+    let mut stream = reader.into_inner();
+    stream.write_all(reply.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// 按 Connect unary 约定路由请求
+fn route(
+    state: &ServerState,
+    verb: &str,
+    path: &str,
+    headers: &HashMap<String, String>,
+    body: &[u8],
+) -> (u16, serde_json::Value) {
+    let method = path
+        .strip_prefix(&format!("/{}/", SERVICE_PATH))
+        .unwrap_or_default()
+        .to_string();
+    let authorized = headers
+        .get("x-codeium-csrf-token")
+        .is_some_and(|token| *token == *state.csrf_token.lock().unwrap());
+
+    state.requests.lock().unwrap().push(RecordedRequest {
+        method: method.clone(),
+        authorized,
+        body: serde_json::from_slice(body).unwrap_or(serde_json::Value::Null),
+    });
+
+    if verb != "POST" || (method != methods::GET_USER_STATUS && method != methods::GET_UNLEASH_DATA) {
+        return connect_error(404, "unimplemented", "no such method");
+    }
+    if !authorized {
+        return connect_error(401, "unauthenticated", "invalid CSRF token");
+    }
+    if !headers
+        .get("content-type")
+        .is_some_and(|v| v.starts_with("application/json"))
+    {
+        return connect_error(415, "invalid_argument", "unsupported content type");
+    }
+    if let Some(status) = state.injected_failures.lock().unwrap().pop_front() {
+        return connect_error(status, "unavailable", "injected failure");
+    }
+
+    match method.as_str() {
+        methods::GET_USER_STATUS => (200, state.user_status.lock().unwrap().clone()),
+        _ => (200, serde_json::json!({})),
+    }
+}
+
+fn connect_error(status: u16, code: &str, message: &str) -> (u16, serde_json::Value) {
+    (status, serde_json::json!({ "code": code, "message": message }))
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        415 => "Unsupported Media Type",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Error",
+    }
+}
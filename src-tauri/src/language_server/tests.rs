@@ -0,0 +1,586 @@
+//! 语言服务器集成测试（伪造进程 + 本地替身服务器，无需安装 Antigravity）
+
+use super::args::{split_command_line, LanguageServerArgs};
+use super::client::{methods, LanguageServerError};
+use super::log_follower::{LogEventKind, LogFollower};
+use super::cmdline_detector::CmdLineDetector;
+use super::test_support::{
+    sample_user_status, test_client, FakeLanguageServer, FakeProcess, FakeProcessSource,
+};
+use super::types::{GetUserStatusResponse, RequestMetadata, UserStatusRequest};
+use super::utils::{parse_lsof_listen_ports, parse_netstat_listen_ports, parse_ports_from_log};
+use std::io::Write;
+
+const TOKEN: &str = "0f1e2d3c-4b5a-6978-8796-a5b4c3d2e1f0";
+const OTHER_TOKEN: &str = "aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee";
+
+fn user_status_request() -> UserStatusRequest {
+    UserStatusRequest {
+        metadata: RequestMetadata::default(),
+    }
+}
+
+/// 返回一个当前没有任何进程监听的端口
+async fn closed_port() -> u16 {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    listener.local_addr().unwrap().port()
+}
+
+#[test]
+fn detects_instances_newest_first_and_ignores_unrelated_processes() {
+    let source = FakeProcessSource::new();
+    source.add(FakeProcess::other(10, "bash"));
+    source.add(FakeProcess::other(11, "antigravity"));
+    source.add(FakeProcess::language_server(100, 1_000, OTHER_TOKEN, 42100));
+    source.add(FakeProcess::language_server(200, 2_000, TOKEN, 42200));
+
+    let instances = CmdLineDetector::with_source(source).detect_all_instances().unwrap();
+
+    assert_eq!(instances.len(), 2);
+    assert_eq!(instances[0].pid, 200);
+    assert_eq!(instances[0].id, "200-2000");
+    assert_eq!(instances[0].csrf_token, TOKEN);
+    assert_eq!(instances[0].api_port, Some(42200));
+    assert_eq!(instances[0].workspace_hint.as_deref(), Some("file_home_user_project"));
+    assert_eq!(instances[0].extension_port, Some(42200));
+    assert!(instances[0].args.enable_lsp);
+    assert_eq!(instances[1].pid, 100);
+}
+
+#[test]
+fn parses_language_server_argv_forms() {
+    let argv = [
+        "/opt/Antigravity Beta/bin/language_server_linux_x64",
+        "--enable_lsp",
+        "--csrf_token=abc-123",
+        "-extension_server_port",
+        "42100",
+        "--api_server_port=42101",
+        "--random_port=false",
+        "--app_data_dir",
+        "/home/user/.config/Antigravity Beta",
+        "--detect_proxy",
+        "--some_new_flag",
+        "value",
+        "--oauth_access_token=ya29.secret-value",
+        "--another_switch",
+        "--lsp_port",
+        "not-a-port",
+    ];
+
+    let args = LanguageServerArgs::parse(&argv);
+
+    assert_eq!(args.program, argv[0]);
+    assert!(args.enable_lsp);
+    assert!(!args.random_port);
+    assert_eq!(args.token(), Some("abc-123"));
+    assert_eq!(args.extension_server_port, Some(42100));
+    assert_eq!(args.api_server_port, Some(42101));
+    assert_eq!(args.lsp_port, None);
+    assert_eq!(args.app_data_dir.as_deref(), Some("/home/user/.config/Antigravity Beta"));
+    assert_eq!(args.unknown.get("detect_proxy"), Some(&None));
+    assert_eq!(args.unknown.get("some_new_flag"), Some(&Some("value".to_string())));
+    assert_eq!(args.unknown.get("another_switch"), Some(&None));
+
+    // CSRF Token 和名称像凭据的未知参数值不会序列化给前端
+    let json = serde_json::to_value(&args).unwrap();
+    assert!(!json.to_string().contains("abc-123"));
+    assert!(!json.to_string().contains("ya29.secret-value"));
+    assert_eq!(json["unknown"]["oauth_access_token"], "[REDACTED]");
+    assert_eq!(json["unknown"]["some_new_flag"], "value");
+    assert_eq!(json["extensionServerPort"], 42100);
+    assert_eq!(
+        args.redacted_unknown().get("oauth_access_token"),
+        Some(&Some("[REDACTED]".to_string()))
+    );
+}
+
+#[test]
+fn falls_back_to_manager_token_and_splits_quoted_command_lines() {
+    let argv = split_command_line(
+        r#""C:\Program Files\Antigravity\language_server_windows_x64.exe" --manager_token 'tok en' --workspace_id="a \"b\"""#,
+    );
+
+    assert_eq!(
+        argv,
+        [
+            r"C:\Program Files\Antigravity\language_server_windows_x64.exe",
+            "--manager_token",
+            "tok en",
+            r#"--workspace_id=a "b""#,
+        ]
+    );
+
+    let args = LanguageServerArgs::parse(&argv);
+    assert_eq!(args.token(), Some("tok en"));
+    assert_eq!(args.workspace_id.as_deref(), Some(r#"a "b""#));
+}
+
+#[test]
+fn detection_fails_without_language_server() {
+    let source = FakeProcessSource::new();
+    source.add(FakeProcess::other(10, "bash"));
+
+    assert!(CmdLineDetector::with_source(source).detect_all_instances().is_err());
+}
+
+#[test]
+fn parses_user_status_with_lenient_numbers_and_unknown_fields() {
+    let response: GetUserStatusResponse =
+        serde_json::from_value(sample_user_status("tester@example.com")).unwrap();
+
+    let status = response.user_status.unwrap();
+    assert_eq!(status.email, "tester@example.com");
+
+    let plan = status.plan_status.unwrap();
+    assert_eq!(plan.available_prompt_credits, 480.0);
+    assert_eq!(plan.available_flow_credits, 950.0);
+    assert_eq!(plan.plan_info.unwrap().max_num_chat_input_tokens, "16384");
+
+    let models = status.cascade_model_config_data.unwrap().client_model_configs;
+    assert_eq!(models[0].quota_info.as_ref().unwrap().remaining_fraction, 0.75);
+    // 剩余为 0 时服务端省略 remainingFraction
+    assert_eq!(models[1].quota_info.as_ref().unwrap().remaining_fraction, 0.0);
+}
+
+#[tokio::test]
+async fn calls_get_user_status_with_csrf_header() {
+    let server = FakeLanguageServer::start(TOKEN).await;
+    let source = FakeProcessSource::new();
+    source.add(FakeProcess::language_server(100, 1_000, TOKEN, server.port()));
+    let (client, _cache) = test_client(source);
+
+    let response: GetUserStatusResponse = client
+        .call(methods::GET_USER_STATUS, &user_status_request())
+        .await
+        .unwrap();
+
+    assert_eq!(response.user_status.unwrap().email, "tester@example.com");
+    assert!(server.requests().iter().all(|r| r.authorized));
+    let request = server
+        .requests()
+        .into_iter()
+        .find(|r| r.method == methods::GET_USER_STATUS)
+        .unwrap();
+    assert_eq!(request.body["metadata"]["ideName"], "antigravity");
+}
+
+#[tokio::test]
+async fn discovery_skips_ports_that_do_not_answer() {
+    let server = FakeLanguageServer::start(TOKEN).await;
+    let dead_port = closed_port().await;
+    let source = FakeProcessSource::new();
+    source.add(FakeProcess {
+        listening_ports: Some(vec![dead_port, server.port()]),
+        ..FakeProcess::language_server(100, 1_000, TOKEN, dead_port)
+    });
+    let (client, _cache) = test_client(source);
+
+    let endpoint = client.endpoint(None).await.unwrap();
+
+    assert_eq!(endpoint.port, server.port());
+}
+
+#[tokio::test]
+async fn discovery_fails_when_no_port_answers() {
+    let dead_port = closed_port().await;
+    let source = FakeProcessSource::new();
+    source.add(FakeProcess::language_server(100, 1_000, TOKEN, dead_port));
+    let (client, _cache) = test_client(source);
+
+    let err = client.endpoint(None).await.unwrap_err();
+
+    assert!(matches!(err, LanguageServerError::Discovery(_)), "{err}");
+}
+
+#[tokio::test]
+async fn discovery_without_verified_ports_never_sends_the_token() {
+    // 命令行中的端口上确实有服务在监听，但无法确认它属于该进程
+    let server = FakeLanguageServer::start(TOKEN).await;
+    let source = FakeProcessSource::new();
+    source.add(FakeProcess {
+        listening_ports: None,
+        ..FakeProcess::language_server(100, 1_000, TOKEN, server.port())
+    });
+    let (client, _cache) = test_client(source);
+
+    let err = client.endpoint(None).await.unwrap_err();
+
+    assert!(matches!(err, LanguageServerError::Discovery(_)), "{err}");
+    assert!(server.requests().is_empty());
+}
+
+#[test]
+fn parses_listening_ports_from_lsof_and_netstat() {
+    let lsof = "p4242\nf23\nn127.0.0.1:42100\nf24\nn[::1]:42101\nf25\nn*:42100\n";
+    assert_eq!(parse_lsof_listen_ports(lsof), vec![42100, 42101]);
+
+    let netstat = "\
+Active Connections
+
+  Proto  Local Address          Foreign Address        State           PID
+  TCP    127.0.0.1:42100        0.0.0.0:0              LISTENING       4242
+  TCP    127.0.0.1:42100        127.0.0.1:53011        ESTABLISHED     4242
+  TCP    127.0.0.1:42300        0.0.0.0:0              LISTENING       999
+  TCP    [::1]:42101            [::]:0                 LISTENING       4242
+";
+    assert_eq!(parse_netstat_listen_ports(netstat, 4242), vec![42100, 42101]);
+}
+
+#[tokio::test]
+async fn endpoint_is_cached_between_calls() {
+    let server = FakeLanguageServer::start(TOKEN).await;
+    let source = FakeProcessSource::new();
+    source.add(FakeProcess::language_server(100, 1_000, TOKEN, server.port()));
+    let (client, cache) = test_client(source);
+
+    for _ in 0..3 {
+        let _: GetUserStatusResponse = client
+            .call(methods::GET_USER_STATUS, &user_status_request())
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(server.request_count(methods::GET_UNLEASH_DATA), 1);
+    assert_eq!(server.request_count(methods::GET_USER_STATUS), 3);
+    assert!(cache.get_stats().hits > 0);
+}
+
+#[tokio::test]
+async fn process_restart_invalidates_cache() {
+    let server = FakeLanguageServer::start(TOKEN).await;
+    let source = FakeProcessSource::new();
+    source.add(FakeProcess::language_server(100, 1_000, TOKEN, server.port()));
+    let (client, cache) = test_client(source.clone());
+
+    let first = client.endpoint(None).await.unwrap();
+    assert_eq!(first.instance_id, "100-1000");
+
+    // 同一 PID 以新的启动时间和 Token 重启
+    server.set_csrf_token(OTHER_TOKEN);
+    source.restart(100, 2_000, OTHER_TOKEN, server.port());
+
+    let _: GetUserStatusResponse = client
+        .call(methods::GET_USER_STATUS, &user_status_request())
+        .await
+        .unwrap();
+
+    let instances = client.instances().await.unwrap();
+    assert_eq!(instances[0].id, "100-2000");
+    assert!(cache.get_stats().invalidations > 0);
+    // 重启后不应带着旧 Token 发出请求
+    assert!(server.requests().iter().all(|r| r.authorized));
+}
+
+#[tokio::test]
+async fn exited_process_is_not_served_from_cache() {
+    let server = FakeLanguageServer::start(TOKEN).await;
+    let source = FakeProcessSource::new();
+    source.add(FakeProcess::language_server(100, 1_000, TOKEN, server.port()));
+    let (client, _cache) = test_client(source.clone());
+    client.endpoint(None).await.unwrap();
+
+    source.kill(100);
+
+    assert!(client.instances().await.is_err());
+}
+
+#[tokio::test]
+async fn newly_started_instance_invalidates_instance_list() {
+    let source = FakeProcessSource::new();
+    source.add(FakeProcess::language_server(100, 1_000, OTHER_TOKEN, 42100));
+    let (_client, cache) = test_client(source.clone());
+
+    let first = cache.instances().await.unwrap();
+    assert_eq!(first.len(), 1);
+
+    // 新窗口启动第二个实例，已有实例仍在运行
+    source.add(FakeProcess::language_server(200, 2_000, TOKEN, 42200));
+
+    let instances = cache.instances().await.unwrap();
+    assert_eq!(instances.len(), 2);
+    assert_eq!(instances[0].id, "200-2000");
+    assert_eq!(instances[0].csrf_token, TOKEN);
+}
+
+#[tokio::test]
+async fn language_server_threads_do_not_invalidate_instance_list() {
+    let source = FakeProcessSource::new();
+    let leader = FakeProcess::language_server(100, 1_000, TOKEN, 42100);
+    source.add(leader.clone());
+    source.add(FakeProcess::thread(101, 1_001, &leader));
+    let (_client, cache) = test_client(source.clone());
+
+    let first = cache.instances().await.unwrap();
+    assert_eq!(first.len(), 1);
+    assert_eq!(first[0].id, "100-1000");
+
+    // 线程不断创建、退出，进程本身没有变化
+    source.kill(101);
+    source.add(FakeProcess::thread(102, 1_002, &leader));
+    source.add(FakeProcess::thread(103, 1_003, &leader));
+    let instances = cache.instances().await.unwrap();
+    source.kill(102);
+    let again = cache.instances().await.unwrap();
+
+    assert_eq!(instances.len(), 1);
+    assert_eq!(again[0].id, "100-1000");
+    let stats = cache.get_stats();
+    assert_eq!(stats.invalidations, 0);
+    assert_eq!(stats.hits, 2);
+}
+
+#[tokio::test]
+async fn retries_transient_server_errors() {
+    let server = FakeLanguageServer::start(TOKEN).await;
+    let source = FakeProcessSource::new();
+    source.add(FakeProcess::language_server(100, 1_000, TOKEN, server.port()));
+    let (client, _cache) = test_client(source);
+    client.endpoint(None).await.unwrap();
+
+    server.fail_next(503, 2);
+    let response: GetUserStatusResponse = client
+        .call(methods::GET_USER_STATUS, &user_status_request())
+        .await
+        .unwrap();
+
+    assert!(response.user_status.is_some());
+    assert_eq!(server.request_count(methods::GET_USER_STATUS), 3);
+}
+
+#[tokio::test]
+async fn gives_up_after_max_retries() {
+    let server = FakeLanguageServer::start(TOKEN).await;
+    let source = FakeProcessSource::new();
+    source.add(FakeProcess::language_server(100, 1_000, TOKEN, server.port()));
+    let (client, _cache) = test_client(source);
+    client.endpoint(None).await.unwrap();
+
+    server.fail_next(500, 10);
+    let err = client
+        .call::<_, GetUserStatusResponse>(methods::GET_USER_STATUS, &user_status_request())
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, LanguageServerError::Status { status: 500, .. }), "{err}");
+    assert_eq!(server.request_count(methods::GET_USER_STATUS), 3);
+}
+
+#[tokio::test]
+async fn wrong_csrf_token_is_rejected_during_discovery() {
+    let server = FakeLanguageServer::start(TOKEN).await;
+    let source = FakeProcessSource::new();
+    source.add(FakeProcess::language_server(100, 1_000, OTHER_TOKEN, server.port()));
+    let (client, _cache) = test_client(source);
+
+    let err = client
+        .call::<_, GetUserStatusResponse>(methods::GET_USER_STATUS, &user_status_request())
+        .await
+        .unwrap_err();
+
+    // 探测时 Token 就被拒绝，端点无法建立
+    assert!(matches!(err, LanguageServerError::Discovery(_)), "{err}");
+    assert!(server.requests().iter().all(|r| !r.authorized));
+}
+
+#[tokio::test]
+async fn targets_a_specific_instance() {
+    let old_server = FakeLanguageServer::start(OTHER_TOKEN).await;
+    let new_server = FakeLanguageServer::start(TOKEN).await;
+    old_server.set_user_status(sample_user_status("old@example.com"));
+    let source = FakeProcessSource::new();
+    source.add(FakeProcess::language_server(100, 1_000, OTHER_TOKEN, old_server.port()));
+    source.add(FakeProcess::language_server(200, 2_000, TOKEN, new_server.port()));
+    let (client, _cache) = test_client(source);
+
+    let newest: GetUserStatusResponse = client
+        .call(methods::GET_USER_STATUS, &user_status_request())
+        .await
+        .unwrap();
+    let old: GetUserStatusResponse = client
+        .call_on(Some("100-1000"), methods::GET_USER_STATUS, &user_status_request())
+        .await
+        .unwrap();
+
+    assert_eq!(newest.user_status.unwrap().email, "tester@example.com");
+    assert_eq!(old.user_status.unwrap().email, "old@example.com");
+}
+
+#[tokio::test]
+async fn certificate_is_pinned_per_instance() {
+    let server = FakeLanguageServer::start(TOKEN).await;
+    let source = FakeProcessSource::new();
+    source.add(FakeProcess::language_server(100, 1_000, TOKEN, server.port()));
+    let (client, _cache) = test_client(source);
+
+    assert!(client.pinned_fingerprint("100-1000").is_none());
+    client.endpoint(None).await.unwrap();
+    assert!(client.pinned_fingerprint("100-1000").is_some());
+}
+
+#[tokio::test]
+async fn changed_certificate_is_rejected_before_sending_token() {
+    let server = FakeLanguageServer::start(TOKEN).await;
+    let source = FakeProcessSource::new();
+    source.add(FakeProcess::language_server(100, 1_000, TOKEN, server.port()));
+    let (client, _cache) = test_client(source);
+    client.endpoint(None).await.unwrap();
+    let requests_before = server.requests().len();
+
+    server.rotate_certificate();
+    let err = client
+        .call::<_, GetUserStatusResponse>(methods::GET_USER_STATUS, &user_status_request())
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, LanguageServerError::CertificateMismatch { .. }), "{err}");
+    assert_eq!(server.requests().len(), requests_before);
+}
+
+/// 测试用临时日志文件
+fn temp_log(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("ls-test-{}-{}.log", std::process::id(), name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn append(path: &std::path::Path, text: &str) {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path).unwrap();
+    file.write_all(text.as_bytes()).unwrap();
+}
+
+#[test]
+fn parses_http_and_https_ports_separately() {
+    let log = "random port at 1111 for HTTP\nrandom port at 2222 for HTTPS\nextension server client at port 3333\n";
+    assert_eq!(parse_ports_from_log(log), (Some(2222), Some(1111), Some(3333)));
+}
+
+#[test]
+fn log_follower_reads_only_appended_complete_lines() {
+    let path = temp_log("append");
+    append(&path, "2025-10-18 10:00:00.000 [info] Language server started pid=4242\n");
+    let mut follower = LogFollower::with_path(path.clone());
+
+    assert_eq!(follower.poll(), 1);
+    assert_eq!(follower.poll(), 0);
+
+    // 不完整的行等到换行符出现后再解析
+    append(&path, "2025-10-18 10:00:01.000 [info] random port at 4321 ");
+    assert_eq!(follower.poll(), 0);
+    append(&path, "for HTTPS\n2025-10-18 10:00:02.000 [info] nothing interesting\n");
+    assert_eq!(follower.poll(), 1);
+
+    let events = follower.events_since(0, 10);
+    assert_eq!(events[0].kind, LogEventKind::ServerStarted { pid: Some(4242) });
+    assert_eq!(events[0].log_time.as_deref(), Some("2025-10-18 10:00:00.000"));
+    assert!(matches!(events[1].kind, LogEventKind::PortsAssigned { https_port: Some(4321), .. }));
+    assert_eq!(follower.ports().https_port, Some(4321));
+    assert_eq!(follower.events_since(events[0].seq, 10).len(), 1);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn log_follower_restarts_after_truncation() {
+    let path = temp_log("truncate");
+    append(&path, "[error] first failure with a fairly long line to make the file bigger\n");
+    let mut follower = LogFollower::with_path(path.clone());
+    assert_eq!(follower.poll(), 1);
+
+    std::fs::write(&path, "CSRF token invalid: 401\n").unwrap();
+    assert_eq!(follower.poll(), 1);
+
+    let events = follower.events_since(0, 10);
+    assert_eq!(events[0].kind, LogEventKind::Error);
+    assert_eq!(events[1].kind, LogEventKind::AuthError);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[cfg(target_os = "linux")]
+mod memory_scan {
+    use super::super::linux::scan_process_for_token;
+    use super::super::types::{MemoryScanOptions, MemoryScanOutcome};
+    use regex::Regex;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    /// 子进程环境变量位于其栈上，只有这一处带有请求头与 Token
+    struct Child(std::process::Child);
+
+    impl Child {
+        fn with_token(token: &str) -> Self {
+            let child = std::process::Command::new("sleep")
+                .arg("30")
+                .env("AG_TEST_HEADER", format!("x-codeium-csrf-token: {}", token))
+                .spawn()
+                .expect("启动子进程失败");
+            Self(child)
+        }
+
+        fn pid(&self) -> u32 {
+            self.0.id()
+        }
+    }
+
+    impl Drop for Child {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    fn uuid_re() -> Regex {
+        Regex::new(r"(?i)[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}").unwrap()
+    }
+
+    fn patterns() -> (Vec<u8>, Vec<u8>) {
+        let key = "x-codeium-csrf-token";
+        let utf16 = key.encode_utf16().flat_map(|c| c.to_le_bytes()).collect();
+        (key.as_bytes().to_vec(), utf16)
+    }
+
+    #[test]
+    fn finds_token_in_process_memory_and_reports_stats() {
+        let token = "5e8f2c1a-9b7d-4e3f-a2c1-0d9e8f7a6b5c";
+        let child = Child::with_token(token);
+
+        let (found, stats) = scan_process_for_token(
+            child.pid(),
+            &uuid_re(),
+            &patterns(),
+            &MemoryScanOptions::with_timeout(Duration::from_secs(10)),
+        )
+        .unwrap();
+
+        assert_eq!(found.as_deref(), Some(token));
+        assert_eq!(stats.outcome, MemoryScanOutcome::Found);
+        assert!(stats.priority_regions > 0);
+        assert!(stats.chunks_scanned > 0);
+        assert!(stats.bytes_read > 0);
+        assert!(stats.read_calls > 0);
+    }
+
+    #[test]
+    fn stops_at_deadline_and_on_cancel() {
+        let child = Child::with_token("5e8f2c1a-9b7d-4e3f-a2c1-0d9e8f7a6b5c");
+
+        let expired = MemoryScanOptions {
+            deadline: Some(Instant::now()),
+            ..MemoryScanOptions::default()
+        };
+        let (found, stats) = scan_process_for_token(child.pid(), &uuid_re(), &patterns(), &expired).unwrap();
+        assert_eq!(found, None);
+        assert_eq!(stats.outcome, MemoryScanOutcome::DeadlineExceeded);
+        assert_eq!(stats.chunks_scanned, 0);
+
+        let cancelled = MemoryScanOptions {
+            cancel: Some(Arc::new(AtomicBool::new(true))),
+            ..MemoryScanOptions::default()
+        };
+        let (found, stats) = scan_process_for_token(child.pid(), &uuid_re(), &patterns(), &cancelled).unwrap();
+        assert_eq!(found, None);
+        assert_eq!(stats.outcome, MemoryScanOutcome::Cancelled);
+    }
+}
@@ -0,0 +1,156 @@
+//! 语言服务器证书固定（TOFU）
+//!
+//! 语言服务器使用自签名证书，无法走常规证书链校验。每个实例第一次探测成功时固定证书的
+//! SHA-256 指纹，之后对该实例的连接都必须出示同一证书，否则在发送请求（及 CSRF Token）
+//! 之前就中断握手。
+//!
+//! 首次探测发生在端点发现阶段，只会连接与该实例相关的端口：该 PID 正在监听的端口
+//! （Linux 读取 `/proc`，macOS / Windows 使用 `lsof` / `netstat`）；无法确认端口归属时
+//! 不连接任何端口。只有 CSRF Token 被接受的端口才会被固定。
+//!
+//! 固定前的证书按端口暂存，同一实例并发探测多个端口时不会互相覆盖。
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// 证书 SHA-256 指纹
+pub type Fingerprint = [u8; 32];
+
+/// 指纹的十六进制表示（`ab:cd:...`）
+pub fn format_fingerprint(fingerprint: &Fingerprint) -> String {
+    fingerprint
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// 单个实例的证书固定状态
+#[derive(Debug, Default)]
+pub struct CertificatePin {
+    /// 已固定的指纹，首次探测成功前为空
+    pinned: Mutex<Option<Fingerprint>>,
+    /// 尚未固定时各端口最近一次握手出示的指纹，该端口请求成功后才固定
+    pending: Mutex<HashMap<u16, Fingerprint>>,
+    /// 各端口最近一次指纹不匹配时实际出示的指纹
+    mismatch: Mutex<HashMap<u16, Fingerprint>>,
+}
+
+impl CertificatePin {
+    pub fn fingerprint(&self) -> Option<Fingerprint> {
+        *self.pinned.lock().unwrap()
+    }
+
+    /// 取出端口最近一次不匹配的 (期望, 实际) 指纹
+    pub fn take_mismatch(&self, port: u16) -> Option<(Fingerprint, Fingerprint)> {
+        let actual = self.mismatch.lock().unwrap().remove(&port)?;
+        Some((self.fingerprint()?, actual))
+    }
+
+    /// 请求成功后调用：固定该端口本次握手的证书（已固定时无操作），返回本次是否新固定了证书
+    pub fn confirm(&self, port: u16) -> bool {
+        let mut pinned = self.pinned.lock().unwrap();
+        if pinned.is_some() {
+            return false;
+        }
+        let mut pending = self.pending.lock().unwrap();
+        let Some(fingerprint) = pending.remove(&port) else {
+            return false;
+        };
+        pending.clear();
+        tracing::info!("🔒 已固定语言服务器证书指纹: {}", format_fingerprint(&fingerprint));
+        *pinned = Some(fingerprint);
+        true
+    }
+
+    /// 校验证书：未固定时暂存指纹，已固定时要求一致
+    fn verify(&self, port: u16, certificate: &[u8]) -> bool {
+        let actual: Fingerprint = Sha256::digest(certificate).into();
+        let pinned = self.pinned.lock().unwrap();
+        match *pinned {
+            None => {
+                self.pending.lock().unwrap().insert(port, actual);
+                true
+            }
+            Some(expected) if expected == actual => true,
+            Some(expected) => {
+                tracing::error!(
+                    "❌ 语言服务器证书指纹变化，拒绝连接: 期望 {}，实际 {}",
+                    format_fingerprint(&expected),
+                    format_fingerprint(&actual)
+                );
+                self.mismatch.lock().unwrap().insert(port, actual);
+                false
+            }
+        }
+    }
+}
+
+/// 按固定指纹校验证书的验证器（签名仍按正常流程校验）
+#[derive(Debug)]
+struct PinningVerifier {
+    pin: Arc<CertificatePin>,
+    /// 该验证器所属客户端连接的端口
+    port: u16,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinningVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if self.pin.verify(self.port, end_entity.as_ref()) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General("语言服务器证书指纹与固定值不一致".to_string()))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// 构建只信任固定证书的 TLS 配置（只用于连接 `port`）
+pub fn pinned_client_config(pin: Arc<CertificatePin>, port: u16) -> Result<rustls::ClientConfig, rustls::Error> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let verifier = Arc::new(PinningVerifier {
+        pin,
+        port,
+        provider: provider.clone(),
+    });
+
+    Ok(rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .dangerous()
+        .with_custom_certificate_verifier(verifier)
+        .with_no_client_auth())
+}
@@ -27,11 +27,40 @@ impl Default for PortInfo {
     }
 }
 
+/// 语言服务器进程身份（PID + 启动时间），进程重启后必然变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerIdentity {
+    pub pid: u32,
+    /// 启动时间（Unix 秒）
+    pub start_time: u64,
+}
+
+/// 单个缓存条目的状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntryInfo {
+    pub key: String,
+    /// 条目所属的进程，为空表示无法关联进程（按 TTL 失效）
+    pub owners: Vec<ServerIdentity>,
+    pub age_secs: u64,
+}
+
 /// 缓存统计信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CacheStats {
     pub csrf_cache_size: u64,
     pub ports_cache_size: u64,
+    #[serde(default)]
+    pub instances_cache_size: u64,
+    #[serde(default)]
+    pub hits: u64,
+    #[serde(default)]
+    pub misses: u64,
+    /// 因所属进程退出或重启而失效的条目数
+    #[serde(default)]
+    pub invalidations: u64,
+    #[serde(default)]
+    pub entries: Vec<CacheEntryInfo>,
 }
 
 /// 缓存初始化结果
@@ -78,7 +107,9 @@ pub struct UserStatusRequest {
 /// 缓存配置常量
 pub struct CacheConfig {
     pub max_cache_entries: u64,
-    pub ttl_seconds: u64,  // 缓存有效期（秒）
+    /// 无法关联进程的条目（如日志中的端口）的有效期（秒）；
+    /// 关联了进程的条目在进程退出或重启时失效，不受此限制
+    pub ttl_seconds: u64,
 }
 
 impl Default for CacheConfig {
@@ -91,14 +122,290 @@ impl Default for CacheConfig {
 }
 
 /// HTTP 请求配置
+#[derive(Debug, Clone)]
 pub struct HttpConfig {
     pub request_timeout_ms: u64,
+    /// 探测端口可用性时的超时
+    pub probe_timeout_ms: u64,
+    /// 可重试错误的最大重试次数（不含首次请求）
+    pub max_retries: u32,
+    /// 首次重试前的等待时间，之后按 2 倍递增
+    pub retry_backoff_ms: u64,
 }
 
 impl Default for HttpConfig {
     fn default() -> Self {
         Self {
             request_timeout_ms: 4000,
+            probe_timeout_ms: 2000,
+            max_retries: 2,
+            retry_backoff_ms: 300,
+        }
+    }
+}
+
+/// 内存扫描选项（命令行中找不到 CSRF Token 时的后备方案）
+#[derive(Debug, Clone)]
+pub struct MemoryScanOptions {
+    /// 截止时间，到达后放弃剩余区域
+    pub deadline: Option<std::time::Instant>,
+    /// 取消标志，置位后尽快停止
+    pub cancel: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+    /// 每个内存区域最多扫描的字节数
+    pub max_region_bytes: usize,
+    /// 并行扫描的线程数
+    pub workers: usize,
+}
+
+impl MemoryScanOptions {
+    /// 从现在起最多扫描 `timeout`
+    pub fn with_timeout(timeout: std::time::Duration) -> Self {
+        Self {
+            deadline: Some(std::time::Instant::now() + timeout),
+            ..Self::default()
+        }
+    }
+
+    /// 是否应停止扫描（取消优先于超时）
+    pub fn interrupted(&self) -> Option<MemoryScanOutcome> {
+        if self
+            .cancel
+            .as_ref()
+            .is_some_and(|c| c.load(std::sync::atomic::Ordering::Relaxed))
+        {
+            return Some(MemoryScanOutcome::Cancelled);
+        }
+        if self.deadline.is_some_and(|d| std::time::Instant::now() >= d) {
+            return Some(MemoryScanOutcome::DeadlineExceeded);
+        }
+        None
+    }
+}
+
+impl Default for MemoryScanOptions {
+    fn default() -> Self {
+        Self {
+            deadline: None,
+            cancel: None,
+            max_region_bytes: super::utils::MAX_REGION_BYTES,
+            workers: std::thread::available_parallelism()
+                .map(|n| n.get().min(4))
+                .unwrap_or(1),
         }
     }
-}
\ No newline at end of file
+}
+
+/// 内存扫描结果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MemoryScanOutcome {
+    Found,
+    #[default]
+    NotFound,
+    DeadlineExceeded,
+    Cancelled,
+    /// 扫描期间进程已退出
+    ProcessExited,
+}
+
+/// 内存扫描统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryScanStats {
+    pub pid: u32,
+    pub outcome: MemoryScanOutcome,
+    /// 可读区域总数
+    pub regions_total: usize,
+    /// 优先扫描的区域数（堆和匿名映射）
+    pub priority_regions: usize,
+    /// 待扫描的分块数
+    pub chunks_total: usize,
+    /// 已读取成功的分块数
+    pub chunks_scanned: usize,
+    /// 读取失败的分块数
+    pub chunks_failed: usize,
+    /// `process_vm_readv` 调用次数
+    pub read_calls: u64,
+    pub bytes_read: u64,
+    pub workers: usize,
+    pub elapsed_ms: u64,
+}
+
+// ========== GetUserStatus 响应模型 ==========
+//
+// 与前端 `LanguageServerResponse` 类型保持一致。protobuf 的 JSON 编码会省略
+// 零值字段，并把 int64 编码为字符串，因此所有字段都带默认值，
+// 数值字段同时接受数字和字符串，未知字段直接忽略。
+
+/// GetUserStatus 响应
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GetUserStatusResponse {
+    pub user_status: Option<UserStatus>,
+}
+
+/// 用户状态
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct UserStatus {
+    pub accepted_latest_terms_of_service: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cascade_model_config_data: Option<CascadeModelConfigData>,
+    pub disable_telemetry: bool,
+    pub email: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan_status: Option<PlanStatus>,
+}
+
+/// Cascade 模型配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CascadeModelConfigData {
+    pub client_model_configs: Vec<ClientModelConfig>,
+    pub client_model_sorts: Vec<ClientModelSort>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_override_model_config: Option<DefaultOverrideModelConfig>,
+}
+
+/// 单个模型配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ClientModelConfig {
+    pub allowed_tiers: Vec<String>,
+    pub is_recommended: bool,
+    pub label: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_or_alias: Option<ModelOrAlias>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quota_info: Option<QuotaInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supports_images: Option<bool>,
+}
+
+/// 模型标识
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ModelOrAlias {
+    pub model: String,
+}
+
+/// 模型配额
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct QuotaInfo {
+    /// 剩余比例（0.0 ~ 1.0），为 0 时服务端会省略该字段
+    #[serde(deserialize_with = "de_f64_lenient")]
+    pub remaining_fraction: f64,
+    /// 配额重置时间（RFC 3339）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reset_time: Option<String>,
+}
+
+/// 模型排序
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ClientModelSort {
+    pub groups: Vec<ModelSortGroup>,
+    pub name: String,
+}
+
+/// 模型排序分组
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ModelSortGroup {
+    pub model_labels: Vec<String>,
+}
+
+/// 默认模型覆盖配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DefaultOverrideModelConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_or_alias: Option<ModelOrAlias>,
+}
+
+/// 套餐状态
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PlanStatus {
+    #[serde(deserialize_with = "de_f64_lenient")]
+    pub available_flow_credits: f64,
+    #[serde(deserialize_with = "de_f64_lenient")]
+    pub available_prompt_credits: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan_info: Option<PlanInfo>,
+}
+
+/// 套餐信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PlanInfo {
+    pub allow_premium_command_models: bool,
+    pub allow_sticky_premium_models: bool,
+    pub browser_enabled: bool,
+    pub can_allow_cascade_in_background: bool,
+    pub can_buy_more_credits: bool,
+    pub can_customize_app_icon: bool,
+    pub can_generate_commit_messages: bool,
+    pub cascade_can_auto_run_commands: bool,
+    pub cascade_web_search_enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_team_config: Option<DefaultTeamConfig>,
+    pub has_autocomplete_fast_mode: bool,
+    pub has_tab_to_jump: bool,
+    pub knowledge_base_enabled: bool,
+    #[serde(deserialize_with = "de_string_lenient")]
+    pub max_custom_chat_instruction_characters: String,
+    #[serde(deserialize_with = "de_string_lenient")]
+    pub max_local_index_size: String,
+    #[serde(deserialize_with = "de_string_lenient")]
+    pub max_num_chat_input_tokens: String,
+    #[serde(deserialize_with = "de_string_lenient")]
+    pub max_num_pinned_context_items: String,
+    #[serde(deserialize_with = "de_string_lenient")]
+    pub max_num_premium_chat_messages: String,
+    #[serde(deserialize_with = "de_f64_lenient")]
+    pub monthly_flex_credit_purchase_amount: f64,
+    #[serde(deserialize_with = "de_f64_lenient")]
+    pub monthly_flow_credits: f64,
+    #[serde(deserialize_with = "de_f64_lenient")]
+    pub monthly_prompt_credits: f64,
+    pub plan_name: String,
+    pub teams_tier: String,
+}
+
+/// 团队默认配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DefaultTeamConfig {
+    pub allow_auto_run_commands: bool,
+    pub allow_browser_experimental_features: bool,
+    pub allow_mcp_servers: bool,
+}
+
+/// 数值字段：接受数字、数字字符串或 null
+fn de_f64_lenient<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::Number(n) => Ok(n.as_f64().unwrap_or_default()),
+        serde_json::Value::String(s) => s.trim().parse().map_err(serde::de::Error::custom),
+        serde_json::Value::Null => Ok(0.0),
+        other => Err(serde::de::Error::custom(format!("期望数字，实际为 {}", other))),
+    }
+}
+
+/// int64 字段：protobuf JSON 编码为字符串，也接受数字或 null
+fn de_string_lenient<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(s) => Ok(s),
+        serde_json::Value::Number(n) => Ok(n.to_string()),
+        serde_json::Value::Null => Ok(String::new()),
+        other => Err(serde::de::Error::custom(format!("期望字符串，实际为 {}", other))),
+    }
+}
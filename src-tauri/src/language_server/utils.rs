@@ -3,7 +3,6 @@ use std::path::PathBuf;
 
 use anyhow::{anyhow, Result};
 use regex::Regex;
-use sysinfo::{System};
 use walkdir::WalkDir;
 use read_process_memory as _;
 
@@ -19,7 +18,12 @@ pub(crate) const CHUNK_SIZE: usize = 512 * 1024; // 512KB 分块读取，降低
 pub(crate) const MAX_REGION_BYTES: usize = 64 * 1024 * 1024; // 每个区域最多扫描 64MB，加速
 
 
+/// 每个日志根目录下检查的最近会话目录数
+const RECENT_LOG_SESSIONS: usize = 3;
+
 /// 查找最新的 Antigravity.log（按修改时间）
+///
+/// 日志按会话分目录存放（`logs/<会话>/...`），只遍历最近修改的几个会话目录
 pub fn find_latest_antigravity_log() -> Option<PathBuf> {
     let mut candidates = Vec::new();
     if let Some(dir) = dirs::data_dir() {
@@ -32,19 +36,29 @@ pub fn find_latest_antigravity_log() -> Option<PathBuf> {
     let mut newest: Option<(PathBuf, std::time::SystemTime)> = None;
 
     for root in candidates {
-        if !root.exists() {
+        let Ok(entries) = std::fs::read_dir(&root) else {
             continue;
-        }
-        if let Ok(entries) = WalkDir::new(root).max_depth(6).into_iter().collect::<Result<Vec<_>, _>>() {
-            for entry in entries {
+        };
+
+        let mut sessions: Vec<(PathBuf, std::time::SystemTime)> = entries
+            .flatten()
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+            .filter_map(|e| Some((e.path(), e.metadata().ok()?.modified().ok()?)))
+            .collect();
+        sessions.sort_by(|a, b| b.1.cmp(&a.1));
+
+        // 日志直接位于根目录时也能找到
+        let dirs_to_walk = std::iter::once((root.clone(), 1))
+            .chain(sessions.into_iter().take(RECENT_LOG_SESSIONS).map(|(p, _)| (p, 5)));
+
+        for (dir, depth) in dirs_to_walk {
+            for entry in WalkDir::new(dir).max_depth(depth).into_iter().flatten() {
                 let path = entry.path();
                 if path.file_name().is_some_and(|n| n == "Antigravity.log") && path.is_file() {
-                    if let Ok(meta) = path.metadata() {
-                        if let Ok(modified) = meta.modified() {
-                            match &newest {
-                                Some((_, ts)) if *ts >= modified => {}
-                                _ => newest = Some((path.to_path_buf(), modified)),
-                            }
+                    if let Ok(modified) = path.metadata().and_then(|m| m.modified()) {
+                        match &newest {
+                            Some((_, ts)) if *ts >= modified => {}
+                            _ => newest = Some((path.to_path_buf(), modified)),
                         }
                     }
                 }
@@ -55,65 +69,61 @@ pub fn find_latest_antigravity_log() -> Option<PathBuf> {
     newest.map(|(p, _)| p)
 }
 
-/// 从日志内容解析 HTTPS/HTTP/extension 端口
+/// 从日志内容解析 HTTPS/HTTP/extension 端口（各取最后一次出现）
 pub fn parse_ports_from_log(content: &str) -> (Option<u16>, Option<u16>, Option<u16>) {
-    let https_re = Regex::new(r"random port at (\d+) for HTTPS").unwrap();
-    let http_re = Regex::new(r"random port at (\d+) for HTTP").unwrap();
-    let ext_re = Regex::new(r"extension server client at port (\d+)").unwrap();
-
-    let https_port = https_re
-        .captures_iter(content)
-        .last()
-        .and_then(|c| c.get(1)?.as_str().parse::<u16>().ok());
-    let http_port = http_re
-        .captures_iter(content)
-        .last()
-        .and_then(|c| c.get(1)?.as_str().parse::<u16>().ok());
-    let extension_port = ext_re
-        .captures_iter(content)
-        .last()
-        .and_then(|c| c.get(1)?.as_str().parse::<u16>().ok());
-
-    (https_port, http_port, extension_port)
-}
-
-/// 进程匹配：忽略大小写，允许 .exe 后缀
-fn collect_target_pids() -> Vec<u32> {
-    let mut system = System::new();
-    system.refresh_processes();
-
-    let mut pids = system
-        .processes()
-        .iter()
-        .filter_map(|(pid, proc_)| {
-            let name = proc_.name().to_string();
-            if is_target_process(&name) {
-                Some(pid.as_u32())
-            } else {
-                None
-            }
-        })
-        .collect::<Vec<_>>();
+    static PATTERNS: std::sync::OnceLock<(Regex, Regex, Regex)> = std::sync::OnceLock::new();
+    let (https_re, http_re, ext_re) = PATTERNS.get_or_init(|| {
+        (
+            Regex::new(r"random port at (\d+) for HTTPS").unwrap(),
+            Regex::new(r"random port at (\d+) for HTTP\b").unwrap(),
+            Regex::new(r"extension server client at port (\d+)").unwrap(),
+        )
+    });
+
+    let last_port = |re: &Regex| {
+        re.captures_iter(content)
+            .last()
+            .and_then(|c| c.get(1)?.as_str().parse::<u16>().ok())
+    };
 
-    // PID 倒序：优先扫描最新启动的渲染/子进程
-    pids.sort_unstable_by(|a, b| b.cmp(a));
-    pids
+    (last_port(https_re), last_port(http_re), last_port(ext_re))
 }
 
-fn is_target_process(name: &str) -> bool {
-    let normalized = name
-        .trim()
-        .to_ascii_lowercase()
-        .trim_end_matches(".exe")
-        .to_string();
-    normalized.contains("antigravity") || normalized.contains("windsurf")
+/// 解析 `lsof -nP -a -p <pid> -iTCP -sTCP:LISTEN -Fn` 的输出（`n127.0.0.1:42100`、`n[::1]:42100`、`n*:42100`）
+pub fn parse_lsof_listen_ports(output: &str) -> Vec<u16> {
+    let mut ports: Vec<u16> = output
+        .lines()
+        .filter_map(|line| line.strip_prefix('n'))
+        .filter_map(|addr| addr.rsplit(':').next()?.parse().ok())
+        .collect();
+    ports.sort_unstable();
+    ports.dedup();
+    ports
 }
 
-fn get_patterns() -> (Vec<u8>, Vec<u8>) {
-    let key = "x-codeium-csrf-token";
-    let pat_utf8 = key.as_bytes().to_vec();
-    let pat_utf16: Vec<u8> = key.encode_utf16().flat_map(|c| c.to_le_bytes()).collect();
-    (pat_utf8, pat_utf16)
+/// 解析 `netstat -ano -p TCP` 的输出，只保留指定 PID 处于 LISTENING 的端口
+///
+/// 格式: `TCP    127.0.0.1:42100    0.0.0.0:0    LISTENING    1234`（状态列随系统语言变化，按列数判断）
+pub fn parse_netstat_listen_ports(output: &str, pid: u32) -> Vec<u16> {
+    let pid = pid.to_string();
+    let mut ports: Vec<u16> = output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            // 监听状态的远端地址为 0.0.0.0:0 / [::]:0
+            match fields.as_slice() {
+                [proto, local, remote, _state, owner]
+                    if proto.eq_ignore_ascii_case("tcp") && remote.ends_with(":0") && *owner == pid =>
+                {
+                    local.rsplit(':').next()?.parse().ok()
+                }
+                _ => None,
+            }
+        })
+        .collect();
+    ports.sort_unstable();
+    ports.dedup();
+    ports
 }
 
 pub(crate) fn find_all_positions(haystack: &[u8], needle: &[u8]) -> Vec<usize> {
@@ -297,23 +307,13 @@ pub async fn get_ports_with_cache() -> Result<PortInfo> {
         Err(e) => {
             tracing::warn!("统一检测失败: {}，回退到日志解析", e);
             
-            // 回退到解析日志
+            // 回退到日志跟踪器中的端口
             tokio::task::spawn_blocking(move || -> Result<PortInfo> {
-                let log_path = find_latest_antigravity_log()
-                    .ok_or_else(|| anyhow!("未找到 Antigravity.log，无法确定端口"))?;
-
-                let content = std::fs::read_to_string(&log_path)
-                    .map_err(|e| anyhow!("读取日志失败: {e}"))?;
-
-                let (https_port, http_port, extension_port) = parse_ports_from_log(&content);
-
-                Ok(PortInfo {
-                    https_port,
-                    http_port,
-                    extension_port,
-                    log_path: Some(log_path.to_string_lossy().to_string()),
-                    csrf_token: None,
-                })
+                let ports = super::log_follower::current_ports();
+                if ports.log_path.is_none() {
+                    return Err(anyhow!("未找到 Antigravity.log，无法确定端口"));
+                }
+                Ok(ports)
             }).await??
         }
     };
@@ -96,3 +96,32 @@ pub(super) fn scan_process_for_token(
     unsafe { let _ = CloseHandle(handle); };
     Ok(None)
 }
+
+/// 获取进程正在监听的 TCP 端口（通过 `netstat -ano`）
+pub(super) fn listening_ports(pid: u32) -> Result<Vec<u16>> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    let output = std::process::Command::new("netstat")
+        .creation_flags(CREATE_NO_WINDOW)
+        .args(["-ano", "-p", "TCP"])
+        .output()
+        .map_err(|e| anyhow!("执行 netstat 失败: {e}"))?;
+    if !output.status.success() {
+        return Err(anyhow!("netstat 执行失败"));
+    }
+    let mut ports = crate::language_server::utils::parse_netstat_listen_ports(&String::from_utf8_lossy(&output.stdout), pid);
+
+    // `-p TCP` 只列出 IPv4，IPv6 监听需要单独查询
+    if let Ok(v6) = std::process::Command::new("netstat")
+        .creation_flags(CREATE_NO_WINDOW)
+        .args(["-ano", "-p", "TCPv6"])
+        .output()
+    {
+        ports.extend(crate::language_server::utils::parse_netstat_listen_ports(&String::from_utf8_lossy(&v6.stdout), pid));
+        ports.sort_unstable();
+        ports.dedup();
+    }
+
+    Ok(ports)
+}
@@ -19,6 +19,8 @@ mod config_manager;
 mod app_settings;
 mod utils;
 mod language_server;
+mod quota;
+mod resource_monitor;
 
 mod db_monitor;
 mod commands;
@@ -112,6 +114,7 @@ fn main() {
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_http::init())
+        .plugin(tauri_plugin_notification::init())
         .manage(AppState::default())
         .setup(|app| {
             setup::init(app)
@@ -135,8 +138,11 @@ fn main() {
             update_account_remark, // 新增：更新账户备注
             // 进程管理命令
             kill_antigravity,
+            shutdown_antigravity,
+            restart_antigravity,
             is_antigravity_running,
             list_antigravity_processes,
+            get_antigravity_process_tree,
             start_antigravity,
             backup_and_restart_antigravity,
             clear_and_restart_antigravity,
@@ -160,11 +166,39 @@ fn main() {
             toggle_system_tray,
               is_silent_start_enabled,
             save_silent_start_state,
+            is_auto_backup_on_login_enabled,
+            save_auto_backup_on_login_state,
+            get_quota_sample_interval,
+            save_quota_sample_interval,
+            get_quota_alert_rules,
+            save_quota_alert_rules,
+            get_process_rules,
+            save_process_rules,
             get_all_settings,
             // 数据库监控命令
             is_database_monitoring_running,
             start_database_monitoring,
             stop_database_monitoring,
+            subscribe_database_changes,
+            unsubscribe_database_changes,
+            list_database_subscriptions,
+            query_database_journal,
+            clear_database_journal,
+            // 编码值解码命令
+            decode_database_value,
+            diff_account_values,
+            // 配额历史命令
+            sample_quota_now,
+            get_quota_history,
+            get_credit_history,
+            get_quota_projections,
+            get_active_quota_alerts,
+            get_model_catalog,
+            // 资源监控命令
+            get_resource_history,
+            get_active_resource_alerts,
+            get_resource_monitor_settings,
+            save_resource_monitor_settings,
             get_log_info,
             clear_logs,
             decrypt_config_data,
@@ -173,6 +207,11 @@ fn main() {
             write_frontend_log,
             // Antigravity 语言服务器接口
             language_server_get_user_status,
+            language_server_get_user_status_raw,
+            language_server_list_instances,
+            language_server_get_user_status_all,
+            language_server_get_readiness,
+            get_antigravity_log_events,
             clear_all_cache_command,
             get_cache_stats_command,
             initialize_language_server_cache,
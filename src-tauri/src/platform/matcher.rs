@@ -0,0 +1,350 @@
+//! Antigravity 进程识别
+//!
+//! 按以下顺序判断一个进程是否属于 Antigravity：
+//! 1. 本程序自身、父进程及所有子孙进程始终排除；
+//! 2. 命中用户配置的排除规则时排除；
+//! 3. 解析后的可执行文件（Linux 上即 `/proc/<pid>/exe`）位于检测到的 Antigravity 安装中时匹配；
+//!    AppImage 运行时可执行文件位于临时挂载目录，改为比较 argv[0]、可执行文件或 `APPIMAGE` 环境变量
+//!    与检测到的 AppImage 路径是否相同；
+//! 4. 命中用户配置的包含规则时匹配（用于无法通过安装路径识别的情况）。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+/// 向上追溯父进程的最大层数（防止异常的父子关系形成环）
+const MAX_ANCESTOR_DEPTH: usize = 64;
+
+/// 进程匹配模式
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "camelCase")]
+pub enum ProcessPattern {
+    /// 精确匹配进程名
+    ExactName(String),
+    /// 进程名或命令行包含指定文本
+    Contains(String),
+    /// 命令行包含指定文本
+    CmdContains(String),
+    /// 命令行以指定文本结尾
+    CmdEndsWith(String),
+    /// 解析后的可执行文件位于指定路径下
+    ExeUnder(String),
+}
+
+impl ProcessPattern {
+    fn matches(&self, name: &str, cmd: &str, exe: Option<&Path>) -> bool {
+        match self {
+            ProcessPattern::ExactName(text) => name == text,
+            ProcessPattern::Contains(text) => name.contains(text.as_str()) || cmd.contains(text.as_str()),
+            ProcessPattern::CmdContains(text) => cmd.contains(text.as_str()),
+            ProcessPattern::CmdEndsWith(text) => cmd.ends_with(text.as_str()),
+            ProcessPattern::ExeUnder(dir) => exe.is_some_and(|exe| exe.starts_with(dir)),
+        }
+    }
+}
+
+/// 用户可配置的进程包含/排除规则
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessRules {
+    /// 额外视为 Antigravity 的进程
+    #[serde(default = "default_include_patterns")]
+    pub include: Vec<ProcessPattern>,
+    /// 即使属于安装目录也不视为 Antigravity 的进程
+    #[serde(default)]
+    pub exclude: Vec<ProcessPattern>,
+}
+
+impl Default for ProcessRules {
+    fn default() -> Self {
+        Self {
+            include: default_include_patterns(),
+            exclude: Vec::new(),
+        }
+    }
+}
+
+/// 各平台默认的包含规则
+///
+/// 只保留能唯一指向 Antigravity 的规则；像 `Contains("Antigravity")` 这样宽泛的规则
+/// 会命中本程序（antigravity-agent）和打开了 antigravity 路径的编辑器
+pub fn default_include_patterns() -> Vec<ProcessPattern> {
+    let mut patterns = Vec::new();
+    match std::env::consts::OS {
+        "macos" => {
+            // 主进程：Electron（Antigravity的包装进程），必须通过路径验证
+            patterns.push(ProcessPattern::CmdContains(
+                "/Applications/Antigravity.app/Contents/MacOS/Electron".to_string(),
+            ));
+            // Helper 进程：Antigravity Helper系列（GPU、Renderer、Plugin等）
+            patterns.push(ProcessPattern::CmdContains(
+                "Antigravity.app/Contents/Frameworks/Antigravity Helper".to_string(),
+            ));
+        }
+        "windows" => {
+            patterns.push(ProcessPattern::ExactName("Antigravity.exe".to_string()));
+        }
+        "linux" => {
+            patterns.push(ProcessPattern::ExactName("antigravity".to_string()));
+            patterns.push(ProcessPattern::ExactName("Antigravity".to_string()));
+        }
+        _ => {
+            patterns.push(ProcessPattern::ExactName("Antigravity".to_string()));
+        }
+    }
+    patterns
+}
+
+static PROCESS_RULES: OnceLock<RwLock<ProcessRules>> = OnceLock::new();
+
+fn rules_lock() -> &'static RwLock<ProcessRules> {
+    PROCESS_RULES.get_or_init(|| RwLock::new(ProcessRules::default()))
+}
+
+/// 当前生效的进程规则
+pub fn process_rules() -> ProcessRules {
+    rules_lock().read().map(|r| r.clone()).unwrap_or_default()
+}
+
+/// 替换进程规则（启动时从设置加载，保存设置时更新）
+pub fn set_process_rules(rules: ProcessRules) {
+    if let Ok(mut current) = rules_lock().write() {
+        *current = rules;
+    }
+}
+
+/// 校验进程规则
+pub fn validate_rules(rules: &ProcessRules) -> Result<(), String> {
+    for pattern in rules.include.iter().chain(&rules.exclude) {
+        let text = match pattern {
+            ProcessPattern::ExactName(t)
+            | ProcessPattern::Contains(t)
+            | ProcessPattern::CmdContains(t)
+            | ProcessPattern::CmdEndsWith(t)
+            | ProcessPattern::ExeUnder(t) => t,
+        };
+        if text.trim().is_empty() {
+            return Err(format!("进程规则不能为空: {:?}", pattern));
+        }
+        if let ProcessPattern::ExeUnder(dir) = pattern {
+            if !Path::new(dir).is_absolute() {
+                return Err(format!("ExeUnder 需要绝对路径: {}", dir));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 检测到的 Antigravity 安装
+///
+/// 可执行文件所在的、名称中带有 antigravity 的最近一级目录视为安装目录
+/// （如 `/usr/share/antigravity`、`...\Programs\Antigravity`、`Antigravity.app`）；
+/// 找不到这样的目录时（如直接安装在 `/usr/bin`）只匹配可执行文件本身；
+/// AppImage 按镜像文件路径识别
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Installation {
+    Directory(PathBuf),
+    Executable(PathBuf),
+    AppImage(PathBuf),
+}
+
+impl Installation {
+    fn from_executable(path: &Path) -> Option<Self> {
+        let resolved = path.canonicalize().ok()?;
+        if resolved
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("appimage"))
+        {
+            return Some(Installation::AppImage(resolved));
+        }
+        let dir = resolved
+            .ancestors()
+            .filter(|p| p.is_dir())
+            .find(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.to_lowercase().contains("antigravity"))
+            })
+            .map(Path::to_path_buf);
+        Some(match dir {
+            Some(dir) => Installation::Directory(dir),
+            None => Installation::Executable(resolved),
+        })
+    }
+
+    /// 进程是否属于该安装
+    fn contains(&self, pid: sysinfo::Pid, process: &sysinfo::Process, exe: Option<&Path>) -> bool {
+        match self {
+            Installation::Directory(dir) => exe.is_some_and(|exe| exe.starts_with(dir)),
+            Installation::Executable(file) => exe == Some(file.as_path()),
+            Installation::AppImage(image) => {
+                exe == Some(image.as_path())
+                    || process.cmd().first().is_some_and(|arg0| Path::new(arg0) == image)
+                    || exe
+                        .filter(|exe| is_appimage_mount(exe))
+                        .and_then(|_| appimage_env(pid))
+                        .is_some_and(|env| env == *image)
+            }
+        }
+    }
+}
+
+/// AppImage 运行时把镜像挂载到 `/tmp/.mount_<名称><随机后缀>`，只有这些进程才需要读取环境变量
+fn is_appimage_mount(exe: &Path) -> bool {
+    exe.components().any(|c| {
+        c.as_os_str()
+            .to_str()
+            .is_some_and(|c| c.starts_with(".mount_"))
+    })
+}
+
+/// 读取进程的 `APPIMAGE` 环境变量（AppImage 运行时设置为镜像文件的绝对路径，子进程会继承）
+#[cfg(target_os = "linux")]
+fn appimage_env(pid: sysinfo::Pid) -> Option<PathBuf> {
+    let environ = std::fs::read(format!("/proc/{}/environ", pid.as_u32())).ok()?;
+    environ
+        .split(|b| *b == 0)
+        .find_map(|entry| entry.strip_prefix(b"APPIMAGE="))
+        .and_then(|value| std::str::from_utf8(value).ok())
+        .map(PathBuf::from)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn appimage_env(_pid: sysinfo::Pid) -> Option<PathBuf> {
+    None
+}
+
+/// 检测 Antigravity 安装（用户配置的可执行文件优先，其次是各平台的默认位置）
+pub fn detect_installations() -> Vec<Installation> {
+    let mut candidates = Vec::new();
+    if let Ok(Some(custom)) = crate::antigravity::path_config::get_custom_executable_path() {
+        candidates.push(PathBuf::from(custom));
+    }
+    candidates.extend(crate::path_utils::AppPaths::antigravity_executable_paths());
+
+    let mut installations: Vec<Installation> = Vec::new();
+    for installation in candidates.iter().filter_map(|p| Installation::from_executable(p)) {
+        if !installations.contains(&installation) {
+            installations.push(installation);
+        }
+    }
+    installations
+}
+
+/// 进程被识别为 Antigravity 的原因
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "value", rename_all = "camelCase")]
+pub enum MatchReason {
+    Installation(Installation),
+    Rule(ProcessPattern),
+}
+
+/// 基于一次进程快照的 Antigravity 进程识别器
+pub struct ProcessMatcher {
+    installations: Vec<Installation>,
+    rules: ProcessRules,
+    /// 本程序自身、父进程和子孙进程
+    excluded: HashSet<sysinfo::Pid>,
+}
+
+impl ProcessMatcher {
+    pub fn with(system: &sysinfo::System, installations: Vec<Installation>, rules: ProcessRules) -> Self {
+        Self {
+            installations,
+            rules,
+            excluded: self_related_pids(system, sysinfo::Pid::from_u32(std::process::id())),
+        }
+    }
+
+    /// 进程是否属于 Antigravity，返回匹配原因
+    pub fn matches(&self, pid: sysinfo::Pid, process: &sysinfo::Process) -> Option<MatchReason> {
+        if self.excluded.contains(&pid) {
+            return None;
+        }
+
+        let name = process.name();
+        let cmd = process.cmd().join(" ");
+        let exe = process.exe().map(strip_deleted_suffix);
+        let exe = exe.as_deref();
+
+        if self.rules.exclude.iter().any(|p| p.matches(name, &cmd, exe)) {
+            return None;
+        }
+
+        if let Some(installation) = self.installations.iter().find(|i| i.contains(pid, process, exe)) {
+            return Some(MatchReason::Installation(installation.clone()));
+        }
+
+        self.rules
+            .include
+            .iter()
+            .find(|p| p.matches(name, &cmd, exe))
+            .map(|p| MatchReason::Rule(p.clone()))
+    }
+
+    pub fn installations(&self) -> &[Installation] {
+        &self.installations
+    }
+
+    pub fn is_excluded(&self, pid: sysinfo::Pid) -> bool {
+        self.excluded.contains(&pid)
+    }
+}
+
+/// 本程序相关的进程：自身、父进程以及所有以自身为祖先的进程
+fn self_related_pids(system: &sysinfo::System, own_pid: sysinfo::Pid) -> HashSet<sysinfo::Pid> {
+    let mut pids = HashSet::from([own_pid]);
+    if let Some(parent) = system.process(own_pid).and_then(|p| p.parent()) {
+        pids.insert(parent);
+    }
+
+    for (pid, process) in super::snapshot::processes(system) {
+        let mut parent = process.parent();
+        for _ in 0..MAX_ANCESTOR_DEPTH {
+            match parent {
+                Some(p) if p == own_pid => {
+                    pids.insert(*pid);
+                    break;
+                }
+                Some(p) => parent = system.process(p).and_then(|p| p.parent()),
+                None => break,
+            }
+        }
+    }
+
+    pids
+}
+
+/// 可执行文件被替换（如升级）后 `/proc/<pid>/exe` 会带上 " (deleted)" 后缀
+fn strip_deleted_suffix(exe: &Path) -> PathBuf {
+    match exe.to_str().and_then(|s| s.strip_suffix(" (deleted)")) {
+        Some(stripped) => PathBuf::from(stripped),
+        None => exe.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn appimage_is_identified_by_image_path() {
+        let dir = std::env::temp_dir().join(format!("matcher-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let image = dir.join("Antigravity.AppImage");
+        std::fs::write(&image, b"").unwrap();
+
+        let installation = Installation::from_executable(&image).unwrap();
+        assert_eq!(installation, Installation::AppImage(image.canonicalize().unwrap()));
+        assert!(!default_include_patterns()
+            .iter()
+            .any(|p| matches!(p, ProcessPattern::CmdContains(t) if t.contains("AppImage"))));
+
+        assert!(is_appimage_mount(Path::new("/tmp/.mount_AntigrXyz123/antigravity")));
+        assert!(!is_appimage_mount(Path::new("/home/user/src/Antigravity.AppImage.tools/bin/node")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -2,8 +2,13 @@
 //! Provides cross-platform functionality for interacting with Antigravity
 
 pub mod antigravity;
+pub mod matcher;
 pub mod process;
+pub mod process_tree;
+pub mod snapshot;
 
 // Re-export commonly used types and functions
 pub use antigravity::*;
+pub use matcher::{ProcessMatcher, ProcessPattern, ProcessRules};
 pub use process::*;
+pub use process_tree::{ProcessNode, ProcessRole, ProcessTree};
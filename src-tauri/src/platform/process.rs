@@ -1,171 +1,251 @@
-/// 关闭Antigravity进程 - 使用sysinfo库实现跨平台统一处理
-pub fn kill_antigravity_processes() -> Result<String, String> {
-    tracing::info!("🔍 开始搜索并关闭 Antigravity 进程");
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use super::snapshot::{self, freshness};
+
+/// 等待主进程优雅退出的默认时长
+pub const GRACEFUL_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 强制终止后确认进程退出的时长
+const KILL_CONFIRM_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 检查进程是否退出的间隔
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 单个进程的结束方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProcessExit {
+    /// 在优雅退出等待期内退出（主进程收到 SIGTERM / 关闭请求，子进程随进程树退出）
+    Graceful,
+    /// 等待超时后被强制终止
+    Killed,
+    /// 开始关闭前已经退出
+    AlreadyExited,
+    /// 强制终止后仍在运行
+    Survived,
+}
 
-    // 使用sysinfo库获取所有进程
-    let mut system = sysinfo::System::new_all();
-    system.refresh_all();
-
-    let mut killed_processes = Vec::new();
-
-    // 定义需要关闭的进程模式（按优先级排序）
-    let process_patterns = get_antigravity_process_patterns();
-
-    for (pid, process) in system.processes() {
-        let process_name = process.name();
-        let process_cmd = process.cmd().join(" ");
-
-        // 检查进程名或命令行是否匹配任何模式
-        if matches_antigravity_process(process_name, &process_cmd, &process_patterns) {
-            tracing::info!("🎯 找到目标进程: {} (PID: {})", process_name, pid);
-            tracing::info!("📝 命令行: {}", process_cmd);
-
-            // 尝试终止进程
-            if process.kill() {
-                killed_processes.push(format!("{} (PID: {})", process_name, pid));
-                tracing::info!("✅ 成功终止进程: {} (PID: {})", process_name, pid);
-            } else {
-                tracing::warn!("⚠️ 终止进程失败: {} (PID: {})", process_name, pid);
-
-                // 尝试多次终止（如果第一次失败）
-                if process.kill() {
-                    killed_processes.push(format!("{} (PID: {} - 强制)", process_name, pid));
-                    tracing::info!("✅ 强制终止进程: {} (PID: {})", process_name, pid);
-                } else {
-                    tracing::error!("❌ 强制终止也失败: {} (PID: {})", process_name, pid);
-                }
-            }
-        }
+/// 单个进程的关闭结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessShutdown {
+    pub pid: u32,
+    pub name: String,
+    /// 是否为主进程（父进程不是 Antigravity 进程）
+    pub is_main: bool,
+    pub exit: ProcessExit,
+    /// 从开始关闭到确认退出的毫秒数
+    pub elapsed_ms: u64,
+}
+
+/// 关闭 Antigravity 的结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShutdownReport {
+    pub processes: Vec<ProcessShutdown>,
+    pub graceful_timeout_ms: u64,
+    pub elapsed_ms: u64,
+}
+
+impl ShutdownReport {
+    /// 是否所有进程都已退出
+    pub fn all_exited(&self) -> bool {
+        self.processes.iter().all(|p| p.exit != ProcessExit::Survived)
     }
 
-    if killed_processes.is_empty() {
-        tracing::info!("ℹ️ 未找到匹配的 Antigravity 进程");
-        tracing::info!("🔍 搜索的进程模式: {:?}", process_patterns);
-        Err("未找到Antigravity进程".to_string())
-    } else {
-        let success_msg = format!("已成功关闭Antigravity进程: {}", killed_processes.join(", "));
-        tracing::info!("🎉 {}", success_msg);
-        Ok(success_msg)
+    /// 简短的文字描述
+    pub fn summary(&self) -> String {
+        let describe = |p: &ProcessShutdown| {
+            let exit = match p.exit {
+                ProcessExit::Graceful => "正常退出",
+                ProcessExit::Killed => "强制终止",
+                ProcessExit::AlreadyExited => "已退出",
+                ProcessExit::Survived => "仍在运行",
+            };
+            format!("{} (PID: {} - {})", p.name, p.pid, exit)
+        };
+        format!(
+            "已关闭Antigravity进程: {}",
+            self.processes.iter().map(describe).collect::<Vec<_>>().join(", ")
+        )
     }
 }
 
-/// 检查 Antigravity 进程是否正在运行（使用 sysinfo）
-pub fn is_antigravity_running() -> bool {
-    tracing::debug!("🔍 检查 Antigravity 进程是否运行");
+/// 关闭Antigravity进程（返回文字描述，见 `shutdown_antigravity_processes`）
+pub fn kill_antigravity_processes() -> Result<String, String> {
+    let report = shutdown_antigravity_processes(GRACEFUL_SHUTDOWN_TIMEOUT)?;
+    if report.all_exited() {
+        Ok(report.summary())
+    } else {
+        Err(format!("部分进程未能关闭: {}", report.summary()))
+    }
+}
 
-    let mut system = sysinfo::System::new_all();
-    system.refresh_all();
+/// 按顺序关闭 Antigravity：先请求主进程退出（Unix 上为 SIGTERM），
+/// 在 `timeout` 内等待整个进程树退出，让 Antigravity 有机会写回 `state.vscdb`，
+/// 超时后强制终止剩余进程，并确认每个进程确实已经退出
+pub fn shutdown_antigravity_processes(timeout: Duration) -> Result<ShutdownReport, String> {
+    tracing::info!("🔍 开始搜索并关闭 Antigravity 进程");
+    let started = Instant::now();
+
+    // (PID, 进程名, 启动时间, 父进程)
+    let (targets, installations) = snapshot::with_snapshot(freshness::NOW, |snapshot| {
+        let matcher = snapshot.matcher();
+        let targets: Vec<(sysinfo::Pid, String, u64, Option<sysinfo::Pid>)> = snapshot
+            .processes()
+            .filter(|(pid, process)| matcher.matches(**pid, process).is_some())
+            .map(|(pid, process)| (*pid, process.name().to_string(), process.start_time(), process.parent()))
+            .collect();
+        (targets, matcher.installations().to_vec())
+    });
+
+    if targets.is_empty() {
+        tracing::info!("ℹ️ 未找到匹配的 Antigravity 进程");
+        tracing::info!("🔍 检测到的安装: {:?}", installations);
+        return Err("未找到Antigravity进程".to_string());
+    }
 
-    let process_patterns = get_antigravity_process_patterns();
+    let target_pids: HashSet<sysinfo::Pid> = targets.iter().map(|t| t.0).collect();
+    let is_main = |parent: &Option<sysinfo::Pid>| !parent.is_some_and(|p| target_pids.contains(&p));
 
-    for (pid, process) in system.processes() {
-        let process_name = process.name();
-        let process_cmd = process.cmd().join(" ");
+    // 快照之后、发送信号之前已经退出的进程
+    let mut exits: HashMap<sysinfo::Pid, (ProcessExit, u64)> = HashMap::new();
+    for (pid, _, start_time, _) in &targets {
+        if !is_running(*pid, *start_time) {
+            exits.insert(*pid, (ProcessExit::AlreadyExited, 0));
+        }
+    }
 
-        if matches_antigravity_process(process_name, &process_cmd, &process_patterns) {
-            tracing::debug!("✅ 发现运行中的 Antigravity 进程: {} (PID: {})", process_name, pid);
-            return true;
+    // 1. 请求主进程退出
+    for (pid, name, start_time, parent) in &targets {
+        if is_main(parent) && !exits.contains_key(pid) {
+            tracing::info!("🎯 请求主进程退出: {} (PID: {})", name, pid);
+            if !with_live_process(*pid, *start_time, request_graceful_exit).unwrap_or(false) {
+                tracing::warn!("⚠️ 发送退出请求失败: {} (PID: {})", name, pid);
+            }
         }
     }
 
-    tracing::debug!("ℹ️ 未发现运行中的 Antigravity 进程");
-    false
-}
+    // 2. 等待进程树退出
+    wait_for_exit(&targets, &mut exits, ProcessExit::Graceful, started, started + timeout);
 
-/// 获取 Antigravity 进程匹配模式
-fn get_antigravity_process_patterns() -> Vec<ProcessPattern> {
-    match std::env::consts::OS {
-        "macos" => {
-            vec![
-                // 主进程：Electron（Antigravity的包装进程），必须通过路径验证
-                ProcessPattern::CmdContains("/Applications/Antigravity.app/Contents/MacOS/Electron"),
-
-                // Helper 进程：Antigravity Helper系列（GPU、Renderer、Plugin等）
-                ProcessPattern::CmdContains("Antigravity.app/Contents/Frameworks/Antigravity Helper"),
-            ]
-        }
-        "windows" => {
-            vec![
-                ProcessPattern::ExactName("Antigravity.exe"),
-                // 兜底，目前未使用
-                ProcessPattern::ExactName("Antigravity"),
-            ]
-        }
-        "linux" => {
-            vec![
-                ProcessPattern::ExactName("antigravity"),
-                ProcessPattern::ExactName("Antigravity"),
-                ProcessPattern::Contains("Antigravity"),
-                ProcessPattern::CmdContains("antigravity"),
-                ProcessPattern::CmdContains("Antigravity.AppImage"),
-            ]
-        }
-        _ => {
-            vec![
-                ProcessPattern::ExactName("Antigravity"),
-            ]
+    // 3. 强制终止剩余进程
+    let stragglers: Vec<_> = targets.iter().filter(|t| !exits.contains_key(&t.0)).collect();
+    if !stragglers.is_empty() {
+        tracing::warn!("⏱️ {} 个进程未在 {}ms 内退出，强制终止", stragglers.len(), timeout.as_millis());
+        for (pid, name, start_time, _) in &stragglers {
+            if with_live_process(*pid, *start_time, |process| process.kill()) == Some(false) {
+                tracing::warn!("⚠️ 强制终止失败: {} (PID: {})", name, pid);
+            }
         }
+        wait_for_exit(&targets, &mut exits, ProcessExit::Killed, started, Instant::now() + KILL_CONFIRM_TIMEOUT);
     }
-}
 
-/// 检查进程是否匹配 Antigravity 模式
-fn matches_antigravity_process(process_name: &str, process_cmd: &str, patterns: &[ProcessPattern]) -> bool {
-    let mut matched = false;
-    for pattern in patterns {
-        match pattern {
-            ProcessPattern::ExactName(name) => {
-                if process_name == *name {
-                    tracing::debug!("✅ 精确匹配进程名: {}", name);
-                    tracing::info!("🎯 匹配模式: ProcessPattern::ExactName(\"{}\")", name);
-                    matched = true;
-                }
-            }
-            ProcessPattern::Contains(text) => {
-                if process_name.contains(text) || process_cmd.contains(text) {
-                    tracing::debug!("✅ 包含匹配: {}", text);
-                    tracing::info!("🎯 匹配模式: ProcessPattern::Contains(\"{}\")", text);
-                    matched = true;
-                }
-            }
-            ProcessPattern::CmdContains(text) => {
-                if process_cmd.contains(text) {
-                    tracing::debug!("✅ 命令行包含匹配: {}", text);
-                    tracing::info!("🎯 匹配模式: ProcessPattern::CmdContains(\"{}\")", text);
-                    matched = true;
-                }
+    let processes: Vec<ProcessShutdown> = targets
+        .iter()
+        .map(|(pid, name, _, parent)| {
+            let (exit, elapsed_ms) = exits
+                .get(pid)
+                .copied()
+                .unwrap_or((ProcessExit::Survived, started.elapsed().as_millis() as u64));
+            match exit {
+                ProcessExit::Survived => tracing::error!("❌ 进程仍在运行: {} (PID: {})", name, pid),
+                _ => tracing::info!("✅ 进程已退出: {} (PID: {}, {:?})", name, pid, exit),
             }
-            ProcessPattern::CmdEndsWith(suffix) => {
-                if process_cmd.ends_with(suffix) {
-                    tracing::debug!("✅ 命令行后缀匹配: {}", suffix);
-                    tracing::info!("🎯 匹配模式: ProcessPattern::CmdEndsWith(\"{}\")", suffix);
-                    matched = true;
-                }
+            ProcessShutdown {
+                pid: pid.as_u32(),
+                name: name.clone(),
+                is_main: is_main(parent),
+                exit,
+                elapsed_ms,
             }
-        }
+        })
+        .collect();
+
+    let report = ShutdownReport {
+        processes,
+        graceful_timeout_ms: timeout.as_millis() as u64,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    };
+    tracing::info!("🎉 {}", report.summary());
+    Ok(report)
+}
+
+/// 请求进程正常退出：Unix 上发送 SIGTERM，Windows 上发送关闭请求（不带 /F 的 taskkill）
+fn request_graceful_exit(process: &sysinfo::Process) -> bool {
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+        std::process::Command::new("taskkill")
+            .creation_flags(CREATE_NO_WINDOW)
+            .args(["/PID", &process.pid().to_string()])
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        process.kill_with(sysinfo::Signal::Term).unwrap_or(false)
     }
-    matched
 }
 
-/// 进程匹配模式
-#[derive(Debug, Clone)]
-pub enum ProcessPattern {
-    ExactName(&'static str),    // 精确匹配进程名
-    Contains(&'static str),      // 包含指定文本
-    CmdContains(&'static str),   // 命令行包含指定文本
-    #[allow(dead_code)]
-    CmdEndsWith(&'static str),   // 命令行以指定文本结尾
+/// 单独刷新进程，启动时间一致（PID 未被复用）时对其执行 `f`
+fn with_live_process<R>(pid: sysinfo::Pid, start_time: u64, f: impl FnOnce(&sysinfo::Process) -> R) -> Option<R> {
+    let mut system = sysinfo::System::new();
+    system.refresh_process(pid);
+    system.process(pid).filter(|p| p.start_time() == start_time).map(f)
 }
 
-/// 获取 Antigravity 进程匹配模式（用于调试）
-pub fn get_antigravity_process_patterns_for_debug() -> Vec<ProcessPattern> {
-    get_antigravity_process_patterns()
+/// 进程是否仍在运行（启动时间不同说明 PID 已被复用）
+fn is_running(pid: sysinfo::Pid, start_time: u64) -> bool {
+    with_live_process(pid, start_time, |p| p.status() != sysinfo::ProcessStatus::Zombie).unwrap_or(false)
 }
 
-/// 检查进程是否匹配 Antigravity 模式（用于调试）
-pub fn matches_antigravity_process_for_debug(
-    process_name: &str,
-    process_cmd: &str,
-    pattern: &ProcessPattern
-) -> bool {
-    matches_antigravity_process(process_name, process_cmd, &[pattern.clone()])
+/// 等待目标进程退出直到 `deadline`，把新退出的进程记为 `exit`
+fn wait_for_exit(
+    targets: &[(sysinfo::Pid, String, u64, Option<sysinfo::Pid>)],
+    exits: &mut HashMap<sysinfo::Pid, (ProcessExit, u64)>,
+    exit: ProcessExit,
+    started: Instant,
+    deadline: Instant,
+) {
+    loop {
+        for (pid, _, start_time, _) in targets {
+            if !exits.contains_key(pid) && !is_running(*pid, *start_time) {
+                exits.insert(*pid, (exit, started.elapsed().as_millis() as u64));
+            }
+        }
+        if exits.len() == targets.len() || Instant::now() >= deadline {
+            return;
+        }
+        std::thread::sleep(EXIT_POLL_INTERVAL);
+    }
+}
+
+/// 检查 Antigravity 进程是否正在运行（使用共享进程快照，供前端轮询）
+pub fn is_antigravity_running() -> bool {
+    tracing::debug!("🔍 检查 Antigravity 进程是否运行");
+
+    let found = snapshot::with_snapshot(freshness::STATUS, |snapshot| {
+        let matcher = snapshot.matcher();
+        snapshot
+            .processes()
+            .find_map(|(pid, process)| {
+                matcher
+                    .matches(*pid, process)
+                    .map(|reason| (process.name().to_string(), *pid, reason))
+            })
+    });
+
+    if let Some((name, pid, reason)) = found {
+        tracing::debug!("✅ 发现运行中的 Antigravity 进程: {} (PID: {}, {:?})", name, pid, reason);
+        return true;
+    }
+
+    tracing::debug!("ℹ️ 未发现运行中的 Antigravity 进程");
+    false
 }
@@ -0,0 +1,295 @@
+//! Antigravity 进程树
+//!
+//! 以识别出的 Antigravity 主进程为根，按父进程 PID 组织出完整的进程树
+//! （渲染/GPU 等 Electron 辅助进程、扩展宿主、`language_server_*` 等），
+//! 每个节点带有角色、启动时间、CPU 占用、常驻内存和命令行，
+//! 界面可以据此展示正在运行的内容以及关闭时会影响哪些进程。
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use super::matcher::ProcessMatcher;
+use super::snapshot::{self, freshness};
+
+/// 进程角色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProcessRole {
+    /// Electron 主进程
+    Main,
+    Renderer,
+    Gpu,
+    /// 其他 Electron 辅助进程（utility、zygote、crashpad 等）
+    Helper,
+    ExtensionHost,
+    LanguageServer,
+    Other,
+}
+
+impl ProcessRole {
+    /// 根据进程名和命令行判断角色
+    ///
+    /// 只有进程树的根（被直接识别、父进程不属于 Antigravity）才可能是主进程；
+    /// 其他没有 `--type` 参数的子孙进程（终端 shell、扩展启动的工具等）归为 `Other`
+    pub fn classify(name: &str, cmdline: &[String], is_root: bool) -> Self {
+        if name.starts_with("language_server")
+            || cmdline
+                .first()
+                .and_then(|p| std::path::Path::new(p).file_name())
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("language_server"))
+        {
+            return ProcessRole::LanguageServer;
+        }
+
+        let flag = |name: &str| {
+            let prefix = format!("--{}=", name);
+            cmdline
+                .iter()
+                .find_map(|arg| arg.strip_prefix(prefix.as_str()))
+        };
+
+        // 旧版本以 `--type=extensionHost` 的 node 进程运行，新版本为带有扩展宿主标识的 utility 进程
+        if cmdline.iter().any(|arg| arg.contains("extensionHost") || arg.contains("Extension Host")) {
+            return ProcessRole::ExtensionHost;
+        }
+
+        match flag("type") {
+            None if is_root => ProcessRole::Main,
+            None => ProcessRole::Other,
+            Some("renderer") => ProcessRole::Renderer,
+            Some("gpu-process") => ProcessRole::Gpu,
+            Some("utility" | "zygote" | "crashpad-handler" | "broker") => ProcessRole::Helper,
+            Some(_) => ProcessRole::Other,
+        }
+    }
+}
+
+/// 进程树节点
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessNode {
+    pub pid: u32,
+    pub parent_pid: Option<u32>,
+    pub name: String,
+    pub role: ProcessRole,
+    /// 启动时间（Unix 秒）
+    pub start_time: u64,
+    /// CPU 占用（百分比，多核时可超过 100）
+    pub cpu_percent: f32,
+    /// 常驻内存（字节）
+    pub memory_bytes: u64,
+    pub exe: Option<String>,
+    pub cmdline: Vec<String>,
+    /// 是否由进程规则/安装路径直接识别（否则是被识别进程的子孙进程）
+    pub matched: bool,
+    pub children: Vec<ProcessNode>,
+}
+
+impl ProcessNode {
+    fn walk<'a>(&'a self, out: &mut Vec<&'a ProcessNode>) {
+        out.push(self);
+        for child in &self.children {
+            child.walk(out);
+        }
+    }
+}
+
+/// Antigravity 进程树
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessTree {
+    /// 主进程（父进程不属于 Antigravity 的进程）
+    pub roots: Vec<ProcessNode>,
+    pub process_count: usize,
+    pub total_cpu_percent: f32,
+    pub total_memory_bytes: u64,
+    /// 毫秒时间戳
+    pub captured_at: i64,
+}
+
+impl ProcessTree {
+    /// 按深度优先顺序列出所有节点
+    pub fn nodes(&self) -> Vec<&ProcessNode> {
+        let mut out = Vec::with_capacity(self.process_count);
+        for root in &self.roots {
+            root.walk(&mut out);
+        }
+        out
+    }
+
+    /// 根据进程快照构建进程树
+    ///
+    /// CPU 占用取决于 `system` 前后两次刷新的间隔，调用方需要保证至少刷新过两次
+    pub fn build(system: &sysinfo::System, matcher: &ProcessMatcher) -> Self {
+        let records = system
+            .processes()
+            .iter()
+            .map(|(pid, process)| ProcessRecord {
+                pid: pid.as_u32(),
+                parent_pid: process.parent().map(|p| p.as_u32()),
+                name: process.name().to_string(),
+                cmdline: process.cmd().to_vec(),
+                start_time: process.start_time(),
+                cpu_percent: process.cpu_usage(),
+                memory_bytes: process.memory(),
+                exe: process.exe().map(|p| p.display().to_string()),
+                is_thread: snapshot::is_thread(process),
+                matched: matcher.matches(*pid, process).is_some(),
+                excluded: matcher.is_excluded(*pid),
+            })
+            .collect();
+        Self::from_records(records, chrono::Local::now().timestamp_millis())
+    }
+
+    /// 由进程列表构建进程树
+    ///
+    /// Linux 上的线程条目（父进程为所属进程、内存与所属进程相同）在建立父子关系前就被排除，
+    /// 不会作为子节点出现，也不会重复计入汇总
+    pub(crate) fn from_records(records: Vec<ProcessRecord>, captured_at: i64) -> Self {
+        let records: HashMap<u32, ProcessRecord> = records
+            .into_iter()
+            .filter(|r| !r.is_thread)
+            .map(|r| (r.pid, r))
+            .collect();
+        let order = |pid: &u32| (records.get(pid).map(|r| r.start_time), *pid);
+
+        let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+        for record in records.values() {
+            if let Some(parent) = record.parent_pid {
+                children.entry(parent).or_default().push(record.pid);
+            }
+        }
+        for pids in children.values_mut() {
+            pids.sort_by_key(order);
+        }
+
+        let mut roots: Vec<u32> = records
+            .values()
+            .filter(|r| r.matched && !r.parent_pid.is_some_and(|p| records.get(&p).is_some_and(|p| p.matched)))
+            .map(|r| r.pid)
+            .collect();
+        roots.sort_by_key(order);
+
+        let mut visited = HashSet::new();
+        let roots: Vec<ProcessNode> = roots
+            .into_iter()
+            .filter_map(|pid| build_node(&records, &children, pid, true, &mut visited))
+            .collect();
+
+        let mut tree = ProcessTree {
+            roots,
+            captured_at,
+            ..Default::default()
+        };
+        let nodes = tree.nodes();
+        let (count, cpu, memory) = (
+            nodes.len(),
+            nodes.iter().map(|n| n.cpu_percent).sum(),
+            nodes.iter().map(|n| n.memory_bytes).sum(),
+        );
+        tree.process_count = count;
+        tree.total_cpu_percent = cpu;
+        tree.total_memory_bytes = memory;
+        tree
+    }
+}
+
+/// 构建进程树所需的单个进程信息
+#[derive(Debug, Clone)]
+pub(crate) struct ProcessRecord {
+    pub pid: u32,
+    pub parent_pid: Option<u32>,
+    pub name: String,
+    pub cmdline: Vec<String>,
+    pub start_time: u64,
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+    pub exe: Option<String>,
+    /// Linux 上 sysinfo 作为进程列出的线程（`/proc/<pid>/task/<tid>`）
+    pub is_thread: bool,
+    /// 是否被识别为 Antigravity 进程
+    pub matched: bool,
+    /// 是否为本程序相关进程
+    pub excluded: bool,
+}
+
+fn build_node(
+    records: &HashMap<u32, ProcessRecord>,
+    children: &HashMap<u32, Vec<u32>>,
+    pid: u32,
+    is_root: bool,
+    visited: &mut HashSet<u32>,
+) -> Option<ProcessNode> {
+    let record = records.get(&pid)?;
+    // 跳过本程序相关进程，并防止异常的父子关系形成环
+    if record.excluded || !visited.insert(pid) {
+        return None;
+    }
+
+    let node_children = children
+        .get(&pid)
+        .map(|pids| {
+            pids.iter()
+                .filter_map(|child| build_node(records, children, *child, false, visited))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(ProcessNode {
+        pid,
+        parent_pid: record.parent_pid,
+        name: record.name.clone(),
+        role: ProcessRole::classify(&record.name, &record.cmdline, is_root),
+        start_time: record.start_time,
+        cpu_percent: record.cpu_percent,
+        memory_bytes: record.memory_bytes,
+        exe: record.exe.clone(),
+        cmdline: record.cmdline.clone(),
+        matched: record.matched,
+        children: node_children,
+    })
+}
+
+/// 采集当前的 Antigravity 进程树（共享快照尚无 CPU 数据时会阻塞约 `MINIMUM_CPU_UPDATE_INTERVAL`）
+pub fn collect_process_tree() -> ProcessTree {
+    let tree = snapshot::with_cpu_snapshot(freshness::DETECTION, |snapshot| {
+        let matcher = snapshot.matcher();
+        ProcessTree::build(snapshot.system(), &matcher)
+    });
+    tracing::debug!(
+        target: "platform::process_tree",
+        processes = tree.process_count,
+        cpu = tree.total_cpu_percent,
+        memory = tree.total_memory_bytes,
+        "采集 Antigravity 进程树"
+    );
+    tree
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn only_root_without_type_is_main() {
+        let electron = args(&["/opt/Antigravity/antigravity"]);
+        assert_eq!(ProcessRole::classify("antigravity", &electron, true), ProcessRole::Main);
+        assert_eq!(
+            ProcessRole::classify("bash", &args(&["/bin/bash", "-l"]), false),
+            ProcessRole::Other
+        );
+        assert_eq!(
+            ProcessRole::classify("antigravity", &args(&["antigravity", "--type=renderer"]), false),
+            ProcessRole::Renderer
+        );
+        assert_eq!(
+            ProcessRole::classify("language_server_linux_x64", &args(&["language_server_linux_x64"]), false),
+            ProcessRole::LanguageServer
+        );
+    }
+}
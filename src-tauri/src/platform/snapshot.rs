@@ -0,0 +1,188 @@
+//! 共享进程快照
+//!
+//! 进程查询（Antigravity 是否运行、关闭进程、语言服务器发现、进程树等）共用同一份
+//! `sysinfo::System`，调用方按需声明可接受的快照时效：快照足够新时直接复用，
+//! 否则在持有锁的情况下刷新一次。快照只通过 [`with_snapshot`] 在锁内访问，
+//! 调用方从中取出需要的数据，锁不会离开本模块。后台任务在快照最近被使用过时定期刷新，
+//! 让前端轮询 `is_antigravity_running` 之类的查询几乎没有开销。
+//! 检测到的 Antigravity 安装与快照一起缓存，识别进程时不必每次重新检测。
+//!
+//! sysinfo 在 Linux 上把每个 `/proc/<pid>/task/<tid>` 线程也列为进程（名称、命令行、
+//! 可执行文件都与所属进程相同），遍历进程时必须使用 [`processes`] 只取真实进程。
+
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+use super::matcher::{detect_installations, process_rules, Installation, ProcessMatcher};
+
+/// 后台刷新间隔
+pub const SNAPSHOT_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 超过该时长没有被读取时，后台任务暂停刷新
+const SNAPSHOT_IDLE_AFTER: Duration = Duration::from_secs(60);
+
+/// 缓存的安装检测结果的有效期（修改自定义可执行文件路径时会立即失效）
+const INSTALLATIONS_TTL: Duration = Duration::from_secs(60);
+
+/// 常用的时效要求
+pub mod freshness {
+    use std::time::Duration;
+
+    /// 必须刷新（关闭进程等需要精确状态的操作）
+    pub const NOW: Duration = Duration::ZERO;
+    /// 语言服务器发现等一次性查询
+    pub const DETECTION: Duration = Duration::from_secs(1);
+    /// 前端轮询的状态查询
+    pub const STATUS: Duration = Duration::from_secs(5);
+}
+
+/// 进程快照
+pub struct ProcessSnapshot {
+    system: sysinfo::System,
+    refreshed_at: Option<Instant>,
+    refresh_count: u64,
+    last_read: Instant,
+    installations: Option<(Instant, Vec<Installation>)>,
+}
+
+impl ProcessSnapshot {
+    fn new() -> Self {
+        Self {
+            system: sysinfo::System::new(),
+            refreshed_at: None,
+            refresh_count: 0,
+            last_read: Instant::now(),
+            installations: None,
+        }
+    }
+
+    pub fn system(&self) -> &sysinfo::System {
+        &self.system
+    }
+
+    /// 距上次刷新的时长（从未刷新时为 `None`）
+    pub fn age(&self) -> Option<Duration> {
+        self.refreshed_at.map(|t| t.elapsed())
+    }
+
+    /// 快照中的真实进程（不含线程）
+    pub fn processes(&self) -> impl Iterator<Item = (&sysinfo::Pid, &sysinfo::Process)> {
+        processes(&self.system)
+    }
+
+    /// 基于当前快照、缓存的安装检测结果和当前进程规则的进程识别器
+    pub fn matcher(&mut self) -> ProcessMatcher {
+        let expired = self
+            .installations
+            .as_ref()
+            .is_none_or(|(detected_at, _)| detected_at.elapsed() > INSTALLATIONS_TTL);
+        if expired {
+            self.installations = Some((Instant::now(), detect_installations()));
+        }
+        let installations = self.installations.as_ref().map(|(_, i)| i.clone()).unwrap_or_default();
+        ProcessMatcher::with(&self.system, installations, process_rules())
+    }
+
+    /// CPU 占用是否可用（至少刷新过两次）
+    pub fn cpu_ready(&self) -> bool {
+        self.refresh_count >= 2
+    }
+
+    fn is_fresh(&self, max_age: Duration) -> bool {
+        self.age().is_some_and(|age| age <= max_age) && !max_age.is_zero()
+    }
+
+    fn refresh(&mut self) {
+        let started = Instant::now();
+        self.system.refresh_processes();
+        self.refreshed_at = Some(Instant::now());
+        self.refresh_count += 1;
+        tracing::trace!(
+            target: "platform::snapshot",
+            processes = self.system.processes().len(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "刷新进程快照"
+        );
+    }
+}
+
+/// 真实进程：排除 sysinfo 在 Linux 上作为进程列出的线程
+pub fn processes(system: &sysinfo::System) -> impl Iterator<Item = (&sysinfo::Pid, &sysinfo::Process)> {
+    system
+        .processes()
+        .iter()
+        .filter(|(_, process)| !is_thread(process))
+}
+
+/// 是否为 sysinfo 在 Linux 上作为进程列出的线程（`/proc/<pid>/task/<tid>`）
+pub fn is_thread(process: &sysinfo::Process) -> bool {
+    process.thread_kind().is_some()
+}
+
+static SNAPSHOT: OnceLock<Mutex<ProcessSnapshot>> = OnceLock::new();
+
+fn lock() -> MutexGuard<'static, ProcessSnapshot> {
+    SNAPSHOT
+        .get_or_init(|| Mutex::new(ProcessSnapshot::new()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// 丢弃缓存的安装检测结果（自定义可执行文件路径变化后调用）
+pub fn invalidate_installations() {
+    lock().installations = None;
+}
+
+/// 在不超过 `max_age` 的进程快照上执行 `f`（`Duration::ZERO` 表示总是刷新）
+///
+/// 快照锁只在 `f` 执行期间持有，`f` 应只提取需要的数据并尽快返回，不要在其中等待或休眠。
+/// 刷新是同步的，异步代码需要通过 `spawn_blocking` 调用
+pub fn with_snapshot<R>(max_age: Duration, f: impl FnOnce(&mut ProcessSnapshot) -> R) -> R {
+    let mut guard = lock();
+    guard.last_read = Instant::now();
+    if !guard.is_fresh(max_age) {
+        guard.refresh();
+    }
+    f(&mut guard)
+}
+
+/// 在带有 CPU 占用的进程快照上执行 `f`
+///
+/// CPU 占用依赖前后两次刷新的间隔，快照只刷新过一次时会（不持有锁）等待
+/// `MINIMUM_CPU_UPDATE_INTERVAL` 后再刷新一次
+pub fn with_cpu_snapshot<R>(max_age: Duration, f: impl FnOnce(&mut ProcessSnapshot) -> R) -> R {
+    if with_snapshot(max_age, |snapshot| snapshot.cpu_ready()) {
+        return with_snapshot(max_age, f);
+    }
+    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    let mut guard = lock();
+    guard.refresh();
+    f(&mut guard)
+}
+
+/// 启动后台刷新任务（快照空闲时不刷新）
+pub fn spawn_refresher() {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(SNAPSHOT_REFRESH_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+
+            let result = tokio::task::spawn_blocking(|| {
+                let mut guard = lock();
+                if guard.last_read.elapsed() > SNAPSHOT_IDLE_AFTER {
+                    return;
+                }
+                if !guard.is_fresh(SNAPSHOT_REFRESH_INTERVAL / 2) {
+                    guard.refresh();
+                }
+            })
+            .await;
+
+            if let Err(e) = result {
+                tracing::warn!(target: "platform::snapshot", "⚠️ 后台刷新进程快照失败: {}", e);
+            }
+        }
+    });
+}
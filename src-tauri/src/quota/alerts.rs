@@ -0,0 +1,364 @@
+//! 低配额告警
+//!
+//! 规则保存在应用设置中，每次采样后评估：
+//! - 目标：某个模型（`*` 匹配全部）或套餐的 prompt / flow 积分
+//! - 条件：剩余低于阈值，或按当前消耗速率预计在 N 分钟内耗尽
+//!
+//! 触发时推送 `quota-alert` 事件、发送桌面通知并更新托盘提示。
+//! 同一规则对同一账户、同一目标在同一重置周期内只触发一次，条件解除后才会再次触发。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+use tracing::{error, info, warn};
+
+use crate::app_settings::AppSettingsManager;
+use crate::system_tray::SystemTrayManager;
+
+use super::projection::{consumption_rate, project};
+use super::store::{get_quota_store, QuotaSnapshot};
+
+/// 告警事件名
+pub const QUOTA_ALERT_EVENT: &str = "quota-alert";
+
+/// 计算消耗速率时使用的历史窗口（毫秒）
+const RATE_WINDOW_MS: i64 = 24 * 3600 * 1000;
+
+/// 告警目标
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum AlertTarget {
+    /// 模型配额，`model` 匹配模型 ID 或显示名称，`*` 匹配全部模型
+    Model { model: String },
+    /// 套餐 prompt 积分
+    PromptCredits,
+    /// 套餐 flow 积分
+    FlowCredits,
+}
+
+/// 告警条件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum AlertCondition {
+    /// 剩余低于阈值（模型为 0.0 ~ 1.0 的比例，积分为绝对数量）
+    Below { threshold: f64 },
+    /// 按当前消耗速率预计在指定分钟内耗尽
+    ExhaustsWithin { minutes: u64 },
+}
+
+/// 告警规则
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaAlertRule {
+    pub id: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub target: AlertTarget,
+    pub condition: AlertCondition,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// 已触发的告警
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaAlert {
+    pub rule_id: String,
+    pub email: String,
+    /// 模型 ID 或 `promptCredits` / `flowCredits`
+    pub target: String,
+    pub label: String,
+    pub remaining: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub projected_exhaustion: Option<i64>,
+    pub message: String,
+    pub timestamp: i64,
+}
+
+/// 告警去重状态：key 为 `规则|账户|目标`，值为触发时的重置周期与告警内容
+struct ActiveAlert {
+    reset_time: Option<String>,
+    alert: QuotaAlert,
+}
+
+static ACTIVE_ALERTS: OnceLock<Mutex<HashMap<String, ActiveAlert>>> = OnceLock::new();
+
+fn active_alerts() -> &'static Mutex<HashMap<String, ActiveAlert>> {
+    ACTIVE_ALERTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 校验规则（保存设置前调用）
+pub fn validate_rules(rules: &[QuotaAlertRule]) -> Result<(), String> {
+    let mut ids = std::collections::HashSet::new();
+    for rule in rules {
+        if rule.id.trim().is_empty() {
+            return Err("告警规则 ID 不能为空".to_string());
+        }
+        if !ids.insert(rule.id.as_str()) {
+            return Err(format!("告警规则 ID 重复: {}", rule.id));
+        }
+        match (&rule.target, &rule.condition) {
+            (AlertTarget::Model { .. }, AlertCondition::Below { threshold })
+                if !(0.0..=1.0).contains(threshold) =>
+            {
+                return Err(format!("规则 {} 的模型阈值必须在 0 ~ 1 之间", rule.id));
+            }
+            (_, AlertCondition::Below { threshold }) if *threshold < 0.0 => {
+                return Err(format!("规则 {} 的阈值不能为负数", rule.id));
+            }
+            (_, AlertCondition::ExhaustsWithin { minutes: 0 }) => {
+                return Err(format!("规则 {} 的时间窗口必须大于 0", rule.id));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// 获取当前仍处于触发状态的告警
+pub fn get_active_alerts() -> Vec<QuotaAlert> {
+    let mut alerts: Vec<QuotaAlert> = active_alerts()
+        .lock()
+        .map(|map| map.values().map(|a| a.alert.clone()).collect())
+        .unwrap_or_default();
+    alerts.sort_by_key(|a| a.timestamp);
+    alerts
+}
+
+/// 一条规则在某个目标上的评估结果
+struct Evaluation {
+    key: String,
+    reset_time: Option<String>,
+    alert: Option<QuotaAlert>,
+}
+
+/// 对一次采样评估所有启用的规则，并投递新触发的告警
+pub fn evaluate(app_handle: &AppHandle, snapshot: &QuotaSnapshot) {
+    let rules: Vec<QuotaAlertRule> = app_handle
+        .state::<AppSettingsManager>()
+        .get_settings()
+        .quota_alert_rules
+        .into_iter()
+        .filter(|r| r.enabled)
+        .collect();
+
+    let evaluations: Vec<Evaluation> = rules
+        .iter()
+        .flat_map(|rule| evaluate_rule(rule, snapshot))
+        .collect();
+
+    let fired = {
+        let Ok(mut active) = active_alerts().lock() else {
+            return;
+        };
+
+        // 移除已经不再被任何启用规则覆盖的告警（规则被删除或禁用）
+        let evaluated_keys: std::collections::HashSet<&str> =
+            evaluations.iter().map(|e| e.key.as_str()).collect();
+        let prefix = format!("|{}|", snapshot.email);
+        active.retain(|key, _| !key.contains(&prefix) || evaluated_keys.contains(key.as_str()));
+
+        let mut fired = Vec::new();
+        for evaluation in evaluations {
+            match evaluation.alert {
+                Some(alert) => match active.get_mut(&evaluation.key) {
+                    // 同一周期内已触发过：只刷新数值，不重复投递
+                    Some(existing) if existing.reset_time == evaluation.reset_time => {
+                        existing.alert.remaining = alert.remaining;
+                        existing.alert.projected_exhaustion = alert.projected_exhaustion;
+                    }
+                    _ => {
+                        fired.push(alert.clone());
+                        active.insert(
+                            evaluation.key,
+                            ActiveAlert {
+                                reset_time: evaluation.reset_time,
+                                alert,
+                            },
+                        );
+                    }
+                },
+                None => {
+                    active.remove(&evaluation.key);
+                }
+            }
+        }
+        fired
+    };
+
+    for alert in &fired {
+        deliver(app_handle, alert);
+    }
+
+    update_tray_tooltip(app_handle);
+}
+
+/// 评估单条规则，返回每个匹配目标的结果
+fn evaluate_rule(rule: &QuotaAlertRule, snapshot: &QuotaSnapshot) -> Vec<Evaluation> {
+    let now = snapshot.timestamp;
+
+    match &rule.target {
+        AlertTarget::Model { model } => snapshot
+            .models
+            .iter()
+            .filter(|s| model == "*" || s.model == *model || s.label == *model)
+            .map(|sample| {
+                let projected = match rule.condition {
+                    AlertCondition::ExhaustsWithin { .. } => model_exhaustion(&snapshot.email, &sample.model),
+                    AlertCondition::Below { .. } => None,
+                };
+                let triggered = is_triggered(&rule.condition, sample.remaining_fraction, projected, now);
+                Evaluation {
+                    key: alert_key(&rule.id, &snapshot.email, &sample.model),
+                    reset_time: sample.reset_time.clone(),
+                    alert: triggered.then(|| QuotaAlert {
+                        rule_id: rule.id.clone(),
+                        email: snapshot.email.clone(),
+                        target: sample.model.clone(),
+                        label: sample.label.clone(),
+                        remaining: sample.remaining_fraction,
+                        projected_exhaustion: projected,
+                        message: build_message(
+                            &sample.label,
+                            &format!("{:.0}%", sample.remaining_fraction * 100.0),
+                            projected,
+                            now,
+                        ),
+                        timestamp: now,
+                    }),
+                }
+            })
+            .collect(),
+        AlertTarget::PromptCredits | AlertTarget::FlowCredits => {
+            let Some(credits) = snapshot.credits.as_ref() else {
+                return Vec::new();
+            };
+            let is_prompt = rule.target == AlertTarget::PromptCredits;
+            let (target, label, remaining) = if is_prompt {
+                ("promptCredits", "Prompt 积分", credits.prompt_credits)
+            } else {
+                ("flowCredits", "Flow 积分", credits.flow_credits)
+            };
+
+            let projected = match rule.condition {
+                AlertCondition::ExhaustsWithin { .. } => credit_exhaustion(&snapshot.email, is_prompt, remaining, now),
+                AlertCondition::Below { .. } => None,
+            };
+            let triggered = is_triggered(&rule.condition, remaining, projected, now);
+
+            vec![Evaluation {
+                key: alert_key(&rule.id, &snapshot.email, target),
+                reset_time: None,
+                alert: triggered.then(|| QuotaAlert {
+                    rule_id: rule.id.clone(),
+                    email: snapshot.email.clone(),
+                    target: target.to_string(),
+                    label: label.to_string(),
+                    remaining,
+                    projected_exhaustion: projected,
+                    message: build_message(label, &format!("{:.0}", remaining), projected, now),
+                    timestamp: now,
+                }),
+            }]
+        }
+    }
+}
+
+fn alert_key(rule_id: &str, email: &str, target: &str) -> String {
+    format!("{}|{}|{}", rule_id, email, target)
+}
+
+fn is_triggered(condition: &AlertCondition, remaining: f64, projected: Option<i64>, now: i64) -> bool {
+    match condition {
+        AlertCondition::Below { threshold } => remaining < *threshold,
+        AlertCondition::ExhaustsWithin { minutes } => {
+            remaining <= 0.0
+                || projected.is_some_and(|t| t - now <= (*minutes as i64) * 60 * 1000)
+        }
+    }
+}
+
+fn build_message(label: &str, remaining: &str, projected: Option<i64>, now: i64) -> String {
+    match projected {
+        Some(t) => {
+            let minutes = ((t - now).max(0) / 60_000).max(1);
+            format!("{} 剩余 {}，预计 {} 分钟内耗尽", label, remaining, minutes)
+        }
+        None => format!("{} 剩余 {}", label, remaining),
+    }
+}
+
+/// 按历史预测模型的耗尽时间（仅在耗尽早于重置时返回）
+fn model_exhaustion(email: &str, model: &str) -> Option<i64> {
+    let store = get_quota_store()?;
+    let since = chrono::Local::now().timestamp_millis() - RATE_WINDOW_MS;
+    let history = store
+        .lock()
+        .ok()?
+        .model_history(email, Some(model), Some(since), None)
+        .ok()?;
+    let refs: Vec<_> = history.iter().collect();
+    let projection = project(&refs)?;
+
+    if projection.exhausts_before_reset {
+        projection.projected_exhaustion
+    } else {
+        None
+    }
+}
+
+/// 按历史预测积分的耗尽时间
+fn credit_exhaustion(email: &str, is_prompt: bool, remaining: f64, now: i64) -> Option<i64> {
+    let store = get_quota_store()?;
+    let history = store
+        .lock()
+        .ok()?
+        .credit_history(email, Some(now - RATE_WINDOW_MS), None)
+        .ok()?;
+    let points: Vec<(i64, f64)> = history
+        .iter()
+        .map(|c| (c.timestamp, if is_prompt { c.prompt_credits } else { c.flow_credits }))
+        .collect();
+    let rate = consumption_rate(&points)?;
+
+    Some(now + (remaining / rate * 3600.0 * 1000.0) as i64)
+}
+
+/// 投递告警：Tauri 事件 + 桌面通知
+fn deliver(app_handle: &AppHandle, alert: &QuotaAlert) {
+    info!(target: "quota::alerts", rule = %alert.rule_id, target_name = %alert.target, "⚠️ 触发配额告警");
+
+    if let Err(e) = app_handle.emit(QUOTA_ALERT_EVENT, alert) {
+        error!(target: "quota::alerts", "❌ 推送 {} 事件失败: {}", QUOTA_ALERT_EVENT, e);
+    }
+
+    if let Err(e) = app_handle
+        .notification()
+        .builder()
+        .title(format!("配额告警 - {}", alert.email))
+        .body(&alert.message)
+        .show()
+    {
+        warn!(target: "quota::alerts", "发送桌面通知失败: {}", e);
+    }
+}
+
+/// 用当前仍触发的告警刷新托盘提示
+fn update_tray_tooltip(app_handle: &AppHandle) {
+    let alerts = get_active_alerts();
+    let tooltip = if alerts.is_empty() {
+        None
+    } else {
+        let lines: Vec<String> = alerts.iter().map(|a| format!("⚠ {}", a.message)).collect();
+        Some(lines.join("\n"))
+    };
+
+    let system_tray = app_handle.state::<SystemTrayManager>();
+    if let Err(e) = system_tray.set_alert_tooltip(tooltip.as_deref(), alerts.len()) {
+        warn!(target: "quota::alerts", "更新托盘提示失败: {}", e);
+    }
+}
@@ -0,0 +1,318 @@
+//! 模型目录
+//!
+//! 把 GetUserStatus 中的 `cascadeModelConfigData` 规整为按账户保存的模型目录
+//! （模型、显示名称、允许的套餐层级、推荐/默认标记、排序分组），并与上次保存的目录比较：
+//! 模型出现、消失、允许层级变化或默认模型变化时推送 `model-catalog-changed` 事件。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter};
+use tracing::{error, info};
+
+use crate::language_server::types::GetUserStatusResponse;
+
+use super::store::get_quota_store;
+
+/// 目录变化事件名
+pub const MODEL_CATALOG_CHANGED_EVENT: &str = "model-catalog-changed";
+
+/// 目录中的单个模型
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogModel {
+    /// 模型 ID（缺失时使用显示名称）
+    pub model: String,
+    pub label: String,
+    /// 允许使用该模型的套餐层级（已排序）
+    #[serde(default)]
+    pub allowed_tiers: Vec<String>,
+    #[serde(default)]
+    pub recommended: bool,
+    /// 是否为默认模型
+    #[serde(default)]
+    pub is_default: bool,
+    #[serde(default)]
+    pub supports_images: Option<bool>,
+    /// 所在排序分组（第一个排序方案中的分组序号）
+    #[serde(default)]
+    pub sort_group: Option<usize>,
+}
+
+/// 账户的模型目录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelCatalog {
+    pub email: String,
+    /// 毫秒时间戳
+    pub updated_at: i64,
+    /// 默认模型 ID
+    #[serde(default)]
+    pub default_model: Option<String>,
+    /// 按排序分组、再按响应顺序排列
+    pub models: Vec<CatalogModel>,
+}
+
+/// 目录变化
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ModelCatalogChange {
+    Added { model: String, label: String },
+    Removed { model: String, label: String },
+    #[serde(rename_all = "camelCase")]
+    TiersChanged {
+        model: String,
+        label: String,
+        before: Vec<String>,
+        after: Vec<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    RecommendedChanged { model: String, label: String, recommended: bool },
+    DefaultChanged { before: Option<String>, after: Option<String> },
+}
+
+/// `model-catalog-changed` 事件内容
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelCatalogChangedEvent {
+    pub email: String,
+    pub changes: Vec<ModelCatalogChange>,
+    pub catalog: ModelCatalog,
+}
+
+impl ModelCatalog {
+    /// 从 GetUserStatus 响应生成目录
+    ///
+    /// 没有用户状态、邮箱或 `cascadeModelConfigData` 时为 `None`：缺少模型配置通常是
+    /// 响应不完整，不能当作空目录保存，否则会误报所有模型都已移除
+    pub fn from_response(status: &GetUserStatusResponse, updated_at: i64) -> Option<Self> {
+        let user_status = status.user_status.as_ref()?;
+        if user_status.email.is_empty() {
+            return None;
+        }
+        let data = user_status.cascade_model_config_data.as_ref()?;
+
+        let default_model = data
+            .default_override_model_config
+            .as_ref()
+            .and_then(|c| c.model_or_alias.as_ref())
+            .map(|m| m.model.clone())
+            .filter(|m| !m.is_empty());
+
+        let groups: HashMap<&str, usize> = data
+            .client_model_sorts
+            .first()
+            .map(|sort| {
+                sort.groups
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(index, group)| group.model_labels.iter().map(move |label| (label.as_str(), index)))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut models: Vec<CatalogModel> = data
+            .client_model_configs
+            .iter()
+            .map(|config| {
+                let model = config
+                    .model_or_alias
+                    .as_ref()
+                    .map(|m| m.model.clone())
+                    .filter(|m| !m.is_empty())
+                    .unwrap_or_else(|| config.label.clone());
+                let mut allowed_tiers = config.allowed_tiers.clone();
+                allowed_tiers.sort();
+                allowed_tiers.dedup();
+
+                CatalogModel {
+                    is_default: default_model.as_deref() == Some(model.as_str()),
+                    model,
+                    label: config.label.clone(),
+                    allowed_tiers,
+                    recommended: config.is_recommended,
+                    supports_images: config.supports_images,
+                    sort_group: groups.get(config.label.as_str()).copied(),
+                }
+            })
+            .collect();
+
+        // 稳定排序：有分组的按分组在前，同组内保持响应顺序
+        models.sort_by_key(|m| m.sort_group.unwrap_or(usize::MAX));
+
+        Some(Self {
+            email: user_status.email.clone(),
+            updated_at,
+            default_model,
+            models,
+        })
+    }
+
+    /// 与上一次的目录比较
+    pub fn diff(&self, previous: &ModelCatalog) -> Vec<ModelCatalogChange> {
+        let before: HashMap<&str, &CatalogModel> =
+            previous.models.iter().map(|m| (m.model.as_str(), m)).collect();
+        let after: HashMap<&str, &CatalogModel> = self.models.iter().map(|m| (m.model.as_str(), m)).collect();
+
+        let mut changes = Vec::new();
+        for model in &self.models {
+            match before.get(model.model.as_str()) {
+                None => changes.push(ModelCatalogChange::Added {
+                    model: model.model.clone(),
+                    label: model.label.clone(),
+                }),
+                Some(old) => {
+                    if old.allowed_tiers != model.allowed_tiers {
+                        changes.push(ModelCatalogChange::TiersChanged {
+                            model: model.model.clone(),
+                            label: model.label.clone(),
+                            before: old.allowed_tiers.clone(),
+                            after: model.allowed_tiers.clone(),
+                        });
+                    }
+                    if old.recommended != model.recommended {
+                        changes.push(ModelCatalogChange::RecommendedChanged {
+                            model: model.model.clone(),
+                            label: model.label.clone(),
+                            recommended: model.recommended,
+                        });
+                    }
+                }
+            }
+        }
+        for model in &previous.models {
+            if !after.contains_key(model.model.as_str()) {
+                changes.push(ModelCatalogChange::Removed {
+                    model: model.model.clone(),
+                    label: model.label.clone(),
+                });
+            }
+        }
+        if previous.default_model != self.default_model {
+            changes.push(ModelCatalogChange::DefaultChanged {
+                before: previous.default_model.clone(),
+                after: self.default_model.clone(),
+            });
+        }
+
+        changes
+    }
+}
+
+/// 用一次 GetUserStatus 响应更新账户的模型目录，返回新目录及相对上次的变化
+///
+/// 第一次看到某个账户的目录时只保存，不报告变化；有变化且提供了应用句柄时推送事件
+pub fn update(
+    app_handle: Option<&AppHandle>,
+    status: &GetUserStatusResponse,
+) -> Result<Option<(ModelCatalog, Vec<ModelCatalogChange>)>, String> {
+    let Some(catalog) = ModelCatalog::from_response(status, chrono::Local::now().timestamp_millis()) else {
+        return Ok(None);
+    };
+
+    let changes = {
+        let store = get_quota_store().ok_or("配额历史库不可用")?;
+        let store = store.lock().map_err(|e| format!("配额历史库锁异常: {}", e))?;
+        let previous = store
+            .load_catalog(&catalog.email)
+            .map_err(|e| format!("读取模型目录失败: {}", e))?;
+        store
+            .save_catalog(&catalog)
+            .map_err(|e| format!("保存模型目录失败: {}", e))?;
+        previous.map(|previous| catalog.diff(&previous)).unwrap_or_default()
+    };
+
+    if !changes.is_empty() {
+        info!(target: "quota::catalog", email = %catalog.email, count = changes.len(), "🆕 模型目录发生变化");
+        if let Some(app_handle) = app_handle {
+            let event = ModelCatalogChangedEvent {
+                email: catalog.email.clone(),
+                changes: changes.clone(),
+                catalog: catalog.clone(),
+            };
+            if let Err(e) = app_handle.emit(MODEL_CATALOG_CHANGED_EVENT, &event) {
+                error!(target: "quota::catalog", "❌ 推送 {} 事件失败: {}", MODEL_CATALOG_CHANGED_EVENT, e);
+            }
+        }
+    }
+
+    Ok(Some((catalog, changes)))
+}
+
+/// 读取账户最近保存的模型目录
+pub fn load(email: &str) -> Result<Option<ModelCatalog>, String> {
+    let store = get_quota_store().ok_or("配额历史库不可用")?;
+    let store = store.lock().map_err(|e| format!("配额历史库锁异常: {}", e))?;
+    store
+        .load_catalog(email)
+        .map_err(|e| format!("读取模型目录失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language_server::test_support::sample_user_status;
+
+    #[test]
+    fn diffs_model_catalogs() {
+        let mut body = sample_user_status("tester@example.com");
+        let response: GetUserStatusResponse = serde_json::from_value(body.clone()).unwrap();
+        let previous = ModelCatalog::from_response(&response, 1).unwrap();
+        assert_eq!(previous.models.len(), 2);
+        assert!(previous.diff(&previous).is_empty());
+
+        let configs = &mut body["userStatus"]["cascadeModelConfigData"];
+        configs["clientModelConfigs"][0]["allowedTiers"] = serde_json::json!(["TEAMS_TIER_PRO"]);
+        configs["clientModelConfigs"][1] = serde_json::json!({
+            "label": "GPT-OSS 120B",
+            "modelOrAlias": { "model": "MODEL_OPENAI_GPT_OSS_120B_MEDIUM" },
+            "isRecommended": true
+        });
+        configs["defaultOverrideModelConfig"] = serde_json::json!({
+            "modelOrAlias": { "model": "MODEL_PLACEHOLDER_M7" }
+        });
+        let response: GetUserStatusResponse = serde_json::from_value(body).unwrap();
+        let current = ModelCatalog::from_response(&response, 2).unwrap();
+
+        assert!(current.models.iter().any(|m| m.model == "MODEL_PLACEHOLDER_M7" && m.is_default));
+        assert_eq!(
+            current.diff(&previous),
+            vec![
+                ModelCatalogChange::TiersChanged {
+                    model: "MODEL_PLACEHOLDER_M7".to_string(),
+                    label: "Gemini 3 Pro".to_string(),
+                    before: vec![],
+                    after: vec!["TEAMS_TIER_PRO".to_string()],
+                },
+                ModelCatalogChange::Added {
+                    model: "MODEL_OPENAI_GPT_OSS_120B_MEDIUM".to_string(),
+                    label: "GPT-OSS 120B".to_string(),
+                },
+                ModelCatalogChange::Removed {
+                    model: "MODEL_PLACEHOLDER_M8".to_string(),
+                    label: "Claude Sonnet 4.5".to_string(),
+                },
+                ModelCatalogChange::DefaultChanged {
+                    before: None,
+                    after: Some("MODEL_PLACEHOLDER_M7".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_model_config_yields_no_catalog() {
+        let status: GetUserStatusResponse = serde_json::from_value(serde_json::json!({
+            "userStatus": { "email": "user@example.com" }
+        }))
+        .unwrap();
+        assert!(ModelCatalog::from_response(&status, 0).is_none());
+
+        let status: GetUserStatusResponse = serde_json::from_value(serde_json::json!({
+            "userStatus": { "email": "user@example.com", "cascadeModelConfigData": {} }
+        }))
+        .unwrap();
+        let catalog = ModelCatalog::from_response(&status, 0).unwrap();
+        assert_eq!(catalog.email, "user@example.com");
+        assert!(catalog.models.is_empty());
+    }
+}
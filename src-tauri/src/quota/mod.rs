@@ -0,0 +1,19 @@
+//! 配额历史模块
+//!
+//! - `store`: 按账户/模型保存配额与积分的时间序列
+//! - `sampler`: Antigravity 运行期间的后台采样
+//! - `projection`: 消耗速率与耗尽时间预测
+//! - `alerts`: 基于设置中规则的低配额告警
+//! - `catalog`: 按账户保存的模型目录及变化检测
+
+pub mod alerts;
+pub mod catalog;
+pub mod projection;
+pub mod sampler;
+pub mod store;
+
+pub use alerts::{get_active_alerts, QuotaAlert, QuotaAlertRule};
+pub use catalog::{ModelCatalog, ModelCatalogChange};
+pub use projection::{project_all, QuotaProjection};
+pub use sampler::{sample_once, spawn_sampler};
+pub use store::{get_quota_store, CreditSample, ModelQuotaSample, QuotaSnapshot};
@@ -0,0 +1,110 @@
+//! 配额消耗速率与耗尽时间预测
+//!
+//! 只使用与最新采样属于同一重置周期（`resetTime` 相同）的数据，
+//! 用最小二乘拟合剩余比例随时间的斜率，得出每小时消耗速率和预计耗尽时间。
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::store::ModelQuotaSample;
+
+/// 参与拟合的最长时间窗口（毫秒）
+const PROJECTION_WINDOW_MS: i64 = 24 * 3600 * 1000;
+
+/// 单个模型的预测结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaProjection {
+    pub model: String,
+    pub label: String,
+    pub remaining_fraction: f64,
+    pub reset_time: Option<String>,
+    /// 每小时消耗的比例（无消耗或样本不足时为 `None`）
+    pub consumption_per_hour: Option<f64>,
+    /// 预计耗尽时间（毫秒时间戳）
+    pub projected_exhaustion: Option<i64>,
+    /// 预计在重置之前耗尽
+    pub exhausts_before_reset: bool,
+    pub sample_count: usize,
+}
+
+/// 按模型分组计算预测（输入需按时间正序）
+pub fn project_all(samples: &[ModelQuotaSample]) -> Vec<QuotaProjection> {
+    let mut by_model: BTreeMap<&str, Vec<&ModelQuotaSample>> = BTreeMap::new();
+    for sample in samples {
+        by_model.entry(sample.model.as_str()).or_default().push(sample);
+    }
+
+    by_model
+        .into_values()
+        .filter_map(|model_samples| project(&model_samples))
+        .collect()
+}
+
+/// 计算单个模型的预测（输入需按时间正序）
+pub fn project(samples: &[&ModelQuotaSample]) -> Option<QuotaProjection> {
+    let latest = *samples.last()?;
+    let window_start = latest.timestamp - PROJECTION_WINDOW_MS;
+
+    let window: Vec<&ModelQuotaSample> = samples
+        .iter()
+        .copied()
+        .filter(|s| s.timestamp >= window_start && s.reset_time == latest.reset_time)
+        .collect();
+
+    let points: Vec<(i64, f64)> = window.iter().map(|s| (s.timestamp, s.remaining_fraction)).collect();
+    let consumption_per_hour = consumption_rate(&points);
+    let projected_exhaustion = consumption_per_hour.map(|rate| {
+        let hours_left = latest.remaining_fraction / rate;
+        latest.timestamp + (hours_left * 3600.0 * 1000.0) as i64
+    });
+
+    let reset_ms = latest
+        .reset_time
+        .as_deref()
+        .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.timestamp_millis());
+    let exhausts_before_reset = match (projected_exhaustion, reset_ms) {
+        (Some(exhaustion), Some(reset)) => exhaustion < reset,
+        (Some(_), None) => true,
+        _ => false,
+    };
+
+    Some(QuotaProjection {
+        model: latest.model.clone(),
+        label: latest.label.clone(),
+        remaining_fraction: latest.remaining_fraction,
+        reset_time: latest.reset_time.clone(),
+        consumption_per_hour,
+        projected_exhaustion,
+        exhausts_before_reset,
+        sample_count: window.len(),
+    })
+}
+
+/// 最小二乘拟合斜率，返回每小时消耗量（正数）
+///
+/// `points` 为 `(毫秒时间戳, 剩余量)`，剩余量可以是比例也可以是积分数
+pub fn consumption_rate(points: &[(i64, f64)]) -> Option<f64> {
+    if points.len() < 2 {
+        return None;
+    }
+
+    let t0 = points[0].0;
+    let points: Vec<(f64, f64)> = points
+        .iter()
+        .map(|(t, v)| ((t - t0) as f64 / 3_600_000.0, *v))
+        .collect();
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let var_x: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if var_x <= f64::EPSILON {
+        return None;
+    }
+    let cov: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let slope = cov / var_x;
+
+    (slope < 0.0).then_some(-slope)
+}
@@ -0,0 +1,157 @@
+//! 配额后台采样
+//!
+//! 语言服务器就绪期间按设置中的间隔调用 GetUserStatus，
+//! 把结果写入配额历史库，通过 `quota-sampled` 事件通知前端并评估告警规则，
+//! 同时更新当前账户的模型目录。
+
+use rusqlite::{Connection, OpenFlags};
+use serde_json::Value;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{debug, error, info, warn};
+
+use crate::app_settings::AppSettingsManager;
+use crate::constants::database;
+use crate::language_server::readiness;
+
+use super::{alerts, catalog};
+use super::store::{get_quota_store, QuotaSnapshot};
+
+/// 采样完成后推送的事件名
+pub const QUOTA_SAMPLED_EVENT: &str = "quota-sampled";
+
+/// 采样关闭或语言服务器未就绪时的检查间隔
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 允许的最小采样间隔（秒），避免频繁请求语言服务器
+pub const MIN_SAMPLE_INTERVAL_SECS: u64 = 30;
+
+/// 当前登录账户
+pub(crate) struct CurrentAuth {
+    pub email: String,
+    pub api_key: String,
+}
+
+/// 启动后台采样循环（应用生命周期内只调用一次）
+pub fn spawn_sampler(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        info!(target: "quota::sampler", "🚀 配额采样任务已启动");
+
+        loop {
+            let interval_secs = app_handle
+                .state::<AppSettingsManager>()
+                .get_settings()
+                .quota_sample_interval_secs;
+
+            if interval_secs == 0 {
+                tokio::time::sleep(IDLE_CHECK_INTERVAL).await;
+                continue;
+            }
+
+            // 语言服务器未就绪时等待就绪，而不是盲目请求
+            if let Err(e) = readiness::wait_until_ready(IDLE_CHECK_INTERVAL).await {
+                debug!(target: "quota::sampler", "跳过本轮采样: {}", e);
+                continue;
+            }
+
+            if let Err(e) = sample_once(&app_handle).await {
+                warn!(target: "quota::sampler", "配额采样失败: {}", e);
+            }
+
+            let interval = interval_secs.max(MIN_SAMPLE_INTERVAL_SECS);
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+        }
+    });
+}
+
+/// 立即采样一次，返回写入的数据
+pub async fn sample_once(app_handle: &AppHandle) -> Result<QuotaSnapshot, String> {
+    let auth = tokio::task::spawn_blocking(read_current_auth)
+        .await
+        .map_err(|e| format!("读取登录状态任务异常: {}", e))??;
+
+    let mut status = crate::language_server::commands::fetch_user_status(&auth.api_key).await?;
+
+    // 以语言服务器返回的账户为准，缺失时回退到本地登录账户
+    if let Some(user_status) = status.user_status.as_mut() {
+        if user_status.email.is_empty() {
+            user_status.email = auth.email.clone();
+        } else if user_status.email != auth.email {
+            debug!(
+                target: "quota::sampler",
+                local = %auth.email,
+                reported = %user_status.email,
+                "语言服务器返回的账户与本地登录账户不一致"
+            );
+        }
+    }
+
+    // 更新模型目录（变化时推送事件）
+    let catalog_app = app_handle.clone();
+    let catalog_status = status.clone();
+    match tokio::task::spawn_blocking(move || catalog::update(Some(&catalog_app), &catalog_status)).await {
+        Ok(Err(e)) => warn!(target: "quota::sampler", "更新模型目录失败: {}", e),
+        Err(e) => error!(target: "quota::sampler", "❌ 模型目录任务异常: {}", e),
+        Ok(Ok(_)) => {}
+    }
+
+    let timestamp = chrono::Local::now().timestamp_millis();
+    let snapshot = tokio::task::spawn_blocking(move || {
+        let store = get_quota_store().ok_or("配额历史库不可用")?;
+        let mut store = store.lock().map_err(|e| format!("配额历史库锁异常: {}", e))?;
+        let snapshot = store
+            .record(timestamp, &status)
+            .map_err(|e| format!("写入配额历史失败: {}", e))?
+            .ok_or("语言服务器未返回用户状态")?;
+        if let Err(e) = store.prune() {
+            warn!(target: "quota::sampler", "清理配额历史失败: {}", e);
+        }
+        Ok::<_, String>(snapshot)
+    })
+    .await
+    .map_err(|e| format!("写入配额历史任务异常: {}", e))??;
+
+    debug!(target: "quota::sampler", count = snapshot.models.len(), "✅ 配额采样完成");
+
+    if let Err(e) = app_handle.emit(QUOTA_SAMPLED_EVENT, &snapshot) {
+        error!(target: "quota::sampler", "❌ 推送 {} 事件失败: {}", QUOTA_SAMPLED_EVENT, e);
+    }
+
+    let alert_app = app_handle.clone();
+    let alert_snapshot = snapshot.clone();
+    if let Err(e) = tokio::task::spawn_blocking(move || alerts::evaluate(&alert_app, &alert_snapshot)).await {
+        error!(target: "quota::sampler", "❌ 告警评估任务异常: {}", e);
+    }
+
+    Ok(snapshot)
+}
+
+/// 从 state.vscdb 读取当前登录账户的邮箱与 apiKey
+pub(crate) fn read_current_auth() -> Result<CurrentAuth, String> {
+    let db_path = crate::platform::get_antigravity_db_path().ok_or("未找到 Antigravity 数据库路径")?;
+    let conn = Connection::open_with_flags(&db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("连接数据库失败 ({}): {}", db_path.display(), e))?;
+
+    let raw: String = conn
+        .query_row(
+            "SELECT value FROM ItemTable WHERE key = ?",
+            [database::AUTH_STATUS],
+            |row| row.get(0),
+        )
+        .map_err(|_| "当前未登录 Antigravity".to_string())?;
+
+    let value: Value = serde_json::from_str(&raw).map_err(|e| format!("解析登录状态失败: {}", e))?;
+    let field = |name: &str| {
+        value
+            .get(name)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+
+    Ok(CurrentAuth {
+        email: field("email").ok_or("登录状态中缺少邮箱")?,
+        api_key: field("apiKey").ok_or("登录状态中缺少 apiKey")?,
+    })
+}
@@ -0,0 +1,286 @@
+//! 配额历史存储
+//!
+//! 按账户、按模型保存 `remainingFraction` / `resetTime`，并单独保存套餐积分，
+//! 以本地 SQLite 时间序列表的形式存放在配置目录下。
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use crate::language_server::types::GetUserStatusResponse;
+use crate::path_utils::AppPaths;
+
+use super::catalog::ModelCatalog;
+
+/// 历史库文件名
+const HISTORY_FILE_NAME: &str = "quota_history.sqlite";
+
+/// 最多保留的天数
+const MAX_HISTORY_AGE_DAYS: i64 = 60;
+
+/// 单个模型的配额采样
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelQuotaSample {
+    /// 毫秒时间戳
+    pub timestamp: i64,
+    pub email: String,
+    pub model: String,
+    pub label: String,
+    pub remaining_fraction: f64,
+    pub reset_time: Option<String>,
+}
+
+/// 套餐积分采样
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreditSample {
+    pub timestamp: i64,
+    pub email: String,
+    pub prompt_credits: f64,
+    pub flow_credits: f64,
+}
+
+/// 一次采样写入的全部数据
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaSnapshot {
+    pub timestamp: i64,
+    pub email: String,
+    pub models: Vec<ModelQuotaSample>,
+    pub credits: Option<CreditSample>,
+}
+
+/// 配额历史库
+pub struct QuotaStore {
+    conn: Connection,
+}
+
+impl QuotaStore {
+    /// 打开（或创建）历史库
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS model_samples (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 timestamp INTEGER NOT NULL,
+                 email TEXT NOT NULL,
+                 model TEXT NOT NULL,
+                 label TEXT NOT NULL,
+                 remaining_fraction REAL NOT NULL,
+                 reset_time TEXT
+             );
+             CREATE INDEX IF NOT EXISTS idx_model_samples ON model_samples(email, model, timestamp);
+             CREATE TABLE IF NOT EXISTS credit_samples (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 timestamp INTEGER NOT NULL,
+                 email TEXT NOT NULL,
+                 prompt_credits REAL NOT NULL,
+                 flow_credits REAL NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS idx_credit_samples ON credit_samples(email, timestamp);
+             CREATE TABLE IF NOT EXISTS model_catalogs (
+                 email TEXT PRIMARY KEY,
+                 updated_at INTEGER NOT NULL,
+                 catalog TEXT NOT NULL
+             );",
+        )?;
+
+        Ok(Self { conn })
+    }
+
+    /// 写入一次 GetUserStatus 采样，返回写入的数据（响应中没有用户状态时为 `None`）
+    pub fn record(
+        &mut self,
+        timestamp: i64,
+        status: &GetUserStatusResponse,
+    ) -> rusqlite::Result<Option<QuotaSnapshot>> {
+        let Some(user_status) = status.user_status.as_ref() else {
+            return Ok(None);
+        };
+        let email = user_status.email.clone();
+
+        let samples: Vec<ModelQuotaSample> = user_status
+            .cascade_model_config_data
+            .iter()
+            .flat_map(|data| data.client_model_configs.iter())
+            .filter_map(|config| {
+                let quota = config.quota_info.as_ref()?;
+                let model = config
+                    .model_or_alias
+                    .as_ref()
+                    .map(|m| m.model.clone())
+                    .filter(|m| !m.is_empty())
+                    .unwrap_or_else(|| config.label.clone());
+                Some(ModelQuotaSample {
+                    timestamp,
+                    email: email.clone(),
+                    model,
+                    label: config.label.clone(),
+                    remaining_fraction: quota.remaining_fraction,
+                    reset_time: quota.reset_time.clone(),
+                })
+            })
+            .collect();
+
+        let credits = user_status.plan_status.as_ref().map(|plan| CreditSample {
+            timestamp,
+            email: email.clone(),
+            prompt_credits: plan.available_prompt_credits,
+            flow_credits: plan.available_flow_credits,
+        });
+
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO model_samples (timestamp, email, model, label, remaining_fraction, reset_time)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for sample in &samples {
+                stmt.execute(params![
+                    sample.timestamp,
+                    sample.email,
+                    sample.model,
+                    sample.label,
+                    sample.remaining_fraction,
+                    sample.reset_time,
+                ])?;
+            }
+
+            if let Some(credit) = credits.as_ref() {
+                tx.execute(
+                    "INSERT INTO credit_samples (timestamp, email, prompt_credits, flow_credits)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![credit.timestamp, credit.email, credit.prompt_credits, credit.flow_credits],
+                )?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(Some(QuotaSnapshot {
+            timestamp,
+            email,
+            models: samples,
+            credits,
+        }))
+    }
+
+    /// 查询模型配额历史（按时间正序）
+    pub fn model_history(
+        &self,
+        email: &str,
+        model: Option<&str>,
+        from: Option<i64>,
+        to: Option<i64>,
+    ) -> rusqlite::Result<Vec<ModelQuotaSample>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT timestamp, email, model, label, remaining_fraction, reset_time FROM model_samples
+             WHERE email = ?1 AND (?2 IS NULL OR model = ?2) AND timestamp >= ?3 AND timestamp <= ?4
+             ORDER BY timestamp ASC",
+        )?;
+
+        let rows = stmt.query_map(
+            params![email, model, from.unwrap_or(i64::MIN), to.unwrap_or(i64::MAX)],
+            |row| {
+                Ok(ModelQuotaSample {
+                    timestamp: row.get(0)?,
+                    email: row.get(1)?,
+                    model: row.get(2)?,
+                    label: row.get(3)?,
+                    remaining_fraction: row.get(4)?,
+                    reset_time: row.get(5)?,
+                })
+            },
+        )?;
+
+        rows.collect()
+    }
+
+    /// 查询积分历史（按时间正序）
+    pub fn credit_history(
+        &self,
+        email: &str,
+        from: Option<i64>,
+        to: Option<i64>,
+    ) -> rusqlite::Result<Vec<CreditSample>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT timestamp, email, prompt_credits, flow_credits FROM credit_samples
+             WHERE email = ?1 AND timestamp >= ?2 AND timestamp <= ?3
+             ORDER BY timestamp ASC",
+        )?;
+
+        let rows = stmt.query_map(
+            params![email, from.unwrap_or(i64::MIN), to.unwrap_or(i64::MAX)],
+            |row| {
+                Ok(CreditSample {
+                    timestamp: row.get(0)?,
+                    email: row.get(1)?,
+                    prompt_credits: row.get(2)?,
+                    flow_credits: row.get(3)?,
+                })
+            },
+        )?;
+
+        rows.collect()
+    }
+
+    /// 读取账户最近保存的模型目录
+    pub fn load_catalog(&self, email: &str) -> rusqlite::Result<Option<ModelCatalog>> {
+        let raw: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT catalog FROM model_catalogs WHERE email = ?1",
+                [email],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        // 无法解析的旧数据视为没有目录
+        Ok(raw.and_then(|raw| serde_json::from_str(&raw).ok()))
+    }
+
+    /// 保存账户的模型目录（覆盖旧目录）
+    pub fn save_catalog(&self, catalog: &ModelCatalog) -> rusqlite::Result<()> {
+        let raw = serde_json::to_string(catalog)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        self.conn.execute(
+            "INSERT INTO model_catalogs (email, updated_at, catalog) VALUES (?1, ?2, ?3)
+             ON CONFLICT(email) DO UPDATE SET updated_at = excluded.updated_at, catalog = excluded.catalog",
+            params![catalog.email, catalog.updated_at, raw],
+        )?;
+        Ok(())
+    }
+
+    /// 清理过期采样
+    pub fn prune(&self) -> rusqlite::Result<usize> {
+        let cutoff = chrono::Local::now().timestamp_millis() - MAX_HISTORY_AGE_DAYS * 24 * 3600 * 1000;
+        let models = self.conn.execute("DELETE FROM model_samples WHERE timestamp < ?1", [cutoff])?;
+        let credits = self.conn.execute("DELETE FROM credit_samples WHERE timestamp < ?1", [cutoff])?;
+        Ok(models + credits)
+    }
+}
+
+/// 全局历史库实例（打开失败时为 `None`）
+static GLOBAL_STORE: OnceLock<Option<Mutex<QuotaStore>>> = OnceLock::new();
+
+/// 获取全局配额历史库
+pub fn get_quota_store() -> Option<&'static Mutex<QuotaStore>> {
+    GLOBAL_STORE
+        .get_or_init(|| {
+            let config_dir = AppPaths::config_dir()?;
+            if let Err(e) = std::fs::create_dir_all(&config_dir) {
+                tracing::warn!(target: "quota::store", "创建配置目录失败: {}", e);
+            }
+            let path = config_dir.join(HISTORY_FILE_NAME);
+            match QuotaStore::open(&path) {
+                Ok(store) => Some(Mutex::new(store)),
+                Err(e) => {
+                    tracing::error!(target: "quota::store", "打开配额历史库失败 {}: {}", path.display(), e);
+                    None
+                }
+            }
+        })
+        .as_ref()
+}
@@ -0,0 +1,367 @@
+//! 资源占用告警
+//!
+//! 规则保存在应用设置中，每次采样后对进程树中的每个进程评估：
+//! - 范围：某个进程角色（不指定时匹配全部进程）
+//! - 条件：CPU 占用或常驻内存超过阈值
+//! - 持续时间：连续超过阈值达到 `sustained_secs` 才触发，避免短暂峰值误报
+//!
+//! 触发时推送 `resource-alert` 事件并发送桌面通知；规则开启 `restart_prompt` 时，
+//! 事件中带有重启提示，由前端询问用户是否重启 Antigravity。
+//! 同一规则对同一进程只触发一次，条件解除或进程退出后才会再次触发。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+use tracing::{error, info, warn};
+
+use crate::platform::{ProcessNode, ProcessRole, ProcessTree};
+
+/// 告警事件名
+pub const RESOURCE_ALERT_EVENT: &str = "resource-alert";
+
+/// 告警条件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ResourceCondition {
+    /// CPU 占用超过指定百分比（单核满载为 100）
+    CpuAbove { percent: f32 },
+    /// 常驻内存超过指定 MB
+    MemoryAbove { megabytes: u64 },
+}
+
+impl ResourceCondition {
+    /// 返回 `(当前值, 阈值)`，超过阈值时为 `Some`
+    fn exceeded(&self, node: &ProcessNode) -> Option<(f64, f64)> {
+        match self {
+            ResourceCondition::CpuAbove { percent } => {
+                (node.cpu_percent > *percent).then_some((node.cpu_percent as f64, *percent as f64))
+            }
+            ResourceCondition::MemoryAbove { megabytes } => {
+                let used = node.memory_bytes as f64 / (1024.0 * 1024.0);
+                (used > *megabytes as f64).then_some((used, *megabytes as f64))
+            }
+        }
+    }
+}
+
+/// 告警规则
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceAlertRule {
+    pub id: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 只评估该角色的进程，`None` 表示全部进程
+    #[serde(default)]
+    pub role: Option<ProcessRole>,
+    pub condition: ResourceCondition,
+    /// 连续超过阈值多少秒后触发
+    pub sustained_secs: u64,
+    /// 触发时是否提示重启 Antigravity
+    #[serde(default)]
+    pub restart_prompt: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// 默认规则：语言服务器持续占满一个核心，或内存超过 4GB
+pub fn default_rules() -> Vec<ResourceAlertRule> {
+    vec![
+        ResourceAlertRule {
+            id: "language-server-cpu".to_string(),
+            enabled: true,
+            role: Some(ProcessRole::LanguageServer),
+            condition: ResourceCondition::CpuAbove { percent: 90.0 },
+            sustained_secs: 120,
+            restart_prompt: true,
+        },
+        ResourceAlertRule {
+            id: "language-server-memory".to_string(),
+            enabled: true,
+            role: Some(ProcessRole::LanguageServer),
+            condition: ResourceCondition::MemoryAbove { megabytes: 4096 },
+            sustained_secs: 60,
+            restart_prompt: true,
+        },
+    ]
+}
+
+/// 已触发的告警
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceAlert {
+    pub rule_id: String,
+    pub pid: u32,
+    pub name: String,
+    pub role: ProcessRole,
+    /// 当前值（CPU 为百分比，内存为 MB）
+    pub value: f64,
+    pub threshold: f64,
+    /// 开始持续超过阈值的时间（毫秒时间戳）
+    pub since: i64,
+    pub restart_prompt: bool,
+    pub message: String,
+    pub timestamp: i64,
+}
+
+/// 持续超限跟踪：key 为 `规则|PID|启动时间`
+#[derive(Default)]
+pub struct AlertTracker {
+    /// 开始超过阈值的时间
+    breaches: HashMap<String, i64>,
+    /// 已触发、条件尚未解除的告警
+    active: HashMap<String, ResourceAlert>,
+}
+
+impl AlertTracker {
+    /// 对一次采样评估所有启用的规则，返回新触发的告警
+    pub fn evaluate(&mut self, rules: &[ResourceAlertRule], tree: &ProcessTree, now: i64) -> Vec<ResourceAlert> {
+        let nodes = tree.nodes();
+        let mut seen = std::collections::HashSet::new();
+        let mut fired = Vec::new();
+
+        for rule in rules.iter().filter(|r| r.enabled) {
+            for node in nodes.iter().filter(|n| !rule.role.is_some_and(|role| role != n.role)) {
+                let key = format!("{}|{}|{}", rule.id, node.pid, node.start_time);
+                let Some((value, threshold)) = rule.condition.exceeded(node) else {
+                    continue;
+                };
+                seen.insert(key.clone());
+
+                let since = *self.breaches.entry(key.clone()).or_insert(now);
+                if now - since < (rule.sustained_secs as i64) * 1000 {
+                    continue;
+                }
+
+                match self.active.get_mut(&key) {
+                    // 已触发过：只刷新数值，不重复投递
+                    Some(existing) => existing.value = value,
+                    None => {
+                        let alert = ResourceAlert {
+                            rule_id: rule.id.clone(),
+                            pid: node.pid,
+                            name: node.name.clone(),
+                            role: node.role,
+                            value,
+                            threshold,
+                            since,
+                            restart_prompt: rule.restart_prompt,
+                            message: build_message(rule, node, value, now - since),
+                            timestamp: now,
+                        };
+                        self.active.insert(key, alert.clone());
+                        fired.push(alert);
+                    }
+                }
+            }
+        }
+
+        // 条件解除、进程退出或规则被删除/禁用时清除状态
+        self.breaches.retain(|key, _| seen.contains(key));
+        self.active.retain(|key, _| seen.contains(key));
+
+        fired
+    }
+
+    pub fn active(&self) -> Vec<ResourceAlert> {
+        let mut alerts: Vec<ResourceAlert> = self.active.values().cloned().collect();
+        alerts.sort_by_key(|a| a.timestamp);
+        alerts
+    }
+}
+
+fn build_message(rule: &ResourceAlertRule, node: &ProcessNode, value: f64, elapsed_ms: i64) -> String {
+    let minutes = (elapsed_ms / 60_000).max(1);
+    match rule.condition {
+        ResourceCondition::CpuAbove { .. } => format!(
+            "{} (PID {}) CPU 占用 {:.0}%，已持续 {} 分钟",
+            node.name, node.pid, value, minutes
+        ),
+        ResourceCondition::MemoryAbove { .. } => format!(
+            "{} (PID {}) 内存占用 {:.0} MB，已持续 {} 分钟",
+            node.name, node.pid, value, minutes
+        ),
+    }
+}
+
+static TRACKER: OnceLock<Mutex<AlertTracker>> = OnceLock::new();
+
+fn tracker() -> &'static Mutex<AlertTracker> {
+    TRACKER.get_or_init(|| Mutex::new(AlertTracker::default()))
+}
+
+/// 校验规则（保存设置前调用）
+pub fn validate_rules(rules: &[ResourceAlertRule]) -> Result<(), String> {
+    let mut ids = std::collections::HashSet::new();
+    for rule in rules {
+        if rule.id.trim().is_empty() {
+            return Err("告警规则 ID 不能为空".to_string());
+        }
+        if !ids.insert(rule.id.as_str()) {
+            return Err(format!("告警规则 ID 重复: {}", rule.id));
+        }
+        match rule.condition {
+            ResourceCondition::CpuAbove { percent } if percent <= 0.0 => {
+                return Err(format!("规则 {} 的 CPU 阈值必须大于 0", rule.id));
+            }
+            ResourceCondition::MemoryAbove { megabytes: 0 } => {
+                return Err(format!("规则 {} 的内存阈值必须大于 0", rule.id));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// 获取当前仍处于触发状态的告警
+pub fn get_active_alerts() -> Vec<ResourceAlert> {
+    tracker().lock().map(|t| t.active()).unwrap_or_default()
+}
+
+/// 评估一次采样并投递新触发的告警
+pub fn evaluate(app_handle: &AppHandle, rules: &[ResourceAlertRule], tree: &ProcessTree) {
+    let fired = {
+        let Ok(mut tracker) = tracker().lock() else {
+            return;
+        };
+        tracker.evaluate(rules, tree, tree.captured_at)
+    };
+
+    for alert in &fired {
+        deliver(app_handle, alert);
+    }
+}
+
+/// 投递告警：Tauri 事件 + 桌面通知
+fn deliver(app_handle: &AppHandle, alert: &ResourceAlert) {
+    info!(target: "resource_monitor::alerts", rule = %alert.rule_id, pid = alert.pid, "⚠️ 触发资源告警");
+
+    if let Err(e) = app_handle.emit(RESOURCE_ALERT_EVENT, alert) {
+        error!(target: "resource_monitor::alerts", "❌ 推送 {} 事件失败: {}", RESOURCE_ALERT_EVENT, e);
+    }
+
+    let body = if alert.restart_prompt {
+        format!("{}\n可在应用中重启 Antigravity", alert.message)
+    } else {
+        alert.message.clone()
+    };
+    if let Err(e) = app_handle
+        .notification()
+        .builder()
+        .title("Antigravity 资源占用告警")
+        .body(body)
+        .show()
+    {
+        warn!(target: "resource_monitor::alerts", "发送桌面通知失败: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::process_tree::ProcessRecord;
+    use crate::resource_monitor::ResourceSample;
+
+    fn language_server_tree(cpu_percent: f32, captured_at: i64) -> ProcessTree {
+        let node = |pid: u32, name: &str, role: ProcessRole, cpu_percent: f32, children: Vec<ProcessNode>| ProcessNode {
+            pid,
+            parent_pid: None,
+            name: name.to_string(),
+            role,
+            start_time: 1_000,
+            cpu_percent,
+            memory_bytes: 512 * 1024 * 1024,
+            exe: None,
+            cmdline: Vec::new(),
+            matched: true,
+            children,
+        };
+        let server = node(300, "language_server_linux_x64", ProcessRole::LanguageServer, cpu_percent, Vec::new());
+        let main = node(100, "antigravity", ProcessRole::Main, 2.0, vec![server]);
+        ProcessTree {
+            roots: vec![main],
+            process_count: 2,
+            total_cpu_percent: cpu_percent + 2.0,
+            total_memory_bytes: 1024 * 1024 * 1024,
+            captured_at,
+        }
+    }
+
+    #[test]
+    fn resource_alerts_require_sustained_breach() {
+        let rules = default_rules();
+        let mut tracker = AlertTracker::default();
+
+        // 短暂峰值不触发
+        assert!(tracker.evaluate(&rules, &language_server_tree(100.0, 0), 0).is_empty());
+        assert!(tracker.evaluate(&rules, &language_server_tree(100.0, 60_000), 60_000).is_empty());
+
+        // 持续 2 分钟后触发一次，之后不重复投递
+        let fired = tracker.evaluate(&rules, &language_server_tree(100.0, 120_000), 120_000);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].rule_id, "language-server-cpu");
+        assert_eq!(fired[0].pid, 300);
+        assert_eq!(fired[0].since, 0);
+        assert!(fired[0].restart_prompt);
+        assert!(tracker.evaluate(&rules, &language_server_tree(100.0, 125_000), 125_000).is_empty());
+        assert_eq!(tracker.active().len(), 1);
+
+        // 条件解除后清除，再次超限需要重新计时
+        assert!(tracker.evaluate(&rules, &language_server_tree(10.0, 130_000), 130_000).is_empty());
+        assert!(tracker.active().is_empty());
+        assert!(tracker.evaluate(&rules, &language_server_tree(100.0, 135_000), 135_000).is_empty());
+
+        let sample = ResourceSample::from_tree(&language_server_tree(100.0, 0));
+        assert_eq!(sample.roles.len(), 2);
+        assert!(sample
+            .roles
+            .iter()
+            .any(|r| r.role == ProcessRole::LanguageServer && r.process_count == 1 && r.peak_cpu_percent == 100.0));
+    }
+
+    #[test]
+    fn thread_entries_are_not_counted_as_processes() {
+        let record = |pid: u32, parent_pid: Option<u32>, name: &str, is_thread: bool| ProcessRecord {
+            pid,
+            parent_pid,
+            name: name.to_string(),
+            cmdline: vec![format!("/opt/antigravity/{}", name)],
+            start_time: 1_000,
+            cpu_percent: 1.0,
+            memory_bytes: 5 * 1024 * 1024 * 1024,
+            exe: None,
+            is_thread,
+            matched: pid == 100,
+            excluded: false,
+        };
+        // 线程条目与所属进程同名、同内存，父进程为所属进程
+        let tree = ProcessTree::from_records(
+            vec![
+                record(100, Some(1), "antigravity", false),
+                record(300, Some(100), "language_server_linux_x64", false),
+                record(301, Some(300), "language_server_linux_x64", true),
+                record(302, Some(300), "language_server_linux_x64", true),
+            ],
+            0,
+        );
+
+        assert_eq!(tree.process_count, 2);
+        assert_eq!(tree.total_memory_bytes, 10 * 1024 * 1024 * 1024);
+        let sample = ResourceSample::from_tree(&tree);
+        assert!(sample
+            .roles
+            .iter()
+            .any(|r| r.role == ProcessRole::LanguageServer && r.process_count == 1));
+
+        let mut tracker = AlertTracker::default();
+        assert!(tracker.evaluate(&default_rules(), &tree, 0).is_empty());
+        let fired = tracker.evaluate(&default_rules(), &tree, 60_000);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].rule_id, "language-server-memory");
+        assert_eq!(fired[0].pid, 300);
+    }
+}
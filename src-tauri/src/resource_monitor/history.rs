@@ -0,0 +1,96 @@
+//! 资源占用历史
+//!
+//! 每次采样按进程角色汇总一次，保存在内存中的环形队列里，不落盘。
+
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+use crate::platform::{ProcessRole, ProcessTree};
+
+/// 单个角色的资源占用
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleUsage {
+    pub role: ProcessRole,
+    pub process_count: usize,
+    /// 该角色所有进程的 CPU 占用之和（百分比）
+    pub cpu_percent: f32,
+    /// 该角色所有进程的常驻内存之和（字节）
+    pub memory_bytes: u64,
+    /// 单个进程的最高 CPU 占用
+    pub peak_cpu_percent: f32,
+    /// 单个进程的最高常驻内存
+    pub peak_memory_bytes: u64,
+}
+
+/// 一次采样
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceSample {
+    /// 毫秒时间戳
+    pub timestamp: i64,
+    pub total_cpu_percent: f32,
+    pub total_memory_bytes: u64,
+    pub roles: Vec<RoleUsage>,
+}
+
+impl ResourceSample {
+    /// 按角色汇总进程树
+    pub fn from_tree(tree: &ProcessTree) -> Self {
+        let mut roles: BTreeMap<u8, RoleUsage> = BTreeMap::new();
+        for node in tree.nodes() {
+            let usage = roles.entry(node.role as u8).or_insert_with(|| RoleUsage {
+                role: node.role,
+                process_count: 0,
+                cpu_percent: 0.0,
+                memory_bytes: 0,
+                peak_cpu_percent: 0.0,
+                peak_memory_bytes: 0,
+            });
+            usage.process_count += 1;
+            usage.cpu_percent += node.cpu_percent;
+            usage.memory_bytes += node.memory_bytes;
+            usage.peak_cpu_percent = usage.peak_cpu_percent.max(node.cpu_percent);
+            usage.peak_memory_bytes = usage.peak_memory_bytes.max(node.memory_bytes);
+        }
+
+        Self {
+            timestamp: tree.captured_at,
+            total_cpu_percent: tree.total_cpu_percent,
+            total_memory_bytes: tree.total_memory_bytes,
+            roles: roles.into_values().collect(),
+        }
+    }
+}
+
+static HISTORY: OnceLock<Mutex<VecDeque<ResourceSample>>> = OnceLock::new();
+
+fn history_lock() -> &'static Mutex<VecDeque<ResourceSample>> {
+    HISTORY.get_or_init(|| Mutex::new(VecDeque::new()))
+}
+
+/// 追加一次采样，超出 `capacity` 时丢弃最早的记录
+pub fn record(sample: ResourceSample, capacity: usize) {
+    let Ok(mut history) = history_lock().lock() else {
+        return;
+    };
+    history.push_back(sample);
+    while history.len() > capacity {
+        history.pop_front();
+    }
+}
+
+/// 读取历史（按时间升序），`since` 为毫秒时间戳
+pub fn history(since: Option<i64>) -> Vec<ResourceSample> {
+    history_lock()
+        .lock()
+        .map(|history| {
+            history
+                .iter()
+                .filter(|s| !matches!(since, Some(t) if s.timestamp < t))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
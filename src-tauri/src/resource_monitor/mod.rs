@@ -0,0 +1,79 @@
+//! Antigravity 资源监控
+//!
+//! - `history`: 按进程角色汇总的 CPU / 内存时间序列（内存中保留最近一段时间）
+//! - `alerts`: 单个进程持续超过阈值时的告警（可附带重启提示）
+//! - `monitor`: 后台采样循环
+//!
+//! `language_server` 占满一个核心或内存涨到数 GB 时，往往要等机器明显变慢才会被发现，
+//! 这里基于共享进程快照定期采样，及早提醒。
+
+pub mod alerts;
+pub mod history;
+pub mod monitor;
+
+use serde::{Deserialize, Serialize};
+
+pub use alerts::{ResourceAlert, ResourceAlertRule};
+pub use history::ResourceSample;
+pub use monitor::spawn_monitor;
+
+/// 允许的最小采样间隔（秒）
+pub const MIN_SAMPLE_INTERVAL_SECS: u64 = 2;
+
+/// 资源监控设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceMonitorSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 采样间隔（秒）
+    #[serde(default = "default_sample_interval_secs")]
+    pub sample_interval_secs: u64,
+    /// 内存中保留的历史时长（分钟）
+    #[serde(default = "default_history_minutes")]
+    pub history_minutes: u64,
+    /// 告警规则
+    #[serde(default = "alerts::default_rules")]
+    pub rules: Vec<ResourceAlertRule>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_sample_interval_secs() -> u64 {
+    5
+}
+
+fn default_history_minutes() -> u64 {
+    30
+}
+
+impl Default for ResourceMonitorSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            sample_interval_secs: default_sample_interval_secs(),
+            history_minutes: default_history_minutes(),
+            rules: alerts::default_rules(),
+        }
+    }
+}
+
+impl ResourceMonitorSettings {
+    /// 校验设置（保存前调用）
+    pub fn validate(&self) -> Result<(), String> {
+        if self.sample_interval_secs < MIN_SAMPLE_INTERVAL_SECS {
+            return Err(format!("采样间隔不能小于 {} 秒", MIN_SAMPLE_INTERVAL_SECS));
+        }
+        if self.history_minutes == 0 {
+            return Err("历史保留时长必须大于 0".to_string());
+        }
+        alerts::validate_rules(&self.rules)
+    }
+
+    /// 按采样间隔换算的历史条数
+    pub fn history_capacity(&self) -> usize {
+        let interval = self.sample_interval_secs.max(MIN_SAMPLE_INTERVAL_SECS);
+        ((self.history_minutes * 60) / interval).max(1) as usize
+    }
+}
@@ -0,0 +1,63 @@
+//! 资源监控后台采样
+//!
+//! 按设置中的间隔采集 Antigravity 进程树，按角色汇总写入历史，
+//! 通过 `resource-sampled` 事件通知前端并评估告警规则。
+
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{debug, error, info};
+
+use crate::app_settings::AppSettingsManager;
+use crate::platform::process_tree::collect_process_tree;
+
+use super::history::{self, ResourceSample};
+use super::{alerts, MIN_SAMPLE_INTERVAL_SECS};
+
+/// 采样完成后推送的事件名
+pub const RESOURCE_SAMPLED_EVENT: &str = "resource-sampled";
+
+/// 监控关闭时的检查间隔
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 启动后台监控循环（应用生命周期内只调用一次）
+pub fn spawn_monitor(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        info!(target: "resource_monitor::monitor", "🚀 资源监控任务已启动");
+
+        loop {
+            let settings = app_handle
+                .state::<AppSettingsManager>()
+                .get_settings()
+                .resource_monitor;
+
+            if !settings.enabled {
+                tokio::time::sleep(IDLE_CHECK_INTERVAL).await;
+                continue;
+            }
+
+            match tokio::task::spawn_blocking(collect_process_tree).await {
+                Ok(tree) if tree.process_count == 0 => {
+                    debug!(target: "resource_monitor::monitor", "Antigravity 未运行，跳过本轮采样");
+                    // 进程全部退出后清除持续超限状态
+                    alerts::evaluate(&app_handle, &settings.rules, &tree);
+                }
+                Ok(tree) => {
+                    let sample = ResourceSample::from_tree(&tree);
+                    history::record(sample.clone(), settings.history_capacity());
+
+                    if let Err(e) = app_handle.emit(RESOURCE_SAMPLED_EVENT, &sample) {
+                        error!(target: "resource_monitor::monitor", "❌ 推送 {} 事件失败: {}", RESOURCE_SAMPLED_EVENT, e);
+                    }
+
+                    alerts::evaluate(&app_handle, &settings.rules, &tree);
+                }
+                Err(e) => {
+                    error!(target: "resource_monitor::monitor", "❌ 采集进程树任务异常: {}", e);
+                }
+            }
+
+            let interval = settings.sample_interval_secs.max(MIN_SAMPLE_INTERVAL_SECS);
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+        }
+    });
+}
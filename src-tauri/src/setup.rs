@@ -1,6 +1,6 @@
 use tauri::{App, Manager};
 use std::sync::Arc;
-use crate::{app_settings, system_tray, db_monitor, window};
+use crate::{app_settings, system_tray, db_monitor, quota, window};
 
 pub fn init(app: &mut App) -> std::result::Result<(), Box<dyn std::error::Error>> {
     tracing::info!(target: "app::setup", "开始应用程序设置");
@@ -9,6 +9,10 @@ pub fn init(app: &mut App) -> std::result::Result<(), Box<dyn std::error::Error>
     let app_handle = app.handle();
     app.manage(app_settings::AppSettingsManager::new(app_handle));
 
+    // 加载 Antigravity 进程识别规则
+    let process_rules = app.state::<app_settings::AppSettingsManager>().get_settings().process_rules;
+    crate::platform::matcher::set_process_rules(process_rules);
+
     // 初始化系统托盘管理器
     app.manage(system_tray::SystemTrayManager::new());
 
@@ -40,6 +44,26 @@ pub fn init(app: &mut App) -> std::result::Result<(), Box<dyn std::error::Error>
 
     tracing::info!(target: "app::setup::db_monitor", "数据库监控器初始化完成");
 
+    // 启动共享进程快照的后台刷新
+    crate::platform::snapshot::spawn_refresher();
+
+    // 语言服务器身份变化时通过应用句柄推送事件
+    crate::language_server::cache::set_app_handle(app.handle().clone());
+
+    // 启动 Antigravity.log 增量跟踪
+    crate::language_server::log_follower::spawn_log_follower(app.handle().clone());
+
+    // 启动语言服务器就绪状态监视器
+    crate::language_server::readiness::spawn_watcher(app.handle().clone());
+
+    // 启动配额后台采样（Antigravity 未运行或间隔为 0 时空转）
+    quota::spawn_sampler(app.handle().clone());
+    tracing::info!(target: "app::setup::quota", "配额采样任务已启动");
+
+    // 启动 Antigravity 资源监控（设置中关闭时空转）
+    crate::resource_monitor::spawn_monitor(app.handle().clone());
+    tracing::info!(target: "app::setup::resource_monitor", "资源监控任务已启动");
+
     // 初始化窗口事件处理器
     if let Err(e) = window::init_window_event_handler(app) {
         tracing::error!(target: "app::setup::window", error = %e, "窗口事件处理器初始化失败");
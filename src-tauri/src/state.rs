@@ -25,6 +25,9 @@ pub struct AntigravityAccount {
     pub last_switched: String,
     #[serde(default)]
     pub remark: String, // 账户备注
+    /// 最近一次获取到的用户状态摘要（套餐、积分、模型配额）及其时效
+    #[serde(default)]
+    pub status_snapshot: Option<crate::antigravity::status_snapshot::AccountStatusSummary>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
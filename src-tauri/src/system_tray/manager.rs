@@ -9,10 +9,15 @@ use crate::app_settings::AppSettingsManager;
 use super::menu::build_menu;
 use super::events::handle_menu_event;
 
+/// 托盘默认提示文字
+const DEFAULT_TOOLTIP: &str = "Antigravity Agent";
+
 /// 系统托盘管理器
 pub struct SystemTrayManager {
     /// 托盘图标实例（使用 Mutex 保护，支持内部可变性）
     tray_icon: Mutex<Option<TrayIcon>>,
+    /// 告警提示（托盘重建时恢复）
+    alert_tooltip: Mutex<Option<(String, usize)>>,
 }
 
 // 强制实现 Send 和 Sync，因为 TrayIcon 只是一个句柄，且我们使用了 Mutex 进行保护
@@ -24,6 +29,7 @@ impl SystemTrayManager {
     pub fn new() -> Self {
         Self {
             tray_icon: Mutex::new(None),
+            alert_tooltip: Mutex::new(None),
         }
     }
 
@@ -146,7 +152,7 @@ impl SystemTrayManager {
 
         let mut builder = TrayIconBuilder::new()
             .menu(&menu)
-            .tooltip("Antigravity Agent")
+            .tooltip(DEFAULT_TOOLTIP)
             .on_menu_event(|app, event| {
                 let id = event.id.as_ref().to_string();
                 let app_clone = app.clone();
@@ -200,6 +206,9 @@ impl SystemTrayManager {
         }
 
         let tray = builder.build(app_handle).map_err(|e| e.to_string())?;
+        if let Some((tooltip, count)) = self.alert_tooltip.lock().unwrap().as_ref() {
+            Self::apply_alert_tooltip(&tray, Some(tooltip), *count);
+        }
         *tray_lock = Some(tray);
 
         println!("✅ 系统托盘图标已创建");
@@ -236,6 +245,35 @@ impl SystemTrayManager {
         None
     }
     
+    /// 设置告警提示：`None` 时恢复默认提示
+    ///
+    /// 托盘未创建时只记录状态，创建托盘时再应用
+    pub fn set_alert_tooltip(&self, tooltip: Option<&str>, count: usize) -> Result<(), String> {
+        *self.alert_tooltip.lock().unwrap() = tooltip.map(|t| (t.to_string(), count));
+
+        let tray_lock = self.tray_icon.lock().unwrap();
+        if let Some(tray) = tray_lock.as_ref() {
+            Self::apply_alert_tooltip(tray, tooltip, count);
+        }
+        Ok(())
+    }
+
+    /// 更新托盘提示与标题角标（标题仅 macOS 菜单栏可见）
+    fn apply_alert_tooltip(tray: &TrayIcon, tooltip: Option<&str>, count: usize) {
+        let text = match tooltip {
+            Some(t) => format!("{}\n{}", DEFAULT_TOOLTIP, t),
+            None => DEFAULT_TOOLTIP.to_string(),
+        };
+        if let Err(e) = tray.set_tooltip(Some(text)) {
+            tracing::warn!("[SystemTray] 设置托盘提示失败: {}", e);
+        }
+
+        let title = (count > 0).then(|| format!("⚠{}", count));
+        if let Err(e) = tray.set_title(title) {
+            tracing::warn!("[SystemTray] 设置托盘标题失败: {}", e);
+        }
+    }
+
     /// 重建并更新菜单（用于账户列表更新）
     pub async fn update_menu(&self, app_handle: &AppHandle) -> Result<(), String> {
         // 1. 先构建菜单（异步操作，不持有锁）
@@ -1,5 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { ProcessInfo } from './types/process.types';
+import type { ProcessInfo, ProcessTree, ShutdownReport } from './types/process.types';
 
 /**
  * 进程管理命令
@@ -21,6 +21,23 @@ export class ProcessCommands {
     return invoke('kill_antigravity');
   }
 
+  /**
+   * 优雅关闭 Antigravity：先请求主进程退出，超时后强制终止剩余进程
+   * @param timeoutMs 等待正常退出的毫秒数（默认 5000）
+   * @returns 每个进程的结束方式
+   */
+  static async shutdown(timeoutMs?: number): Promise<ShutdownReport> {
+    return invoke('shutdown_antigravity', { timeoutMs });
+  }
+
+  /**
+   * 重启 Antigravity（优雅关闭后重新启动，用于资源告警的重启提示）
+   * @returns 启动结果消息
+   */
+  static async restart(): Promise<string> {
+    return invoke('restart_antigravity');
+  }
+
   /**
    * 检查 Antigravity 进程是否正在运行
    * @returns 是否正在运行
@@ -37,6 +54,14 @@ export class ProcessCommands {
     return invoke('list_antigravity_processes');
   }
 
+  /**
+   * 获取 Antigravity 进程树（含角色、CPU 和内存占用）
+   * @returns 以主进程为根的进程树
+   */
+  static async getProcessTree(): Promise<ProcessTree> {
+    return invoke('get_antigravity_process_tree');
+  }
+
   /**
    * 备份并重启 Antigravity（登录新账户流程）
    *
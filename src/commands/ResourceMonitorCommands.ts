@@ -0,0 +1,45 @@
+import { invoke } from '@tauri-apps/api/core';
+import type {
+  ResourceAlert,
+  ResourceMonitorSettings,
+  ResourceSample,
+} from './types/resource-monitor.types';
+
+/**
+ * 资源监控命令
+ */
+export class ResourceMonitorCommands {
+  /**
+   * 获取资源占用历史
+   * @param since 起始毫秒时间戳（不传则返回全部保留的历史）
+   * @returns 按时间升序的采样
+   */
+  static async getHistory(since?: number): Promise<ResourceSample[]> {
+    return invoke('get_resource_history', { since });
+  }
+
+  /**
+   * 获取当前仍处于触发状态的资源告警
+   * @returns 告警列表
+   */
+  static async getActiveAlerts(): Promise<ResourceAlert[]> {
+    return invoke('get_active_resource_alerts');
+  }
+
+  /**
+   * 获取资源监控设置
+   * @returns 资源监控设置
+   */
+  static async getSettings(): Promise<ResourceMonitorSettings> {
+    return invoke('get_resource_monitor_settings');
+  }
+
+  /**
+   * 保存资源监控设置（整体替换）
+   * @param settings 资源监控设置
+   * @returns 保存结果消息
+   */
+  static async saveSettings(settings: ResourceMonitorSettings): Promise<string> {
+    return invoke('save_resource_monitor_settings', { settings });
+  }
+}
@@ -1,5 +1,6 @@
 import { invoke } from '@tauri-apps/api/core';
 import type { AppSettings } from './types/settings.types';
+import type { ProcessRules } from './types/process.types';
 
 /**
  * 设置管理命令
@@ -23,6 +24,23 @@ export class SettingsCommands {
     return invoke('save_silent_start_state', { enabled });
   }
 
+  /**
+   * 获取 Antigravity 进程识别规则
+   * @returns 包含/排除规则
+   */
+  static async getProcessRules(): Promise<ProcessRules> {
+    return invoke('get_process_rules');
+  }
+
+  /**
+   * 保存 Antigravity 进程识别规则（整体替换）
+   * @param rules 包含/排除规则
+   * @returns 保存结果消息
+   */
+  static async saveProcessRules(rules: ProcessRules): Promise<string> {
+    return invoke('save_process_rules', { rules });
+  }
+
   /**
    * 获取所有应用设置
    * @returns 应用设置对象
@@ -47,4 +47,37 @@ export interface AntigravityAccount {
 
   /** 账户备注 */
   remark?: string;
+
+  /** 最近一次获取到的用户状态摘要，从未获取过时为 null */
+  status_snapshot?: AccountStatusSummary | null;
+}
+
+/**
+ * 单个模型的配额摘要
+ */
+export interface ModelStatusSummary {
+  model: string;
+  label: string;
+  /** 剩余比例（0 ~ 1），没有配额信息时为 null */
+  remaining_fraction: number | null;
+  reset_time: string | null;
+}
+
+/**
+ * 账户用户状态摘要
+ */
+export interface AccountStatusSummary {
+  /** 采集时间（毫秒时间戳） */
+  captured_at: number;
+  plan_name: string;
+  teams_tier: string;
+  /** 套餐中的布尔开关 */
+  plan_flags: Record<string, boolean>;
+  available_prompt_credits: number;
+  available_flow_credits: number;
+  monthly_prompt_credits: number;
+  monthly_flow_credits: number;
+  models: ModelStatusSummary[];
+  /** 距采集时的秒数 */
+  age_secs: number;
 }
@@ -15,9 +15,128 @@ export interface ProcessInfo {
   /** 命令行参数 */
   command: string;
 
-  /** 匹配的模式索引 */
-  matched_pattern: number;
+  /** 解析后的可执行文件路径 */
+  exe: string | null;
 
-  /** 模式描述 */
-  pattern_description: string;
+  /** 识别为 Antigravity 的原因 */
+  match_reason: ProcessMatchReason;
+}
+
+/**
+ * 进程匹配模式
+ */
+export type ProcessPattern =
+  | { kind: 'exactName'; value: string }
+  | { kind: 'contains'; value: string }
+  | { kind: 'cmdContains'; value: string }
+  | { kind: 'cmdEndsWith'; value: string }
+  | { kind: 'exeUnder'; value: string };
+
+/**
+ * 进程包含/排除规则
+ */
+export interface ProcessRules {
+  include: ProcessPattern[];
+  exclude: ProcessPattern[];
+}
+
+/**
+ * 检测到的 Antigravity 安装（安装目录或单个可执行文件）
+ */
+export type Installation =
+  | { directory: string }
+  | { executable: string };
+
+/**
+ * 识别为 Antigravity 的原因
+ */
+export type ProcessMatchReason =
+  | { kind: 'installation'; value: Installation }
+  | { kind: 'rule'; value: ProcessPattern };
+
+/**
+ * 单个进程的结束方式
+ * - graceful: 在等待期内正常退出
+ * - killed: 超时后被强制终止
+ * - alreadyExited: 开始关闭前已退出
+ * - survived: 强制终止后仍在运行
+ */
+export type ProcessExit = 'graceful' | 'killed' | 'alreadyExited' | 'survived';
+
+/**
+ * 单个进程的关闭结果
+ */
+export interface ProcessShutdown {
+  pid: number;
+  name: string;
+
+  /** 是否为主进程 */
+  isMain: boolean;
+
+  exit: ProcessExit;
+
+  /** 从开始关闭到确认退出的毫秒数 */
+  elapsedMs: number;
+}
+
+/**
+ * 关闭 Antigravity 的结果
+ */
+export interface ShutdownReport {
+  processes: ProcessShutdown[];
+  gracefulTimeoutMs: number;
+  elapsedMs: number;
+}
+
+/**
+ * 进程角色
+ */
+export type ProcessRole =
+  | 'main'
+  | 'renderer'
+  | 'gpu'
+  | 'helper'
+  | 'extensionHost'
+  | 'languageServer'
+  | 'other';
+
+/**
+ * 进程树节点
+ */
+export interface ProcessNode {
+  pid: number;
+  parentPid: number | null;
+  name: string;
+  role: ProcessRole;
+
+  /** 启动时间（Unix 秒） */
+  startTime: number;
+
+  /** CPU 占用（百分比，多核时可超过 100） */
+  cpuPercent: number;
+
+  /** 常驻内存（字节） */
+  memoryBytes: number;
+
+  exe: string | null;
+  cmdline: string[];
+
+  /** 是否被直接识别为 Antigravity（否则为其子孙进程） */
+  matched: boolean;
+
+  children: ProcessNode[];
+}
+
+/**
+ * Antigravity 进程树
+ */
+export interface ProcessTree {
+  /** 主进程 */
+  roots: ProcessNode[];
+  processCount: number;
+  totalCpuPercent: number;
+  totalMemoryBytes: number;
+
+  /** 毫秒时间戳 */
+  capturedAt: number;
 }
@@ -0,0 +1,98 @@
+/**
+ * 资源监控相关类型定义
+ */
+
+import type { ProcessRole } from './process.types';
+
+/**
+ * 单个进程角色的资源占用
+ */
+export interface RoleUsage {
+  role: ProcessRole;
+  processCount: number;
+
+  /** 该角色所有进程的 CPU 占用之和（百分比） */
+  cpuPercent: number;
+
+  /** 该角色所有进程的常驻内存之和（字节） */
+  memoryBytes: number;
+
+  /** 单个进程的最高 CPU 占用 */
+  peakCpuPercent: number;
+
+  /** 单个进程的最高常驻内存 */
+  peakMemoryBytes: number;
+}
+
+/**
+ * 一次资源采样（`resource-sampled` 事件内容）
+ */
+export interface ResourceSample {
+  /** 毫秒时间戳 */
+  timestamp: number;
+  totalCpuPercent: number;
+  totalMemoryBytes: number;
+  roles: RoleUsage[];
+}
+
+/**
+ * 告警条件
+ */
+export type ResourceCondition =
+  | { kind: 'cpuAbove'; percent: number }
+  | { kind: 'memoryAbove'; megabytes: number };
+
+/**
+ * 资源告警规则
+ */
+export interface ResourceAlertRule {
+  id: string;
+  enabled: boolean;
+
+  /** 只评估该角色的进程，null 表示全部进程 */
+  role: ProcessRole | null;
+
+  condition: ResourceCondition;
+
+  /** 连续超过阈值多少秒后触发 */
+  sustainedSecs: number;
+
+  /** 触发时是否提示重启 Antigravity */
+  restartPrompt: boolean;
+}
+
+/**
+ * 资源监控设置
+ */
+export interface ResourceMonitorSettings {
+  enabled: boolean;
+
+  /** 采样间隔（秒） */
+  sample_interval_secs: number;
+
+  /** 内存中保留的历史时长（分钟） */
+  history_minutes: number;
+
+  rules: ResourceAlertRule[];
+}
+
+/**
+ * 已触发的资源告警（`resource-alert` 事件内容）
+ */
+export interface ResourceAlert {
+  ruleId: string;
+  pid: number;
+  name: string;
+  role: ProcessRole;
+
+  /** 当前值（CPU 为百分比，内存为 MB） */
+  value: number;
+  threshold: number;
+
+  /** 开始持续超过阈值的时间（毫秒时间戳） */
+  since: number;
+
+  restartPrompt: boolean;
+  message: string;
+  timestamp: number;
+}
@@ -9,12 +9,27 @@ import {listen, UnlistenFn} from '@tauri-apps/api/event';
 import { EventEmitter } from 'events';
 import { logger } from '../utils/logger';
 
+// 单个字段的 JSON Patch（RFC 6902）操作
+export interface DatabasePatchOp {
+    op: 'add' | 'remove' | 'replace';
+    path: string;
+    value?: any;
+}
+
+// 单个字段的变化（敏感字段已在后端脱敏）
+export interface DatabaseKeyChange {
+    key: string;
+    changeType: 'added' | 'removed' | 'changed';
+    size: number;
+    patch: DatabasePatchOp[];
+    truncated?: boolean;
+}
+
 // 数据库变化事件数据接口
 export interface DatabaseChangeEvent {
     timestamp: number;
-    old_data?: any;
-    new_data?: any;
-    diff?: any;
+    changes: DatabaseKeyChange[];
+    truncated: boolean;
     originalEvent?: any;
 }
 
@@ -73,24 +88,20 @@ export const useDbMonitoringStore = create<DbMonitoringActions>()(
               eventId: event.id || 'unknown'
             });
 
-            // 解析事件数据：newData, oldData, diff
-            const { newData, oldData, diff } = event.payload;
+            // 解析事件数据：按字段的 patch 列表
+            const { timestamp, changes = [], truncated = false } = event.payload;
 
-            if (diff) {
-              logger.info('数据库变化摘要', {
-                module: 'DbMonitoringStore',
-                hasChanges: diff.hasChanges,
-                changedFieldsCount: diff.changedFields?.length || 0,
-                summary: diff.summary
-              });
-            }
+            logger.info('数据库变化摘要', {
+              module: 'DbMonitoringStore',
+              changedFieldsCount: changes.length,
+              truncated
+            });
 
             // 发射内部数据库变化事件
             databaseEventEmitter.emit(DATABASE_EVENTS.DATA_CHANGED, {
-              timestamp: Date.now(),
-              newData,
-              oldData,
-              diff,
+              timestamp: timestamp ?? Date.now(),
+              changes,
+              truncated,
               originalEvent: event
             });
 